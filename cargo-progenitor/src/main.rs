@@ -176,6 +176,17 @@ fn main() -> Result<()> {
                 )
                 .chars(),
             );
+            if args.include_client {
+                // The embedded support code gates form encoding and its
+                // streaming types on these features.
+                tomlout.extend(
+                    "[features]\n\
+                    default = [\"form\", \"stream\"]\n\
+                    form = []\n\
+                    stream = []\n"
+                        .chars(),
+                );
+            }
 
             save(&toml, tomlout.as_str())?;
 
@@ -7,21 +7,32 @@
 use std::ops::{Deref, DerefMut};
 
 use bytes::Bytes;
+#[cfg(feature = "stream")]
 use futures_core::Stream;
 use reqwest::RequestBuilder;
 use serde::{de::DeserializeOwned, Serialize};
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "stream", not(target_arch = "wasm32")))]
 type InnerByteStream =
     std::pin::Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send + Sync>>;
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(feature = "stream", target_arch = "wasm32"))]
 type InnerByteStream =
     std::pin::Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>>>>;
 
 /// Untyped byte stream used for both success and error responses.
+///
+/// It implements [`Stream`] itself, so it can be passed wherever a stream is
+/// expected without [`ByteStream::into_inner`].
+#[cfg(feature = "stream")]
 pub struct ByteStream(InnerByteStream);
 
+/// Placeholder for the byte stream type, which can't be constructed when the
+/// `stream` feature is disabled.
+#[cfg(not(feature = "stream"))]
+pub enum ByteStream {}
+
+#[cfg(feature = "stream")]
 impl ByteStream {
     /// Creates a new ByteStream
     ///
@@ -34,244 +45,4308 @@ impl ByteStream {
     pub fn into_inner(self) -> InnerByteStream {
         self.0
     }
-}
-
-impl Deref for ByteStream {
-    type Target = InnerByteStream;
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    /// Consumes the [`ByteStream`], discarding the data and returning the
+    /// total number of bytes.
+    ///
+    /// This drains the body completely, which allows the underlying
+    /// connection to be reused. The count may be compared with
+    /// [`ResponseValue::content_length`] to verify that a download completed.
+    pub async fn count_bytes(mut self) -> reqwest::Result<u64> {
+        let mut total = 0;
+        while let Some(chunk) = self.next_chunk().await {
+            total += chunk?.len() as u64;
+        }
+        Ok(total)
     }
-}
 
-impl DerefMut for ByteStream {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+    /// Collects the stream into a single buffer, calling `f` with the number
+    /// of bytes received so far and `total` after each chunk, e.g. to render
+    /// a progress bar.
+    ///
+    /// `total` is typically the [`ResponseValue::content_length`] of the
+    /// response, and is passed through unchanged. `f` is called a final time
+    /// once the stream ends successfully, so that completion can be reported
+    /// even for an empty body.
+    pub async fn collect_with_progress<F>(
+        mut self,
+        total: Option<u64>,
+        mut f: F,
+    ) -> reqwest::Result<Bytes>
+    where
+        F: FnMut(u64, Option<u64>),
+    {
+        let mut buf = bytes::BytesMut::new();
+        while let Some(chunk) = self.next_chunk().await {
+            buf.extend_from_slice(&chunk?);
+            f(buf.len() as u64, total);
+        }
+        f(buf.len() as u64, total);
+        Ok(buf.freeze())
     }
-}
-
-/// Typed value returned by generated client methods.
-///
-/// This is used for successful responses and may appear in error responses
-/// generated from the server (see [`Error::ErrorResponse`])
-pub struct ResponseValue<T> {
-    inner: T,
-    status: reqwest::StatusCode,
-    headers: reqwest::header::HeaderMap,
-    // TODO cookies?
-}
 
-impl<T: DeserializeOwned> ResponseValue<T> {
-    #[doc(hidden)]
-    pub async fn from_response<E: std::fmt::Debug>(
-        response: reqwest::Response,
-    ) -> Result<Self, Error<E>> {
-        let status = response.status();
-        let headers = response.headers().clone();
-        let full = response.bytes().await.map_err(Error::ResponseBodyError)?;
-        let inner = serde_json::from_slice(&full)
-            .map_err(|e| Error::InvalidResponsePayload(full, e))?;
+    /// Transforms each chunk of the stream, e.g. to decrypt or transcode a
+    /// download as it arrives.
+    ///
+    /// Chunk boundaries are those of the underlying stream, so `f` must not
+    /// assume any particular chunk size. Errors from the underlying stream are
+    /// passed through unchanged.
+    pub fn map_chunks<F>(self, f: F) -> ByteStream
+    where
+        F: FnMut(Bytes) -> Bytes + Send + Sync + 'static,
+    {
+        ByteStream(Box::pin(MapChunks { inner: self.0, f }))
+    }
 
-        Ok(Self {
-            inner,
-            status,
-            headers,
+    /// Calls `f` with each chunk of the stream without changing it, e.g. to
+    /// compute a running checksum or report progress while a download is
+    /// written to disk.
+    pub fn inspect_chunks<F>(self, mut f: F) -> ByteStream
+    where
+        F: FnMut(&Bytes) + Send + Sync + 'static,
+    {
+        self.map_chunks(move |chunk| {
+            f(&chunk);
+            chunk
         })
     }
-}
-
-#[cfg(not(target_arch = "wasm32"))]
-impl ResponseValue<reqwest::Upgraded> {
-    #[doc(hidden)]
-    pub async fn upgrade<E: std::fmt::Debug>(
-        response: reqwest::Response,
-    ) -> Result<Self, Error<E>> {
-        let status = response.status();
-        let headers = response.headers().clone();
-        if status == reqwest::StatusCode::SWITCHING_PROTOCOLS {
-            let inner =
-                response.upgrade().await.map_err(Error::InvalidUpgrade)?;
 
-            Ok(Self {
-                inner,
-                status,
-                headers,
-            })
-        } else {
-            Err(Error::UnexpectedResponse(response))
+    /// Calls `f` with the total number of bytes received so far after each
+    /// chunk of the stream, e.g. to render a progress bar against the
+    /// [`ResponseValue::content_length`] of the response.
+    ///
+    /// `f` isn't called for errors from the underlying stream, which are
+    /// passed through unchanged.
+    pub fn inspect_progress<F>(
+        self,
+        f: F,
+    ) -> impl Stream<Item = reqwest::Result<Bytes>>
+    where
+        F: FnMut(u64),
+    {
+        InspectProgress {
+            inner: self.0,
+            received: 0,
+            f,
         }
     }
-}
 
-impl ResponseValue<ByteStream> {
-    #[doc(hidden)]
-    pub fn stream(response: reqwest::Response) -> Self {
-        let status = response.status();
-        let headers = response.headers().clone();
-        Self {
-            inner: ByteStream(Box::pin(response.bytes_stream())),
-            status,
-            headers,
+    /// Arranges for `f` to be called once the stream ends, either
+    /// successfully or with the first error.
+    ///
+    /// This is useful for cleanup or for reporting that a download finished.
+    /// If the stream is dropped before it ends, `f` is not called.
+    pub fn on_complete<F>(self, f: F) -> ByteStream
+    where
+        F: FnOnce(Result<(), &reqwest::Error>) + Send + Sync + 'static,
+    {
+        ByteStream(Box::pin(OnComplete {
+            inner: self.0,
+            f: Some(f),
+        }))
+    }
+
+    /// Transforms each chunk of the stream with a fallible function.
+    ///
+    /// The resulting stream ends after the first error, whether from `f` or
+    /// from the underlying stream.
+    pub fn try_map_chunks<F, E>(
+        self,
+        f: F,
+    ) -> impl Stream<Item = Result<Bytes, ChunkError<E>>>
+    where
+        F: FnMut(Bytes) -> Result<Bytes, E>,
+    {
+        TryMapChunks {
+            inner: Some(self.0),
+            f,
         }
     }
-}
 
-impl ResponseValue<()> {
-    #[doc(hidden)]
-    pub fn empty(response: reqwest::Response) -> Self {
-        let status = response.status();
-        let headers = response.headers().clone();
-        // TODO is there anything we want to do to confirm that there is no
-        // content?
-        Self {
-            inner: (),
-            status,
-            headers,
+    /// Passes the stream through unchanged while computing its digest, e.g.
+    /// to check a download against its `Digest` or `Content-MD5` header
+    /// without buffering it.
+    ///
+    /// `expected` is the raw digest rather than an encoding of it. Once the
+    /// underlying stream ends, a [`DigestMismatch`] error is yielded if the
+    /// digest differs. The resulting stream ends after the first error.
+    #[cfg(feature = "digest")]
+    pub fn verify_digest(
+        self,
+        algo: DigestAlgo,
+        expected: &[u8],
+    ) -> impl Stream<Item = Result<Bytes, ChunkError<DigestMismatch>>> {
+        VerifyDigest {
+            inner: Some(self.0),
+            hasher: DigestHasher::new(algo),
+            expected: expected.to_vec(),
         }
     }
-}
 
-impl<T> ResponseValue<T> {
-    /// Creates a [`ResponseValue`] from the inner type, status, and headers.
+    /// Collects the stream into a single buffer, giving up once `duration`
+    /// has elapsed.
     ///
-    /// Useful for generating test fixtures.
-    pub fn new(
-        inner: T,
-        status: reqwest::StatusCode,
-        headers: reqwest::header::HeaderMap,
-    ) -> Self {
-        Self {
-            inner,
-            status,
-            headers,
+    /// The deadline applies to the whole body rather than to each chunk. If
+    /// the deadline passes or the stream fails, the data received up to that
+    /// point is returned along with the error so that, for example, the
+    /// remainder can be requested with a `Range` header.
+    #[cfg(feature = "tokio")]
+    pub async fn collect_with_timeout(
+        mut self,
+        duration: std::time::Duration,
+    ) -> Result<Bytes, (Bytes, CollectError)> {
+        let deadline = tokio::time::Instant::now() + duration;
+        let mut buf = bytes::BytesMut::new();
+        loop {
+            match tokio::time::timeout_at(deadline, self.next_chunk()).await {
+                Ok(Some(Ok(chunk))) => buf.extend_from_slice(&chunk),
+                Ok(Some(Err(e))) => {
+                    return Err((buf.freeze(), CollectError::Stream(e)))
+                }
+                Ok(None) => return Ok(buf.freeze()),
+                Err(_) => return Err((buf.freeze(), CollectError::TimedOut)),
+            }
         }
     }
 
-    /// Consumes the ResponseValue, returning the wrapped value.
-    pub fn into_inner(self) -> T {
-        self.inner
+    /// Collects the stream into a single buffer, failing with
+    /// [`CollectError::LimitExceeded`] as soon as more than `max` bytes have
+    /// been received, so that a misbehaving server can't exhaust memory.
+    pub async fn collect_with_limit(
+        self,
+        max: usize,
+    ) -> Result<Bytes, CollectError> {
+        self.collect_limited(max, 0).await
     }
 
-    /// Gets the status from this response.
-    pub fn status(&self) -> reqwest::StatusCode {
-        self.status
+    async fn collect_limited(
+        mut self,
+        max: usize,
+        reserve: usize,
+    ) -> Result<Bytes, CollectError> {
+        let mut buf = bytes::BytesMut::with_capacity(reserve.min(max));
+        while let Some(chunk) = self.next_chunk().await {
+            let chunk = chunk.map_err(CollectError::Stream)?;
+            let read = buf.len() + chunk.len();
+            if read > max {
+                return Err(CollectError::LimitExceeded { limit: max, read });
+            }
+            buf.extend_from_slice(&chunk);
+        }
+        Ok(buf.freeze())
     }
 
-    /// Gets the headers from this response.
-    pub fn headers(&self) -> &reqwest::header::HeaderMap {
-        &self.headers
+    /// Sends each chunk of the stream, converted by `chunk_fn`, to a channel.
+    ///
+    /// The stream is read only as quickly as the channel accepts items, so a
+    /// slow consumer applies backpressure to the download rather than causing
+    /// data to be buffered. This returns early, successfully, if the receiver
+    /// is dropped.
+    #[cfg(feature = "tokio")]
+    pub async fn pipe_to<T, F>(
+        mut self,
+        tx: tokio::sync::mpsc::Sender<T>,
+        mut chunk_fn: F,
+    ) -> reqwest::Result<()>
+    where
+        F: FnMut(Bytes) -> T,
+    {
+        while let Some(chunk) = self.next_chunk().await {
+            if tx.send(chunk_fn(chunk?)).await.is_err() {
+                break;
+            }
+        }
+        Ok(())
     }
 
-    /// Gets the parsed value of the Content-Length header, if present and
-    /// valid.
-    pub fn content_length(&self) -> Option<u64> {
-        self.headers
-            .get(reqwest::header::CONTENT_LENGTH)?
-            .to_str()
-            .ok()?
-            .parse::<u64>()
-            .ok()
+    /// Parses the stream as a JSON array, producing each element as soon as
+    /// it has been received in full.
+    ///
+    /// Only one element is buffered at a time, so arbitrarily long arrays can
+    /// be processed in bounded memory. The stream ends after the first error.
+    pub fn json_array_stream<T, E>(self) -> JsonArrayStream<T, E>
+    where
+        T: DeserializeOwned,
+    {
+        JsonArrayStream {
+            inner: self.0,
+            buf: Vec::new(),
+            pos: 0,
+            item_start: 0,
+            depth: 0,
+            in_string: false,
+            escape: false,
+            count: 0,
+            phase: JsonArrayPhase::Start,
+            _item: std::marker::PhantomData,
+        }
     }
 
-    #[doc(hidden)]
-    pub fn map<U: std::fmt::Debug, F, E>(
-        self,
-        f: F,
-    ) -> Result<ResponseValue<U>, E>
+    /// Parses the stream as newline-delimited JSON (NDJSON), producing the
+    /// value of each line as soon as it has been received in full.
+    ///
+    /// Blank lines are ignored. By default the stream ends after the first
+    /// line that can't be parsed; see [`JsonLinesStream::skip_malformed`].
+    pub fn json_lines<T, E>(self) -> JsonLinesStream<T, E>
     where
-        F: FnOnce(T) -> U,
+        T: DeserializeOwned,
     {
-        let Self {
-            inner,
-            status,
-            headers,
-        } = self;
+        self.json_lines_at("")
+    }
 
-        Ok(ResponseValue {
-            inner: f(inner),
-            status,
-            headers,
-        })
+    /// Like [`ByteStream::json_lines`], but produces the value found at the
+    /// JSON pointer `pointer` (RFC 6901) in each line, e.g. `/result` to
+    /// unwrap records delivered as `{"result": {...}}`, so that the envelope
+    /// needn't be described by a type of its own.
+    ///
+    /// A line without a value at `pointer` is malformed.
+    pub fn json_lines_at<T, E>(self, pointer: &str) -> JsonLinesStream<T, E>
+    where
+        T: DeserializeOwned,
+    {
+        JsonLinesStream {
+            inner: Some(self.0),
+            buf: Vec::new(),
+            pointer: pointer.to_string(),
+            skip_malformed: false,
+            body_error: Error::ResponseBodyError,
+            _item: std::marker::PhantomData,
+        }
     }
-}
 
-impl ResponseValue<ByteStream> {
-    /// Consumes the `ResponseValue`, returning the wrapped [`Stream`].
-    pub fn into_inner_stream(self) -> InnerByteStream {
-        self.into_inner().into_inner()
+    /// Splits the stream into frames with `decoder`, for length-prefixed or
+    /// delimiter-framed protocols, producing each frame as soon as it has
+    /// been received in full.
+    ///
+    /// Any [`tokio_util::codec::Decoder`] may be used, such as `LinesCodec` or
+    /// `LengthDelimitedCodec`. Partial frames are buffered across chunks, and
+    /// once the body ends the decoder's `decode_eof` is given what remains.
+    /// Errors reading the body are converted to the decoder's error type by
+    /// way of [`std::io::Error`]. The stream ends after the first error.
+    #[cfg(feature = "tokio")]
+    pub fn framed<D>(self, decoder: D) -> FramedStream<D>
+    where
+        D: tokio_util::codec::Decoder,
+    {
+        FramedStream {
+            inner: Some(self.0),
+            buf: bytes::BytesMut::new(),
+            decoder,
+            done: false,
+        }
     }
-}
 
-impl<T> Deref for ResponseValue<T> {
-    type Target = T;
+    /// Adapts the stream into a [`tokio::io::AsyncRead`], e.g. to copy a
+    /// download straight to a file with `tokio::io::copy`.
+    ///
+    /// Errors reading the body are reported as [`std::io::Error`]s whose
+    /// inner error is the `reqwest::Error`.
+    #[cfg(feature = "tokio")]
+    pub fn into_async_read(self) -> impl tokio::io::AsyncRead {
+        tokio_util::io::StreamReader::new(IoStream(self.0))
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.inner
+    async fn next_chunk(&mut self) -> Option<reqwest::Result<Bytes>> {
+        std::future::poll_fn(|cx| self.0.as_mut().poll_next(cx)).await
     }
 }
 
-impl<T> DerefMut for ResponseValue<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.inner
+/// Error produced while collecting a [`ByteStream`].
+#[derive(Debug)]
+pub enum CollectError {
+    /// The underlying stream failed.
+    Stream(reqwest::Error),
+    /// The deadline for collecting the stream elapsed.
+    TimedOut,
+    /// The stream was longer than the limit.
+    LimitExceeded {
+        /// The maximum number of bytes to be collected.
+        limit: usize,
+        /// The number of bytes received, including the chunk that exceeded
+        /// the limit.
+        read: usize,
+    },
+}
+
+impl std::fmt::Display for CollectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CollectError::Stream(e) => write!(f, "Stream Error: {}", e),
+            CollectError::TimedOut => write!(f, "Timed Out"),
+            CollectError::LimitExceeded { limit, read } => write!(
+                f,
+                "Limit Exceeded: read {} bytes, more than the limit of {}",
+                read, limit
+            ),
+        }
     }
 }
 
-impl<T> AsRef<T> for ResponseValue<T> {
-    fn as_ref(&self) -> &T {
-        &self.inner
+impl std::error::Error for CollectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CollectError::Stream(e) => Some(e),
+            CollectError::TimedOut => None,
+            CollectError::LimitExceeded { .. } => None,
+        }
     }
 }
 
-impl<T: std::fmt::Debug> std::fmt::Debug for ResponseValue<T> {
+/// Error produced by the stream returned by [`ByteStream::try_map_chunks`].
+#[cfg(feature = "stream")]
+#[derive(Debug)]
+pub enum ChunkError<E> {
+    /// The underlying stream failed.
+    Stream(reqwest::Error),
+    /// The transformation failed.
+    Map(E),
+}
+
+#[cfg(feature = "stream")]
+impl<E: std::fmt::Display> std::fmt::Display for ChunkError<E> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.inner.fmt(f)
+        match self {
+            ChunkError::Stream(e) => write!(f, "Stream Error: {}", e),
+            ChunkError::Map(e) => write!(f, "Map Error: {}", e),
+        }
     }
 }
 
-/// Error produced by generated client methods.
-///
-/// The type parameter may be a struct if there's a single expected error type
-/// or an enum if there are multiple valid error types. It can be the unit type
-/// if there are no structured returns expected.
-pub enum Error<E = ()> {
-    /// The request did not conform to API requirements.
-    InvalidRequest(String),
+#[cfg(feature = "stream")]
+impl<E: std::error::Error + 'static> std::error::Error for ChunkError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ChunkError::Stream(e) => Some(e),
+            ChunkError::Map(e) => Some(e),
+        }
+    }
+}
 
-    /// A server error either due to the data, or with the connection.
-    CommunicationError(reqwest::Error),
+#[cfg(feature = "stream")]
+struct HoldPermit {
+    inner: InnerByteStream,
+    permit: Option<RequestPermit>,
+}
 
-    /// An expected response when upgrading connection.
-    InvalidUpgrade(reqwest::Error),
+#[cfg(feature = "stream")]
+impl Stream for HoldPermit {
+    type Item = reqwest::Result<Bytes>;
 
-    /// A documented, expected error response.
-    ErrorResponse(ResponseValue<E>),
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let item = std::task::ready!(this.inner.as_mut().poll_next(cx));
+        if !matches!(item, Some(Ok(_))) {
+            this.permit = None;
+        }
+        std::task::Poll::Ready(item)
+    }
+}
 
-    /// Encountered an error reading the body for an expected response.
-    ResponseBodyError(reqwest::Error),
+#[cfg(feature = "stream")]
+struct MapChunks<F> {
+    inner: InnerByteStream,
+    f: F,
+}
 
-    /// An expected response code whose deserialization failed.
-    InvalidResponsePayload(Bytes, serde_json::Error),
+#[cfg(feature = "stream")]
+// The function is never pinned.
+impl<F> Unpin for MapChunks<F> {}
 
-    /// A response not listed in the API description. This may represent a
-    /// success or failure response; check `status().is_success()`.
-    UnexpectedResponse(reqwest::Response),
+#[cfg(feature = "stream")]
+impl<F: FnMut(Bytes) -> Bytes> Stream for MapChunks<F> {
+    type Item = reqwest::Result<Bytes>;
 
-    /// An error occurred in the processing of a request pre-hook.
-    PreHookError(String),
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        this.inner
+            .as_mut()
+            .poll_next(cx)
+            .map(|item| item.map(|chunk| chunk.map(&mut this.f)))
+    }
 }
 
-impl<E> Error<E> {
-    /// Returns the status code, if the error was generated from a response.
-    pub fn status(&self) -> Option<reqwest::StatusCode> {
-        match self {
-            Error::InvalidRequest(_) => None,
-            Error::PreHookError(_) => None,
-            Error::CommunicationError(e) => e.status(),
-            Error::ErrorResponse(rv) => Some(rv.status()),
-            Error::InvalidUpgrade(e) => e.status(),
-            Error::ResponseBodyError(e) => e.status(),
-            Error::InvalidResponsePayload(_, _) => None,
+/// Stream of the elements of a JSON array response body; see
+/// [`ByteStream::json_array_stream`].
+#[cfg(feature = "stream")]
+pub struct JsonArrayStream<T, E> {
+    inner: InnerByteStream,
+    buf: Vec<u8>,
+    pos: usize,
+    item_start: usize,
+    depth: usize,
+    in_string: bool,
+    escape: bool,
+    count: usize,
+    phase: JsonArrayPhase,
+    _item: std::marker::PhantomData<fn() -> (T, E)>,
+}
+
+#[cfg(feature = "stream")]
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum JsonArrayPhase {
+    Start,
+    BeforeItem,
+    InItem,
+    Done,
+}
+
+#[cfg(feature = "stream")]
+impl<T: DeserializeOwned, E> JsonArrayStream<T, E> {
+    fn fail(&mut self, msg: &str) -> Error<E> {
+        self.phase = JsonArrayPhase::Done;
+        Error::InvalidResponsePayload(
+            Bytes::copy_from_slice(&self.buf),
+            <serde_json::Error as serde::de::Error>::custom(msg),
+        )
+    }
+
+    /// Scans the buffered data for the next complete element, returning
+    /// `None` if more data is needed or the array has ended.
+    fn parse_next(&mut self) -> Option<Result<T, Error<E>>> {
+        while self.pos < self.buf.len() {
+            let b = self.buf[self.pos];
+            match self.phase {
+                JsonArrayPhase::Start => {
+                    if b == b'[' {
+                        self.phase = JsonArrayPhase::BeforeItem;
+                    } else if !b.is_ascii_whitespace() {
+                        return Some(Err(self.fail("expected a JSON array")));
+                    }
+                }
+                JsonArrayPhase::BeforeItem => {
+                    if b == b']' && self.count == 0 {
+                        self.phase = JsonArrayPhase::Done;
+                        return None;
+                    }
+                    if !b.is_ascii_whitespace() {
+                        // Process this byte again as the start of the item.
+                        self.phase = JsonArrayPhase::InItem;
+                        self.item_start = self.pos;
+                        continue;
+                    }
+                }
+                JsonArrayPhase::InItem if self.in_string => {
+                    if self.escape {
+                        self.escape = false;
+                    } else if b == b'\\' {
+                        self.escape = true;
+                    } else if b == b'"' {
+                        self.in_string = false;
+                    }
+                }
+                JsonArrayPhase::InItem => match b {
+                    b'"' => self.in_string = true,
+                    b'{' | b'[' => self.depth += 1,
+                    b'}' | b']' if self.depth > 0 => self.depth -= 1,
+                    b',' | b']' => {
+                        let item = &self.buf[self.item_start..self.pos];
+                        let result =
+                            serde_json::from_slice(item).map_err(|e| {
+                                Error::InvalidResponsePayload(
+                                    Bytes::copy_from_slice(item),
+                                    e,
+                                )
+                            });
+                        self.phase = if b == b',' && result.is_ok() {
+                            JsonArrayPhase::BeforeItem
+                        } else {
+                            JsonArrayPhase::Done
+                        };
+                        self.count += 1;
+                        self.buf.drain(..=self.pos);
+                        self.pos = 0;
+                        return Some(result);
+                    }
+                    _ => {}
+                },
+                JsonArrayPhase::Done => return None,
+            }
+            self.pos += 1;
+        }
+        None
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T: DeserializeOwned, E> Stream for JsonArrayStream<T, E> {
+    type Item = Result<T, Error<E>>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(item) = this.parse_next() {
+                return std::task::Poll::Ready(Some(item));
+            }
+            if this.phase == JsonArrayPhase::Done {
+                return std::task::Poll::Ready(None);
+            }
+            match std::task::ready!(this.inner.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => this.buf.extend_from_slice(&chunk),
+                Some(Err(e)) => {
+                    this.phase = JsonArrayPhase::Done;
+                    return std::task::Poll::Ready(Some(Err(
+                        Error::ResponseBodyError(e),
+                    )));
+                }
+                None => {
+                    let e = this.fail("unexpected end of JSON array");
+                    return std::task::Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T, E> std::fmt::Debug for JsonArrayStream<T, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("JsonArrayStream")
+    }
+}
+
+/// Stream of the values of a newline-delimited JSON response body; see
+/// [`ByteStream::json_lines`] and [`ByteStream::json_lines_at`].
+#[cfg(feature = "stream")]
+pub struct JsonLinesStream<T, E> {
+    inner: Option<InnerByteStream>,
+    buf: Vec<u8>,
+    pointer: String,
+    skip_malformed: bool,
+    body_error: fn(reqwest::Error) -> Error<E>,
+    _item: std::marker::PhantomData<fn() -> (T, E)>,
+}
+
+#[cfg(feature = "stream")]
+impl<T: DeserializeOwned, E> JsonLinesStream<T, E> {
+    /// Skips lines that can't be parsed, rather than producing an error and
+    /// ending the stream. Errors reading the body still end the stream.
+    pub fn skip_malformed(mut self, skip: bool) -> Self {
+        self.skip_malformed = skip;
+        self
+    }
+
+    /// Parses a line, returning `None` if it's blank.
+    fn parse_line(&self, line: &[u8]) -> Option<Result<T, Error<E>>> {
+        let start = line.iter().position(|b| !b.is_ascii_whitespace())?;
+        let end = line.iter().rposition(|b| !b.is_ascii_whitespace())?;
+        let line = &line[start..=end];
+        let result = if self.pointer.is_empty() {
+            serde_json::from_slice(line)
+        } else {
+            serde_json::from_slice(line).and_then(
+                |mut value: serde_json::Value| {
+                    let value =
+                        value.pointer_mut(&self.pointer).ok_or_else(|| {
+                            <serde_json::Error as serde::de::Error>::custom(
+                                format!("no value at {}", self.pointer),
+                            )
+                        })?;
+                    serde_json::from_value(value.take())
+                },
+            )
+        };
+        Some(result.map_err(|e| {
+            Error::InvalidResponsePayload(Bytes::copy_from_slice(line), e)
+        }))
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T: DeserializeOwned, E> Stream for JsonLinesStream<T, E> {
+    type Item = Result<T, Error<E>>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            while let Some(end) = this.buf.iter().position(|b| *b == b'\n') {
+                let line = this.buf.drain(..=end).collect::<Vec<_>>();
+                match this.parse_line(&line) {
+                    None => {}
+                    Some(Err(_)) if this.skip_malformed => {}
+                    Some(result) => {
+                        if result.is_err() {
+                            this.inner = None;
+                            this.buf.clear();
+                        }
+                        return std::task::Poll::Ready(Some(result));
+                    }
+                }
+            }
+
+            let Some(inner) = this.inner.as_mut() else {
+                return std::task::Poll::Ready(None);
+            };
+            match std::task::ready!(inner.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => this.buf.extend_from_slice(&chunk),
+                Some(Err(e)) => {
+                    this.inner = None;
+                    this.buf.clear();
+                    let error = (this.body_error)(e);
+                    return std::task::Poll::Ready(Some(Err(error)));
+                }
+                None => {
+                    // The last line needn't be terminated.
+                    this.inner = None;
+                    this.buf.push(b'\n');
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T, E> std::fmt::Debug for JsonLinesStream<T, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("JsonLinesStream")
+    }
+}
+
+/// Adapts the errors of a body stream for [`ByteStream::into_async_read`].
+#[cfg(feature = "tokio")]
+struct IoStream(InnerByteStream);
+
+#[cfg(feature = "tokio")]
+impl Stream for IoStream {
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.0.as_mut().poll_next(cx).map(|item| {
+            item.map(|result| {
+                result.map_err(|e| {
+                    let kind = if e.is_timeout() {
+                        std::io::ErrorKind::TimedOut
+                    } else {
+                        std::io::ErrorKind::Other
+                    };
+                    std::io::Error::new(kind, e)
+                })
+            })
+        })
+    }
+}
+
+/// Stream of the frames of a response body; see [`ByteStream::framed`].
+#[cfg(feature = "tokio")]
+pub struct FramedStream<D> {
+    inner: Option<InnerByteStream>,
+    buf: bytes::BytesMut,
+    decoder: D,
+    done: bool,
+}
+
+#[cfg(feature = "tokio")]
+impl<D> Stream for FramedStream<D>
+where
+    D: tokio_util::codec::Decoder + Unpin,
+{
+    type Item = Result<D::Item, D::Error>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if this.done {
+                return std::task::Poll::Ready(None);
+            }
+
+            let Some(inner) = this.inner.as_mut() else {
+                // The body has ended; the decoder handles any remainder.
+                let result = this.decoder.decode_eof(&mut this.buf);
+                if !matches!(result, Ok(Some(_))) {
+                    this.done = true;
+                }
+                return std::task::Poll::Ready(result.transpose());
+            };
+
+            match this.decoder.decode(&mut this.buf) {
+                Ok(Some(frame)) => {
+                    return std::task::Poll::Ready(Some(Ok(frame)))
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    this.done = true;
+                    return std::task::Poll::Ready(Some(Err(e)));
+                }
+            }
+
+            match std::task::ready!(inner.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => this.buf.extend_from_slice(&chunk),
+                Some(Err(e)) => {
+                    this.done = true;
+                    let e = std::io::Error::new(std::io::ErrorKind::Other, e);
+                    return std::task::Poll::Ready(Some(Err(e.into())));
+                }
+                None => this.inner = None,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<D> std::fmt::Debug for FramedStream<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FramedStream")
+    }
+}
+
+#[cfg(feature = "stream")]
+struct OnComplete<F> {
+    inner: InnerByteStream,
+    f: Option<F>,
+}
+
+// The function is never pinned.
+#[cfg(feature = "stream")]
+impl<F> Unpin for OnComplete<F> {}
+
+#[cfg(feature = "stream")]
+impl<F> Stream for OnComplete<F>
+where
+    F: FnOnce(Result<(), &reqwest::Error>),
+{
+    type Item = reqwest::Result<Bytes>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let item = std::task::ready!(this.inner.as_mut().poll_next(cx));
+        let outcome = match &item {
+            Some(Ok(_)) => None,
+            Some(Err(e)) => Some(Err(e)),
+            None => Some(Ok(())),
+        };
+        if let Some(outcome) = outcome {
+            if let Some(f) = this.f.take() {
+                f(outcome);
+            }
+        }
+        std::task::Poll::Ready(item)
+    }
+}
+
+#[cfg(feature = "stream")]
+struct InspectProgress<F> {
+    inner: InnerByteStream,
+    received: u64,
+    f: F,
+}
+
+#[cfg(feature = "stream")]
+// The function is never pinned.
+impl<F> Unpin for InspectProgress<F> {}
+
+#[cfg(feature = "stream")]
+impl<F: FnMut(u64)> Stream for InspectProgress<F> {
+    type Item = reqwest::Result<Bytes>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let item = std::task::ready!(this.inner.as_mut().poll_next(cx));
+        if let Some(Ok(chunk)) = &item {
+            this.received += chunk.len() as u64;
+            (this.f)(this.received);
+        }
+        std::task::Poll::Ready(item)
+    }
+}
+
+#[cfg(feature = "stream")]
+struct TryMapChunks<F> {
+    inner: Option<InnerByteStream>,
+    f: F,
+}
+
+#[cfg(feature = "stream")]
+// The function is never pinned.
+impl<F> Unpin for TryMapChunks<F> {}
+
+#[cfg(feature = "stream")]
+impl<F, E> Stream for TryMapChunks<F>
+where
+    F: FnMut(Bytes) -> Result<Bytes, E>,
+{
+    type Item = Result<Bytes, ChunkError<E>>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let Some(inner) = this.inner.as_mut() else {
+            return std::task::Poll::Ready(None);
+        };
+        let item = match std::task::ready!(inner.as_mut().poll_next(cx)) {
+            None => None,
+            Some(Ok(chunk)) => Some((this.f)(chunk).map_err(ChunkError::Map)),
+            Some(Err(e)) => Some(Err(ChunkError::Stream(e))),
+        };
+        if !matches!(item, Some(Ok(_))) {
+            this.inner = None;
+        }
+        std::task::Poll::Ready(item)
+    }
+}
+
+/// A digest algorithm for [`ByteStream::verify_digest`].
+#[cfg(feature = "digest")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgo {
+    /// MD5, as in the `Content-MD5` header.
+    Md5,
+    /// SHA-256, as in the `sha-256` digest of the `Digest` header.
+    Sha256,
+}
+
+/// Error produced by the stream returned by [`ByteStream::verify_digest`]
+/// when the digest of the body isn't the one expected.
+#[cfg(feature = "digest")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestMismatch {
+    /// The algorithm of the digests.
+    pub algo: DigestAlgo,
+    /// The digest that was expected.
+    pub expected: Vec<u8>,
+    /// The digest of the body.
+    pub actual: Vec<u8>,
+}
+
+#[cfg(feature = "digest")]
+impl std::fmt::Display for DigestMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hex = |digest: &[u8]| {
+            digest
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>()
+        };
+        write!(
+            f,
+            "{:?} digest mismatch: expected {}, got {}",
+            self.algo,
+            hex(&self.expected),
+            hex(&self.actual),
+        )
+    }
+}
+
+#[cfg(feature = "digest")]
+impl std::error::Error for DigestMismatch {}
+
+#[cfg(feature = "digest")]
+enum DigestHasher {
+    Md5(md5::Md5),
+    Sha256(sha2::Sha256),
+}
+
+#[cfg(feature = "digest")]
+impl DigestHasher {
+    fn new(algo: DigestAlgo) -> Self {
+        match algo {
+            DigestAlgo::Md5 => DigestHasher::Md5(md5::Digest::new()),
+            DigestAlgo::Sha256 => DigestHasher::Sha256(sha2::Digest::new()),
+        }
+    }
+
+    fn algo(&self) -> DigestAlgo {
+        match self {
+            DigestHasher::Md5(_) => DigestAlgo::Md5,
+            DigestHasher::Sha256(_) => DigestAlgo::Sha256,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            DigestHasher::Md5(h) => md5::Digest::update(h, data),
+            DigestHasher::Sha256(h) => sha2::Digest::update(h, data),
+        }
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        match self {
+            DigestHasher::Md5(h) => md5::Digest::finalize_reset(h).to_vec(),
+            DigestHasher::Sha256(h) => sha2::Digest::finalize_reset(h).to_vec(),
+        }
+    }
+}
+
+#[cfg(feature = "digest")]
+struct VerifyDigest {
+    inner: Option<InnerByteStream>,
+    hasher: DigestHasher,
+    expected: Vec<u8>,
+}
+
+#[cfg(feature = "digest")]
+impl Stream for VerifyDigest {
+    type Item = Result<Bytes, ChunkError<DigestMismatch>>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let Some(inner) = this.inner.as_mut() else {
+            return std::task::Poll::Ready(None);
+        };
+        let item = match std::task::ready!(inner.as_mut().poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                this.hasher.update(&chunk);
+                return std::task::Poll::Ready(Some(Ok(chunk)));
+            }
+            Some(Err(e)) => Some(Err(ChunkError::Stream(e))),
+            None => {
+                let actual = this.hasher.finish();
+                (actual != this.expected).then(|| {
+                    Err(ChunkError::Map(DigestMismatch {
+                        algo: this.hasher.algo(),
+                        expected: std::mem::take(&mut this.expected),
+                        actual,
+                    }))
+                })
+            }
+        };
+        this.inner = None;
+        std::task::Poll::Ready(item)
+    }
+}
+
+#[cfg(feature = "decompression")]
+enum ContentDecoder {
+    Identity,
+    Gzip(flate2::write::GzDecoder<Vec<u8>>),
+    Deflate(flate2::write::ZlibDecoder<Vec<u8>>),
+}
+
+#[cfg(feature = "decompression")]
+impl ContentDecoder {
+    /// Chooses a decoder for the given `Content-Encoding`, if it's supported.
+    fn new(encoding: Option<&str>) -> Option<Self> {
+        let mut codings = encoding
+            .map(|value| split_header_list(value, ','))
+            .unwrap_or_default()
+            .into_iter()
+            .map(str::trim)
+            .filter(|coding| {
+                !coding.is_empty() && !coding.eq_ignore_ascii_case("identity")
+            });
+        let decoder = match codings.next() {
+            None => ContentDecoder::Identity,
+            Some(coding)
+                if coding.eq_ignore_ascii_case("gzip")
+                    || coding.eq_ignore_ascii_case("x-gzip") =>
+            {
+                ContentDecoder::Gzip(flate2::write::GzDecoder::new(Vec::new()))
+            }
+            Some(coding) if coding.eq_ignore_ascii_case("deflate") => {
+                ContentDecoder::Deflate(flate2::write::ZlibDecoder::new(
+                    Vec::new(),
+                ))
+            }
+            Some(_) => return None,
+        };
+        // Stacked encodings aren't supported.
+        match codings.next() {
+            None => Some(decoder),
+            Some(_) => None,
+        }
+    }
+
+    fn decode(&mut self, chunk: Bytes) -> std::io::Result<Bytes> {
+        use std::io::Write;
+        match self {
+            ContentDecoder::Identity => Ok(chunk),
+            ContentDecoder::Gzip(d) => {
+                d.write_all(&chunk)?;
+                Ok(std::mem::take(d.get_mut()).into())
+            }
+            ContentDecoder::Deflate(d) => {
+                d.write_all(&chunk)?;
+                Ok(std::mem::take(d.get_mut()).into())
+            }
+        }
+    }
+
+    fn finish(&mut self) -> std::io::Result<Bytes> {
+        match self {
+            ContentDecoder::Identity => Ok(Bytes::new()),
+            ContentDecoder::Gzip(d) => {
+                d.try_finish()?;
+                Ok(std::mem::take(d.get_mut()).into())
+            }
+            ContentDecoder::Deflate(d) => {
+                d.try_finish()?;
+                Ok(std::mem::take(d.get_mut()).into())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "decompression")]
+struct Decoded {
+    inner: Option<InnerByteStream>,
+    decoder: ContentDecoder,
+    // In lenient mode, the input received before the decoder has produced
+    // any output, which is passed through as is if decoding fails.
+    pending: Option<bytes::BytesMut>,
+}
+
+#[cfg(feature = "decompression")]
+impl Stream for Decoded {
+    type Item = Result<Bytes, ChunkError<std::io::Error>>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let Some(inner) = this.inner.as_mut() else {
+                return std::task::Poll::Ready(None);
+            };
+            let item = match std::task::ready!(inner.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => {
+                    if let Some(pending) = this.pending.as_mut() {
+                        pending.extend_from_slice(&chunk);
+                    }
+                    this.decoder.decode(chunk).map_err(ChunkError::Map)
+                }
+                Some(Err(e)) => Err(ChunkError::Stream(e)),
+                None => {
+                    this.inner = None;
+                    this.decoder.finish().map_err(ChunkError::Map)
+                }
+            };
+            let item = match (item, this.pending.take()) {
+                (Err(ChunkError::Map(e)), Some(pending)) => {
+                    log::warn!(
+                        "response body could not be decoded ({}); \
+                        treating it as unencoded",
+                        e,
+                    );
+                    this.decoder = ContentDecoder::Identity;
+                    Ok(pending.freeze())
+                }
+                (Ok(chunk), pending) if chunk.is_empty() => {
+                    this.pending = pending;
+                    Ok(chunk)
+                }
+                (item, _) => item,
+            };
+            match item {
+                // Compressed input doesn't always produce output.
+                Ok(chunk) if chunk.is_empty() => continue,
+                Ok(chunk) => return std::task::Poll::Ready(Some(Ok(chunk))),
+                Err(e) => {
+                    this.inner = None;
+                    return std::task::Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl Deref for ByteStream {
+    type Target = InnerByteStream;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "stream")]
+impl DerefMut for ByteStream {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Streams the body of one request or response as the body of another
+/// request without buffering it, e.g. to proxy a download to another
+/// service.
+#[cfg(all(feature = "stream", not(target_arch = "wasm32")))]
+impl From<ByteStream> for reqwest::Body {
+    fn from(stream: ByteStream) -> Self {
+        reqwest::Body::wrap_stream(stream.0)
+    }
+}
+
+#[cfg(feature = "stream")]
+impl Stream for ByteStream {
+    type Item = reqwest::Result<Bytes>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.get_mut().0.as_mut().poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+/// Produces the body of a streamed upload afresh for each attempt, so that
+/// the upload can be retried.
+///
+/// A streamed `reqwest::Body` is consumed as it's sent and can't be replayed;
+/// a factory instead builds a new body (e.g. by reopening a file) whenever one
+/// is needed. The factory must produce identical content on every call.
+/// ```ignore
+/// let factory = BodyFactory::new(|| reqwest::Body::wrap_stream(open_blob()));
+/// let rv = factory
+///     .retry(3, |body| client.upload_blob(id, body))
+///     .await?;
+/// ```
+pub struct BodyFactory {
+    make: Box<dyn Fn() -> reqwest::Body + Send + Sync>,
+    #[cfg(not(target_arch = "wasm32"))]
+    budget: Option<std::sync::Arc<RetryBudget>>,
+}
+
+impl BodyFactory {
+    /// Creates a new [`BodyFactory`].
+    pub fn new<F>(make: F) -> Self
+    where
+        F: Fn() -> reqwest::Body + Send + Sync + 'static,
+    {
+        Self {
+            make: Box::new(make),
+            #[cfg(not(target_arch = "wasm32"))]
+            budget: None,
+        }
+    }
+
+    /// Limits the retries made by [`BodyFactory::retry`] with `budget`, which
+    /// is typically shared by all the factories used with a client so that
+    /// retries back off globally while the API is failing.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn retry_budget(self, budget: std::sync::Arc<RetryBudget>) -> Self {
+        Self {
+            budget: Some(budget),
+            ..self
+        }
+    }
+
+    /// Produces a new body.
+    pub fn body(&self) -> reqwest::Body {
+        (self.make)()
+    }
+
+    /// Invokes `op` with a new body until it succeeds, fails with an error
+    /// that isn't retriable, or `max_attempts` attempts have been made.
+    ///
+    /// Errors are retried if [`Error::is_retryable`]: connection failures,
+    /// timeouts, and responses with the status 408, 425, 429, or 5xx. Attempts
+    /// are made immediately one after the other; callers that want a delay
+    /// between attempts can sleep within `op`. If the factory has a
+    /// [`RetryBudget`], the last error is returned once the budget is spent.
+    pub async fn retry<T, E, Op, Fut>(
+        &self,
+        max_attempts: usize,
+        mut op: Op,
+    ) -> Result<T, Error<E>>
+    where
+        Op: FnMut(reqwest::Body) -> Fut,
+        Fut: std::future::Future<Output = Result<T, Error<E>>>,
+    {
+        self.budget_deposit();
+        let mut attempt = 1;
+        loop {
+            match op(self.body()).await {
+                Err(e)
+                    if attempt < max_attempts
+                        && e.is_retryable()
+                        && self.budget_withdraw() =>
+                {
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn budget_deposit(&self) {
+        if let Some(budget) = &self.budget {
+            budget.deposit();
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn budget_deposit(&self) {}
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn budget_withdraw(&self) -> bool {
+        match &self.budget {
+            Some(budget) => budget.try_withdraw(),
+            None => true,
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn budget_withdraw(&self) -> bool {
+        true
+    }
+}
+
+/// Budget that caps retries relative to original requests, so that retrying
+/// doesn't multiply the load on an API that is already failing.
+///
+/// This is a token bucket: each original request deposits `ratio` tokens and
+/// each retry withdraws one, so that over time retries make up at most
+/// `ratio` of requests. The balance is capped at the tokens that 100
+/// requests would deposit, which bounds the retries that can follow a long
+/// quiet period. In addition, `min_per_sec` retries are allowed each second
+/// regardless of the balance, so that clients that make few requests can
+/// still retry. The [`Default`] budget allows retries of 20% of requests
+/// plus 10 per second.
+///
+/// Clients generated with a custom transport hold a budget set with
+/// `Client::with_retry_budget`, which their `Client::body_factory` factories
+/// share:
+/// ```ignore
+/// let client = Client::new(url).with_retry_budget(0.1, 5);
+/// let factory = client.body_factory(make_body);
+/// ```
+/// A budget can also be shared by hand:
+/// ```ignore
+/// let budget = Arc::new(RetryBudget::new(0.1, 5));
+/// let factory = BodyFactory::new(make_body).retry_budget(budget.clone());
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub struct RetryBudget {
+    ratio: f64,
+    min_per_sec: u32,
+    state: std::sync::Mutex<RetryBudgetState>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct RetryBudgetState {
+    tokens: f64,
+    reserve: u32,
+    // When the reserve was last replenished, or `None` until the first
+    // retry.
+    reserve_since: Option<std::time::Instant>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RetryBudget {
+    /// Creates a budget allowing retries of `ratio` of original requests,
+    /// plus `min_per_sec` retries each second. Negative ratios are treated as
+    /// zero.
+    pub fn new(ratio: f64, min_per_sec: u32) -> Self {
+        Self {
+            ratio: ratio.max(0.0),
+            min_per_sec,
+            state: std::sync::Mutex::new(RetryBudgetState {
+                tokens: 0.0,
+                reserve: min_per_sec,
+                reserve_since: None,
+            }),
+        }
+    }
+
+    /// Records an original request, earning credit for later retries.
+    pub fn deposit(&self) {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.tokens = (state.tokens + self.ratio).min(self.ratio * 100.0);
+    }
+
+    /// Withdraws credit for a retry, returning whether the retry may be made.
+    pub fn try_withdraw(&self) -> bool {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = std::time::Instant::now();
+        match state.reserve_since {
+            Some(since)
+                if now.duration_since(since)
+                    < std::time::Duration::from_secs(1) => {}
+            _ => {
+                state.reserve = self.min_per_sec;
+                state.reserve_since = Some(now);
+            }
+        }
+
+        if state.reserve > 0 {
+            state.reserve -= 1;
+            true
+        } else if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for RetryBudget {
+    fn default() -> Self {
+        Self::new(0.2, 10)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::fmt::Debug for RetryBudget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryBudget")
+            .field("ratio", &self.ratio)
+            .field("min_per_sec", &self.min_per_sec)
+            .finish_non_exhaustive()
+    }
+}
+
+impl std::fmt::Debug for BodyFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BodyFactory")
+    }
+}
+
+/// Runs operations with at most `limit` in flight at once, collecting their
+/// results in the order the operations were given.
+///
+/// Operations are started as earlier ones complete, so a slow operation
+/// doesn't hold up the others. A `limit` of zero is treated as one.
+/// ```ignore
+/// let results = progenitor_client::batch(
+///     ids.iter().map(|id| client.get_widget(id)),
+///     8,
+/// )
+/// .await;
+/// for (index, error) in results.failures() {
+///     eprintln!("widget {} failed: {}", ids[index], error);
+/// }
+/// ```
+pub async fn batch<I, F, T, E>(
+    operations: I,
+    limit: usize,
+) -> BatchResults<T, E>
+where
+    I: IntoIterator<Item = F>,
+    F: std::future::Future<Output = Result<T, Error<E>>>,
+{
+    use std::future::Future;
+
+    let limit = limit.max(1);
+    let mut pending = operations.into_iter();
+    let mut running = Vec::<(usize, std::pin::Pin<Box<F>>)>::new();
+    let mut results = Vec::<Option<Result<T, Error<E>>>>::new();
+    let mut exhausted = false;
+
+    std::future::poll_fn(|cx| loop {
+        while !exhausted && running.len() < limit {
+            let Some(operation) = pending.next() else {
+                exhausted = true;
+                break;
+            };
+            running.push((results.len(), Box::pin(operation)));
+            results.push(None);
+        }
+
+        let before = running.len();
+        running.retain_mut(|(index, operation)| {
+            match operation.as_mut().poll(cx) {
+                std::task::Poll::Ready(result) => {
+                    results[*index] = Some(result);
+                    false
+                }
+                std::task::Poll::Pending => true,
+            }
+        });
+
+        // Operations that completed make room for more to be started.
+        if running.is_empty() && exhausted {
+            return std::task::Poll::Ready(());
+        }
+        if running.len() == before {
+            return std::task::Poll::Pending;
+        }
+    })
+    .await;
+
+    BatchResults {
+        results: results
+            .into_iter()
+            .map(|result| result.expect("all operations completed"))
+            .collect(),
+    }
+}
+
+/// Results of the operations run by [`batch`], in the order the operations
+/// were given.
+pub struct BatchResults<T, E> {
+    results: Vec<Result<T, Error<E>>>,
+}
+
+impl<T, E> BatchResults<T, E> {
+    /// Returns true if every operation succeeded.
+    pub fn is_all_ok(&self) -> bool {
+        self.results.iter().all(Result::is_ok)
+    }
+
+    /// Iterates over the errors of the operations that failed along with the
+    /// position of each operation.
+    pub fn failures(&self) -> impl Iterator<Item = (usize, &Error<E>)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| Some((index, result.as_ref().err()?)))
+    }
+
+    /// Consumes the results, returning the results of each operation.
+    pub fn into_results(self) -> Vec<Result<T, Error<E>>> {
+        self.results
+    }
+
+    /// Consumes the results, returning the successful values if every
+    /// operation succeeded or otherwise the errors along with the position of
+    /// each failed operation.
+    pub fn into_all_ok(self) -> Result<Vec<T>, Vec<(usize, Error<E>)>> {
+        let mut values = Vec::with_capacity(self.results.len());
+        let mut errors = Vec::new();
+        for (index, result) in self.results.into_iter().enumerate() {
+            match result {
+                Ok(value) => values.push(value),
+                Err(e) => errors.push((index, e)),
+            }
+        }
+        if errors.is_empty() {
+            Ok(values)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Turns a long-poll operation into a stream of its responses, issuing the
+/// operation again each time the stream is polled after a response, with
+/// the cursor that `cursor_extractor` takes from the previous response.
+///
+/// `op_factory` is invoked with the latest cursor, or `None` until a
+/// response has provided one; a response from which `cursor_extractor`
+/// takes no cursor leaves the previous one in place. Errors are yielded as
+/// items. After an error that [`Error::is_retryable`], the operation is
+/// issued again with the same cursor after a delay that starts at one second
+/// and doubles up to 30 seconds (see [`LongPoll::backoff`]), and is reset by
+/// a success; any other error ends the stream. Timing relies on the Tokio
+/// runtime, so this needs the `tokio` feature.
+/// ```ignore
+/// let events = progenitor_client::long_poll(
+///     |cursor: Option<String>| async move {
+///         client.watch_events(cursor.as_deref()).await
+///     },
+///     |rv| rv.headers().get("x-cursor")?.to_str().ok().map(String::from),
+/// );
+/// ```
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+pub fn long_poll<C, T, E, Op, Fut, X>(
+    op_factory: Op,
+    cursor_extractor: X,
+) -> LongPoll<C, Op, Fut, X>
+where
+    C: Clone,
+    Op: FnMut(Option<C>) -> Fut,
+    Fut: std::future::Future<Output = Result<ResponseValue<T>, Error<E>>>,
+    X: FnMut(&ResponseValue<T>) -> Option<C>,
+{
+    let initial = std::time::Duration::from_secs(1);
+    LongPoll {
+        op_factory,
+        cursor_extractor,
+        cursor: None,
+        pending: None,
+        delay: None,
+        initial,
+        max: std::time::Duration::from_secs(30),
+        next: initial,
+        done: false,
+    }
+}
+
+/// Stream of the responses to a long-poll operation, as returned by
+/// [`long_poll`].
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+pub struct LongPoll<C, Op, Fut, X> {
+    op_factory: Op,
+    cursor_extractor: X,
+    cursor: Option<C>,
+    pending: Option<std::pin::Pin<Box<Fut>>>,
+    delay: Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
+    initial: std::time::Duration,
+    max: std::time::Duration,
+    next: std::time::Duration,
+    done: bool,
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl<C, Op, Fut, X> LongPoll<C, Op, Fut, X> {
+    /// Waits `initial` after the first of consecutive retriable errors,
+    /// doubling the delay after each further error up to `max`.
+    pub fn backoff(
+        self,
+        initial: std::time::Duration,
+        max: std::time::Duration,
+    ) -> Self {
+        Self {
+            initial,
+            max,
+            next: initial,
+            ..self
+        }
+    }
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+// The operation's future and the delay are boxed.
+impl<C, Op, Fut, X> Unpin for LongPoll<C, Op, Fut, X> {}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl<C, T, E, Op, Fut, X> Stream for LongPoll<C, Op, Fut, X>
+where
+    C: Clone,
+    Op: FnMut(Option<C>) -> Fut,
+    Fut: std::future::Future<Output = Result<ResponseValue<T>, Error<E>>>,
+    X: FnMut(&ResponseValue<T>) -> Option<C>,
+{
+    type Item = Result<ResponseValue<T>, Error<E>>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        use std::future::Future;
+
+        let this = self.get_mut();
+        if this.done {
+            return std::task::Poll::Ready(None);
+        }
+        if let Some(delay) = this.delay.as_mut() {
+            std::task::ready!(delay.as_mut().poll(cx));
+            this.delay = None;
+        }
+
+        let pending = this.pending.get_or_insert_with(|| {
+            Box::pin((this.op_factory)(this.cursor.clone()))
+        });
+        let result = std::task::ready!(pending.as_mut().poll(cx));
+        this.pending = None;
+        match &result {
+            Ok(rv) => {
+                if let Some(cursor) = (this.cursor_extractor)(rv) {
+                    this.cursor = Some(cursor);
+                }
+                this.next = this.initial;
+            }
+            Err(e) if e.is_retryable() => {
+                this.delay = Some(Box::pin(tokio::time::sleep(this.next)));
+                this.next = (this.next * 2).min(this.max);
+            }
+            Err(_) => this.done = true,
+        }
+        std::task::Poll::Ready(Some(result))
+    }
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl<C, Op, Fut, X> std::fmt::Debug for LongPoll<C, Op, Fut, X> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LongPoll")
+    }
+}
+
+/// Error produced by a [`Transport`] while executing a request.
+pub type TransportError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+#[cfg(not(target_arch = "wasm32"))]
+type TransportFuture<'a> = std::pin::Pin<
+    Box<
+        dyn std::future::Future<
+                Output = Result<reqwest::Response, TransportError>,
+            > + Send
+            + 'a,
+    >,
+>;
+
+#[cfg(target_arch = "wasm32")]
+type TransportFuture<'a> = std::pin::Pin<
+    Box<
+        dyn std::future::Future<
+                Output = Result<reqwest::Response, TransportError>,
+            > + 'a,
+    >,
+>;
+
+/// Executes the requests issued by a generated client.
+///
+/// Clients generated with a custom transport send each request through this
+/// trait rather than calling into their `reqwest::Client` directly. The
+/// default implementation is `reqwest::Client` itself; alternate
+/// implementations can return canned responses in tests or route requests to
+/// other backends such as an in-process server.
+pub trait Transport: Send + Sync {
+    /// Executes the request, producing the raw response.
+    fn execute(&self, request: reqwest::Request) -> TransportFuture<'_>;
+}
+
+impl Transport for reqwest::Client {
+    fn execute(&self, request: reqwest::Request) -> TransportFuture<'_> {
+        let response = reqwest::Client::execute(self, request);
+        Box::pin(async move { response.await.map_err(Into::into) })
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for std::sync::Arc<T> {
+    fn execute(&self, request: reqwest::Request) -> TransportFuture<'_> {
+        T::execute(self, request)
+    }
+}
+
+impl std::fmt::Debug for dyn Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Transport")
+    }
+}
+
+/// [`Transport`] that produces responses by calling a function.
+///
+/// This is primarily useful for tests:
+/// ```ignore
+/// let transport = TransportFn::new(|request| {
+///     assert_eq!(request.url().path(), "/widgets");
+///     Ok(http::Response::new("[]").into())
+/// });
+/// let client = Client::new_with_transport("http://localhost", transport);
+/// ```
+pub struct TransportFn<F>(F);
+
+impl<F> TransportFn<F>
+where
+    F: Fn(reqwest::Request) -> Result<reqwest::Response, TransportError>
+        + Send
+        + Sync,
+{
+    /// Creates a new [`TransportFn`].
+    pub fn new(f: F) -> Self {
+        Self(f)
+    }
+}
+
+impl<F> Transport for TransportFn<F>
+where
+    F: Fn(reqwest::Request) -> Result<reqwest::Response, TransportError>
+        + Send
+        + Sync,
+{
+    fn execute(&self, request: reqwest::Request) -> TransportFuture<'_> {
+        let result = (self.0)(request);
+        Box::pin(async move { result })
+    }
+}
+
+/// [`Transport`] that sends requests over a Unix domain socket rather than
+/// TCP, e.g. to reach the control API of a local daemon.
+///
+/// The scheme and host of the client's base URL are ignored, so it may be a
+/// placeholder such as `http://localhost`; the path and query are sent as
+/// usual. Each request is made on a new connection, and request bodies must
+/// be buffered: streaming request bodies aren't supported. Response bodies
+/// are streamed as they are over TCP.
+/// ```ignore
+/// let transport = UnixSocketTransport::new("/var/run/docker.sock");
+/// let client = Client::new_with_transport("http://localhost", transport);
+/// ```
+#[cfg(all(feature = "unix-socket", unix))]
+#[derive(Debug, Clone)]
+pub struct UnixSocketTransport {
+    path: std::path::PathBuf,
+}
+
+#[cfg(all(feature = "unix-socket", unix))]
+impl UnixSocketTransport {
+    /// Creates a transport that connects to the socket at `path`.
+    pub fn new(path: impl Into<std::path::PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Gets the path of the socket.
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
+
+#[cfg(all(feature = "unix-socket", unix))]
+impl Transport for UnixSocketTransport {
+    fn execute(&self, request: reqwest::Request) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let request = unix_socket_request(request)?;
+            let stream = tokio::net::UnixStream::connect(&self.path).await?;
+            let (mut sender, connection) =
+                hyper::client::conn::handshake(stream).await?;
+            tokio::spawn(async move {
+                // Failures are reported through the response or its body.
+                let _ = connection.await;
+            });
+            let response = sender.send_request(request).await?;
+            Ok(response.map(reqwest::Body::wrap_stream).into())
+        })
+    }
+}
+
+/// Converts a request into the form sent over a Unix socket, with just the
+/// path and query as its target.
+#[cfg(all(feature = "unix-socket", unix))]
+fn unix_socket_request(
+    request: reqwest::Request,
+) -> Result<hyper::Request<hyper::Body>, TransportError> {
+    let body = match request.body() {
+        None => hyper::Body::empty(),
+        Some(body) => match body.as_bytes() {
+            Some(bytes) => hyper::Body::from(bytes.to_vec()),
+            None => {
+                return Err("streaming request bodies are not supported over \
+                    a Unix socket"
+                    .into())
+            }
+        },
+    };
+
+    let url = request.url();
+    let mut target = url.path().to_string();
+    if let Some(query) = url.query() {
+        target.push('?');
+        target.push_str(query);
+    }
+
+    let mut unix_request = hyper::Request::builder()
+        .method(request.method().clone())
+        .uri(target)
+        .body(body)?;
+    let headers = unix_request.headers_mut();
+    *headers = request.headers().clone();
+    if !headers.contains_key(reqwest::header::HOST) {
+        let host = url.host_str().unwrap_or("localhost");
+        headers.insert(
+            reqwest::header::HOST,
+            reqwest::header::HeaderValue::from_str(host)?,
+        );
+    }
+    Ok(unix_request)
+}
+
+/// [`Transport`] that copies selected headers from a context, such as the
+/// incoming request a service is handling, onto each outgoing request.
+///
+/// The `source` function is called for each request and typically reads a
+/// task-local or similar context; headers already set on the outgoing
+/// request are left unchanged.
+/// ```ignore
+/// let transport = PropagateHeaders::new(
+///     reqwest::Client::new(),
+///     &[HeaderName::from_static("x-tenant-id")],
+///     || INCOMING_HEADERS.try_with(|headers| headers.clone()).ok(),
+/// );
+/// ```
+pub struct PropagateHeaders<T> {
+    inner: T,
+    names: Vec<reqwest::header::HeaderName>,
+    source: Box<dyn Fn() -> Option<reqwest::header::HeaderMap> + Send + Sync>,
+}
+
+impl<T: Transport> PropagateHeaders<T> {
+    /// Wraps `inner`, propagating the headers in `names` from the map
+    /// produced by `source`.
+    pub fn new<F>(
+        inner: T,
+        names: &[reqwest::header::HeaderName],
+        source: F,
+    ) -> Self
+    where
+        F: Fn() -> Option<reqwest::header::HeaderMap> + Send + Sync + 'static,
+    {
+        Self {
+            inner,
+            names: names.to_vec(),
+            source: Box::new(source),
+        }
+    }
+}
+
+impl<T: Transport> Transport for PropagateHeaders<T> {
+    fn execute(&self, mut request: reqwest::Request) -> TransportFuture<'_> {
+        if let Some(context) = (self.source)() {
+            for name in &self.names {
+                if request.headers().contains_key(name) {
+                    continue;
+                }
+                for value in context.get_all(name) {
+                    request.headers_mut().append(name.clone(), value.clone());
+                }
+            }
+        }
+        self.inner.execute(request)
+    }
+}
+
+/// [`Transport`] that appends constant query parameters, such as an
+/// `api-version`, to each request.
+///
+/// A parameter is appended only if the request's URL doesn't already have a
+/// parameter of the same name, so that an operation's own parameters take
+/// precedence. Names and values are encoded as the generated operations
+/// encode their query parameters.
+pub struct DefaultQuery<T> {
+    inner: T,
+    params: Vec<(String, String)>,
+}
+
+impl<T: Transport> DefaultQuery<T> {
+    /// Wraps `inner`, appending the `params` pairs of names and values.
+    pub fn new(inner: T, params: &[(&str, &str)]) -> Self {
+        Self {
+            inner,
+            params: params
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl<T: Transport> Transport for DefaultQuery<T> {
+    fn execute(&self, mut request: reqwest::Request) -> TransportFuture<'_> {
+        let url = request.url_mut();
+        let present = url
+            .query_pairs()
+            .map(|(name, _)| name.into_owned())
+            .collect::<std::collections::BTreeSet<_>>();
+        let missing = self
+            .params
+            .iter()
+            .filter(|(name, _)| !present.contains(name))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            let mut pairs = url.query_pairs_mut();
+            for (name, value) in missing {
+                pairs.append_pair(name, value);
+            }
+        }
+        self.inner.execute(request)
+    }
+}
+
+/// [`Transport`] that caps the number of requests in flight to each host,
+/// queueing the excess until earlier requests complete.
+///
+/// A request counts against the limit from when it is sent until its
+/// response body has been read, or, for streamed responses, until the
+/// [`ByteStream`] ends or is dropped. Responses reported as
+/// [`Error::UnexpectedResponse`] hold their slot until they are dropped.
+///
+/// Each attempt made by [`BodyFactory::retry`] is a separate request, and
+/// waits for a slot of its own. When used with [`batch`], the lower of the
+/// two limits applies.
+pub struct ConcurrencyLimit<T> {
+    inner: T,
+    limit: usize,
+    hosts: std::sync::Mutex<
+        std::collections::HashMap<String, std::sync::Arc<HostLimiter>>,
+    >,
+}
+
+impl<T: Transport> ConcurrencyLimit<T> {
+    /// Wraps `inner`, allowing at most `limit` requests in flight to each
+    /// host; a limit of 0 is treated as 1.
+    pub fn new(inner: T, limit: usize) -> Self {
+        Self {
+            inner,
+            limit: limit.max(1),
+            hosts: Default::default(),
+        }
+    }
+
+    fn limiter(&self, url: &reqwest::Url) -> std::sync::Arc<HostLimiter> {
+        let host = format!(
+            "{}:{}",
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default(),
+        );
+        self.hosts
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(host)
+            .or_insert_with(|| {
+                std::sync::Arc::new(HostLimiter {
+                    limit: self.limit,
+                    state: Default::default(),
+                })
+            })
+            .clone()
+    }
+}
+
+impl<T: Transport> Transport for ConcurrencyLimit<T> {
+    fn execute(&self, request: reqwest::Request) -> TransportFuture<'_> {
+        let limiter = self.limiter(request.url());
+        Box::pin(async move {
+            let permit = HostLimiter::acquire(limiter).await;
+            let mut response = self.inner.execute(request).await?;
+            // The permit is released when the body has been consumed; see
+            // `take_permit`.
+            response.extensions_mut().insert(permit);
+            Ok(response)
+        })
+    }
+}
+
+#[derive(Default)]
+struct HostLimiterState {
+    in_flight: usize,
+    waiters: Vec<std::task::Waker>,
+}
+
+struct HostLimiter {
+    limit: usize,
+    state: std::sync::Mutex<HostLimiterState>,
+}
+
+impl HostLimiter {
+    async fn acquire(limiter: std::sync::Arc<Self>) -> RequestPermit {
+        std::future::poll_fn(|cx| {
+            let mut state = limiter
+                .state
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if state.in_flight < limiter.limit {
+                state.in_flight += 1;
+                std::task::Poll::Ready(())
+            } else {
+                state.waiters.push(cx.waker().clone());
+                std::task::Poll::Pending
+            }
+        })
+        .await;
+        RequestPermit(limiter)
+    }
+}
+
+/// A slot held by a request sent through [`ConcurrencyLimit`].
+struct RequestPermit(std::sync::Arc<HostLimiter>);
+
+impl Drop for RequestPermit {
+    fn drop(&mut self) {
+        let waiters = {
+            let mut state = self
+                .0
+                .state
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            state.in_flight -= 1;
+            std::mem::take(&mut state.waiters)
+        };
+        // Waiters that have since been dropped don't take the slot, so wake
+        // them all rather than just the first.
+        waiters.into_iter().for_each(std::task::Waker::wake);
+    }
+}
+
+/// Takes the [`ConcurrencyLimit`] permit of a response, if any, so that it
+/// can be held until the body has been read. Reading the body drops the rest
+/// of the response, including its extensions, before the read completes.
+fn take_permit(response: &mut reqwest::Response) -> Option<RequestPermit> {
+    response.extensions_mut().remove::<RequestPermit>()
+}
+
+/// [`Transport`] that sends each request with `Connection: close`, so that
+/// cancelling an operation closes its connection and the server can detect
+/// that the client went away.
+///
+/// Cancelling an operation, by dropping its future or its [`ByteStream`],
+/// ordinarily leaves the server to finish its work and hands the HTTP/1.1
+/// connection back to the pool if it can still be reused. When every
+/// request asks for its connection to be closed the connection is never
+/// pooled, so dropping the operation drops the connection and the server
+/// sees it close; this is useful for expensive operations such as reports or
+/// exports whose server-side work should stop when they're abandoned. Each
+/// request pays for a connection of its own. HTTP/2 has no `Connection`
+/// header, and dropping an operation there resets its stream instead.
+pub struct CloseOnCancel<T> {
+    inner: T,
+}
+
+impl<T: Transport> CloseOnCancel<T> {
+    /// Wraps `inner`, which executes the requests.
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T: Transport> Transport for CloseOnCancel<T> {
+    fn execute(&self, mut request: reqwest::Request) -> TransportFuture<'_> {
+        if request.version() < reqwest::Version::HTTP_2 {
+            request.headers_mut().insert(
+                reqwest::header::CONNECTION,
+                reqwest::header::HeaderValue::from_static("close"),
+            );
+        }
+        self.inner.execute(request)
+    }
+}
+
+/// [`Transport`] that marks each response so that its body is retained by
+/// the [`ResponseValue`] decoded from it; see [`ResponseValue::raw_body`].
+///
+/// This is useful for debugging or auditing responses whose decoded form
+/// loses detail, at the cost of holding each body in memory alongside its
+/// decoded value for as long as the value lives. Bodies that are streamed
+/// or upgraded aren't retained.
+pub struct RetainBodies<T> {
+    inner: T,
+}
+
+impl<T: Transport> RetainBodies<T> {
+    /// Wraps `inner`, which executes the requests.
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T: Transport> Transport for RetainBodies<T> {
+    fn execute(&self, request: reqwest::Request) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let mut result = self.inner.execute(request).await;
+            if let Ok(response) = &mut result {
+                response.extensions_mut().insert(RetainBody);
+            }
+            result
+        })
+    }
+}
+
+/// Marks a response whose body is to be retained; see [`RetainBodies`].
+#[derive(Clone)]
+struct RetainBody;
+
+/// [`Transport`] that hedges reads to cut tail latency: if a `GET`, `HEAD`, or
+/// `OPTIONS` request hasn't been answered within `delay`, an identical
+/// request is sent and whichever succeeds first is used.
+///
+/// `delay` is best set near a high percentile of the operation's usual
+/// latency, e.g. the 95th, so that only the slowest requests are sent twice.
+/// The other request is cancelled by dropping it once one has succeeded; if
+/// one fails, the other is awaited instead. Only these safe methods are
+/// hedged, as a duplicate of any other request could repeat its side
+/// effects, and requests whose bodies are streamed can't be duplicated.
+///
+/// Timing relies on the Tokio runtime, so this needs the `tokio` feature.
+/// Clients generated with a custom transport can use it through
+/// `Client::new_with_transport`.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+pub struct Hedge<T> {
+    inner: T,
+    delay: std::time::Duration,
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl<T: Transport> Hedge<T> {
+    /// Wraps `inner`, which executes the requests, sending a second request
+    /// for reads that take longer than `delay`.
+    pub fn new(inner: T, delay: std::time::Duration) -> Self {
+        Self { inner, delay }
+    }
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl<T: Transport> Transport for Hedge<T> {
+    fn execute(&self, request: reqwest::Request) -> TransportFuture<'_> {
+        use std::future::Future;
+        use std::task::Poll;
+
+        let safe = matches!(
+            *request.method(),
+            reqwest::Method::GET
+                | reqwest::Method::HEAD
+                | reqwest::Method::OPTIONS
+        );
+        let hedge = match safe.then(|| request.try_clone()).flatten() {
+            Some(hedge) => hedge,
+            None => return self.inner.execute(request),
+        };
+
+        let mut primary = self.inner.execute(request);
+        Box::pin(async move {
+            let mut sleep = Box::pin(tokio::time::sleep(self.delay));
+            let first = std::future::poll_fn(|cx| {
+                if let Poll::Ready(result) = primary.as_mut().poll(cx) {
+                    return Poll::Ready(Some(result));
+                }
+                sleep.as_mut().poll(cx).map(|()| None)
+            })
+            .await;
+            if let Some(result) = first {
+                return result;
+            }
+
+            // Race the two requests; a failure leaves the other to finish.
+            let mut secondary = self.inner.execute(hedge);
+            let mut primary = Some(primary);
+            let mut secondary_done = false;
+            std::future::poll_fn(|cx| {
+                if let Some(fut) = primary.as_mut() {
+                    if let Poll::Ready(result) = fut.as_mut().poll(cx) {
+                        if result.is_ok() || secondary_done {
+                            return Poll::Ready(result);
+                        }
+                        primary = None;
+                    }
+                }
+                if !secondary_done {
+                    if let Poll::Ready(result) = secondary.as_mut().poll(cx) {
+                        if result.is_ok() || primary.is_none() {
+                            return Poll::Ready(result);
+                        }
+                        secondary_done = true;
+                    }
+                }
+                Poll::Pending
+            })
+            .await
+        })
+    }
+}
+
+/// Summary of a request and its response, as retained by a [`RequestLog`].
+#[derive(Clone, Debug)]
+pub struct RequestSummary {
+    /// Method of the request.
+    pub method: reqwest::Method,
+    /// URL of the request.
+    pub url: reqwest::Url,
+    /// Headers of the request, with sensitive values redacted.
+    pub request_headers: reqwest::header::HeaderMap,
+    /// Start of the request body, or `None` if the body was streamed.
+    pub request_body: Option<Bytes>,
+    /// Status of the response, or `None` if the request failed.
+    pub status: Option<reqwest::StatusCode>,
+    /// Headers of the response, with sensitive values redacted.
+    pub response_headers: reqwest::header::HeaderMap,
+    /// Start of the response body, once it has been read by
+    /// [`ResponseValue::from_response`]; bodies consumed as a [`ByteStream`]
+    /// or in some other way aren't captured.
+    pub response_body: Option<Bytes>,
+    /// The transport error, if the request failed.
+    pub error: Option<String>,
+    /// Time until the response headers were received; this is always zero
+    /// on `wasm32`.
+    pub elapsed: std::time::Duration,
+}
+
+/// Bounded log of the most recent requests made through a
+/// [`RecordRequests`] transport, for post-mortem debugging, e.g. through an
+/// admin endpoint.
+///
+/// Values of the `Authorization`, `Proxy-Authorization`, `Cookie`, and
+/// `Set-Cookie` headers, and of any headers added with [`Self::redact`], are
+/// replaced with `<redacted>`. Bodies are truncated to the configured limit.
+#[derive(Debug)]
+pub struct RequestLog {
+    capacity: usize,
+    body_limit: usize,
+    redacted: Vec<reqwest::header::HeaderName>,
+    entries: std::sync::Mutex<
+        std::collections::VecDeque<
+            std::sync::Arc<std::sync::Mutex<RequestSummary>>,
+        >,
+    >,
+}
+
+impl RequestLog {
+    /// Creates a log that retains the last `capacity` requests, keeping at
+    /// most `body_limit` bytes of each body.
+    pub fn new(capacity: usize, body_limit: usize) -> Self {
+        Self {
+            capacity,
+            body_limit,
+            redacted: vec![
+                reqwest::header::AUTHORIZATION,
+                reqwest::header::PROXY_AUTHORIZATION,
+                reqwest::header::COOKIE,
+                reqwest::header::SET_COOKIE,
+            ],
+            entries: Default::default(),
+        }
+    }
+
+    /// Redacts the values of the header `name` as well as the defaults.
+    pub fn redact(mut self, name: reqwest::header::HeaderName) -> Self {
+        self.redacted.push(name);
+        self
+    }
+
+    /// Gets the retained requests, oldest first.
+    pub fn recent(&self) -> Vec<RequestSummary> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(|entry| {
+                entry
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .clone()
+            })
+            .collect()
+    }
+
+    fn record(
+        &self,
+        summary: RequestSummary,
+    ) -> std::sync::Arc<std::sync::Mutex<RequestSummary>> {
+        let entry = std::sync::Arc::new(std::sync::Mutex::new(summary));
+        if self.capacity > 0 {
+            let mut entries = self
+                .entries
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if entries.len() == self.capacity {
+                entries.pop_front();
+            }
+            entries.push_back(entry.clone());
+        }
+        entry
+    }
+
+    fn redact_headers(
+        &self,
+        headers: &reqwest::header::HeaderMap,
+    ) -> reqwest::header::HeaderMap {
+        let mut headers = headers.clone();
+        for name in &self.redacted {
+            if let reqwest::header::Entry::Occupied(mut entry) =
+                headers.entry(name)
+            {
+                for value in entry.iter_mut() {
+                    *value =
+                        reqwest::header::HeaderValue::from_static("<redacted>");
+                }
+            }
+        }
+        headers
+    }
+
+    fn truncate(&self, body: &[u8]) -> Bytes {
+        Bytes::copy_from_slice(&body[..body.len().min(self.body_limit)])
+    }
+}
+
+/// [`Transport`] that records a summary of each request and its response in
+/// a [`RequestLog`].
+pub struct RecordRequests<T> {
+    inner: T,
+    log: std::sync::Arc<RequestLog>,
+}
+
+impl<T: Transport> RecordRequests<T> {
+    /// Wraps `inner`, which executes the requests, recording them to `log`.
+    pub fn new(inner: T, log: std::sync::Arc<RequestLog>) -> Self {
+        Self { inner, log }
+    }
+}
+
+impl<T: Transport> Transport for RecordRequests<T> {
+    fn execute(&self, request: reqwest::Request) -> TransportFuture<'_> {
+        let method = request.method().clone();
+        let url = request.url().clone();
+        let request_headers = self.log.redact_headers(request.headers());
+        let request_body = request
+            .body()
+            .and_then(reqwest::Body::as_bytes)
+            .map(|body| self.log.truncate(body));
+        #[cfg(not(target_arch = "wasm32"))]
+        let start = std::time::Instant::now();
+
+        Box::pin(async move {
+            let mut result = self.inner.execute(request).await;
+            #[cfg(not(target_arch = "wasm32"))]
+            let elapsed = start.elapsed();
+            #[cfg(target_arch = "wasm32")]
+            let elapsed = std::time::Duration::ZERO;
+
+            let mut summary = RequestSummary {
+                method,
+                url,
+                request_headers,
+                request_body,
+                status: None,
+                response_headers: Default::default(),
+                response_body: None,
+                error: None,
+                elapsed,
+            };
+            match &result {
+                Ok(response) => {
+                    summary.status = Some(response.status());
+                    summary.response_headers =
+                        self.log.redact_headers(response.headers());
+                }
+                Err(e) => summary.error = Some(e.to_string()),
+            }
+            let entry = self.log.record(summary);
+
+            // The body is filled in when it's read; see `take_recorded`.
+            if let Ok(response) = &mut result {
+                response.extensions_mut().insert(RecordedResponse {
+                    entry,
+                    limit: self.log.body_limit,
+                });
+            }
+            result
+        })
+    }
+}
+
+struct RecordedResponse {
+    entry: std::sync::Arc<std::sync::Mutex<RequestSummary>>,
+    limit: usize,
+}
+
+impl RecordedResponse {
+    fn body(self, body: &[u8]) {
+        let body = Bytes::copy_from_slice(&body[..body.len().min(self.limit)]);
+        self.entry
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .response_body = Some(body);
+    }
+}
+
+/// Takes the [`RequestLog`] entry of a response, if any, so that the body can
+/// be recorded once it has been read.
+fn take_recorded(response: &mut reqwest::Response) -> Option<RecordedResponse> {
+    response.extensions_mut().remove::<RecordedResponse>()
+}
+
+/// [`Transport`] that adds a W3C Trace Context `traceparent` header to
+/// requests that lack one.
+///
+/// Each request without a `traceparent` gets a new randomly generated trace
+/// ID and parent ID, with the sampled flag clear; requests that already carry
+/// the header (e.g. set by a tracing library) are passed through unchanged.
+/// The value that was sent can be found in the request passed to the inner
+/// transport, and correlated with [`ResponseValue::request_id`].
+pub struct TraceparentTransport<T> {
+    inner: T,
+}
+
+impl<T: Transport> TraceparentTransport<T> {
+    /// Wraps `inner`, which executes the requests.
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T: Transport> Transport for TraceparentTransport<T> {
+    fn execute(&self, mut request: reqwest::Request) -> TransportFuture<'_> {
+        if !request.headers().contains_key(TRACEPARENT) {
+            let value = format!(
+                "00-{:016x}{:016x}-{:016x}-00",
+                random_u64(),
+                random_u64(),
+                random_u64(),
+            );
+            request.headers_mut().insert(
+                TRACEPARENT,
+                reqwest::header::HeaderValue::try_from(value)
+                    .expect("hex value is a valid header"),
+            );
+        }
+        self.inner.execute(request)
+    }
+}
+
+const TRACEPARENT: &str = "traceparent";
+
+/// Produces a pseudo-random value using the randomly seeded keys of the
+/// standard library's hasher; this is suitable for identifiers but not for
+/// anything security sensitive.
+fn random_u64() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut hasher =
+        std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.finish()
+}
+
+/// [`Transport`] that records each request as an OpenTelemetry client span
+/// and propagates its context to the server.
+///
+/// Spans are created with the global tracer as children of the current
+/// context, named by the request method, and carry the `http.method`,
+/// `http.url`, `server.address`, `server.port`, and `http.status_code`
+/// attributes. A span's status is set to an error for transport failures and
+/// responses with a 4xx or 5xx status. The span's context is injected into
+/// the request headers with the global propagator, so with the W3C Trace
+/// Context propagator installed requests carry `traceparent` and
+/// `tracestate`. The span ends when the response headers arrive, as the body
+/// may be read much later or not at all.
+///
+/// This needs the `opentelemetry` feature. Clients generated with a custom
+/// transport can use it through `Client::new_with_transport`:
+/// ```ignore
+/// let transport = OpenTelemetryTransport::new(reqwest::Client::new());
+/// let client = Client::new_with_transport(baseurl, transport);
+/// ```
+#[cfg(feature = "opentelemetry")]
+pub struct OpenTelemetryTransport<T> {
+    inner: T,
+}
+
+#[cfg(feature = "opentelemetry")]
+impl<T: Transport> OpenTelemetryTransport<T> {
+    /// Wraps `inner`, which executes the requests.
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+#[cfg(feature = "opentelemetry")]
+impl<T: Transport> Transport for OpenTelemetryTransport<T> {
+    fn execute(&self, mut request: reqwest::Request) -> TransportFuture<'_> {
+        use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer};
+        use opentelemetry::{global, Context, KeyValue};
+
+        let url = request.url();
+        let mut attributes = vec![
+            KeyValue::new("http.method", request.method().to_string()),
+            KeyValue::new("http.url", url.to_string()),
+        ];
+        if let Some(host) = url.host_str() {
+            attributes.push(KeyValue::new("server.address", host.to_string()));
+        }
+        if let Some(port) = url.port_or_known_default() {
+            attributes.push(KeyValue::new("server.port", i64::from(port)));
+        }
+
+        let tracer = global::tracer("progenitor-client");
+        let span = tracer
+            .span_builder(request.method().to_string())
+            .with_kind(SpanKind::Client)
+            .with_attributes(attributes)
+            .start(&tracer);
+        let cx = Context::current_with_span(span);
+        global::get_text_map_propagator(|propagator| {
+            propagator
+                .inject_context(&cx, &mut HeaderInjector(request.headers_mut()))
+        });
+
+        let response = self.inner.execute(request);
+        Box::pin(async move {
+            let result = response.await;
+            let span = cx.span();
+            match &result {
+                Ok(response) => {
+                    let status = response.status();
+                    span.set_attribute(KeyValue::new(
+                        "http.status_code",
+                        i64::from(status.as_u16()),
+                    ));
+                    if status.is_client_error() || status.is_server_error() {
+                        span.set_status(Status::error(status.to_string()));
+                    }
+                }
+                Err(e) => span.set_status(Status::error(e.to_string())),
+            }
+            span.end();
+            result
+        })
+    }
+}
+
+/// Sets the headers produced by an OpenTelemetry propagator, skipping any
+/// that aren't valid headers.
+#[cfg(feature = "opentelemetry")]
+struct HeaderInjector<'a>(&'a mut reqwest::header::HeaderMap);
+
+#[cfg(feature = "opentelemetry")]
+impl opentelemetry::propagation::Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        let name = reqwest::header::HeaderName::from_bytes(key.as_bytes());
+        let value = reqwest::header::HeaderValue::try_from(value);
+        if let (Ok(name), Ok(value)) = (name, value) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+/// Builder for the `reqwest::Client` used by a generated client.
+///
+/// This starts from the same configuration that a generated `Client::new()`
+/// uses (15 second connect and request timeouts) and exposes the knobs that
+/// most affect throughput and connection churn for long-lived clients. The
+/// result is intended to be passed to `Client::new_with_client()`:
+/// ```ignore
+/// let client = progenitor_client::ClientBuilder::new()
+///     .tcp_keepalive(Duration::from_secs(60))
+///     .pool_max_idle_per_host(16)
+///     .build()?;
+/// let client = Client::new_with_client("https://api.example.com", client);
+/// ```
+///
+/// Unless otherwise configured, TCP keepalive is disabled, `TCP_NODELAY` is
+/// set, idle pooled connections are closed after 90 seconds, and there is no
+/// limit on the number of idle connections per host. Note that when a server
+/// speaks HTTP/2, concurrent requests are multiplexed over a single
+/// connection per host so the pool sizing has little effect while keepalive
+/// matters more.
+pub struct ClientBuilder {
+    inner: reqwest::ClientBuilder,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientBuilder {
+    /// Creates a new builder with the defaults of generated clients.
+    pub fn new() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        let inner = {
+            let dur = std::time::Duration::from_secs(15);
+
+            reqwest::ClientBuilder::new()
+                .connect_timeout(dur)
+                .timeout(dur)
+        };
+        #[cfg(target_arch = "wasm32")]
+        let inner = reqwest::ClientBuilder::new();
+
+        Self { inner }
+    }
+
+    /// Sets the interval for TCP keepalive probes on open connections.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn tcp_keepalive(self, interval: std::time::Duration) -> Self {
+        Self {
+            inner: self.inner.tcp_keepalive(interval),
+        }
+    }
+
+    /// Sets whether `TCP_NODELAY` is enabled on connections.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn tcp_nodelay(self, enabled: bool) -> Self {
+        Self {
+            inner: self.inner.tcp_nodelay(enabled),
+        }
+    }
+
+    /// Sets how long an idle connection is kept in the pool before it is
+    /// closed.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_idle_timeout(self, timeout: std::time::Duration) -> Self {
+        Self {
+            inner: self.inner.pool_idle_timeout(timeout),
+        }
+    }
+
+    /// Sets the maximum number of idle connections kept per host.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_max_idle_per_host(self, max: usize) -> Self {
+        Self {
+            inner: self.inner.pool_max_idle_per_host(max),
+        }
+    }
+
+    /// Sets how redirects are followed; by default up to 10 redirects are
+    /// followed to any destination.
+    ///
+    /// [`ResponseValue::final_url`] reports the URL of the last request made,
+    /// so it differs from the request URL only if a redirect was followed.
+    /// When a redirect isn't followed, the 3xx response itself is returned;
+    /// as such responses are rarely documented, this usually surfaces as an
+    /// [`Error::UnexpectedResponse`]. Note that when following a redirect to
+    /// a different host the `Authorization`, `Cookie`, and similar sensitive
+    /// headers are removed from the redirected request.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn redirect_policy(self, policy: RedirectPolicy) -> Self {
+        Self {
+            inner: self.inner.redirect(policy.0),
+        }
+    }
+
+    /// Sets whether TLS information is recorded for each response; see
+    /// [`ResponseValue::tls_info`].
+    #[cfg(all(
+        any(feature = "tls-info", feature = "tls-info-rustls"),
+        not(target_arch = "wasm32")
+    ))]
+    pub fn tls_info(self, enabled: bool) -> Self {
+        Self {
+            inner: self.inner.tls_info(enabled),
+        }
+    }
+
+    /// Applies arbitrary `reqwest::ClientBuilder` configuration.
+    pub fn map<F>(self, f: F) -> Self
+    where
+        F: FnOnce(reqwest::ClientBuilder) -> reqwest::ClientBuilder,
+    {
+        Self {
+            inner: f(self.inner),
+        }
+    }
+
+    /// Builds the `reqwest::Client`.
+    pub fn build(self) -> reqwest::Result<reqwest::Client> {
+        self.inner.build()
+    }
+}
+
+/// Policy passed to [`ClientBuilder::redirect_policy`].
+#[cfg(not(target_arch = "wasm32"))]
+pub struct RedirectPolicy(reqwest::redirect::Policy);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RedirectPolicy {
+    /// Don't follow redirects.
+    pub fn none() -> Self {
+        Self(reqwest::redirect::Policy::none())
+    }
+
+    /// Follow at most `max` redirects to any destination.
+    pub fn limited(max: usize) -> Self {
+        Self(reqwest::redirect::Policy::limited(max))
+    }
+
+    /// Follow at most 10 redirects, provided each stays on the same origin
+    /// (scheme, host, and port) as the original request; a redirect elsewhere
+    /// isn't followed.
+    pub fn same_origin() -> Self {
+        Self(reqwest::redirect::Policy::custom(|attempt| {
+            let same_origin = attempt
+                .previous()
+                .first()
+                .is_none_or(|first| first.origin() == attempt.url().origin());
+            if !same_origin {
+                attempt.stop()
+            } else if attempt.previous().len() > 10 {
+                attempt.error("too many redirects")
+            } else {
+                attempt.follow()
+            }
+        }))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<reqwest::redirect::Policy> for RedirectPolicy {
+    fn from(policy: reqwest::redirect::Policy) -> Self {
+        Self(policy)
+    }
+}
+
+/// Description of an operation of a generated client, as returned by
+/// `Client::operations()` in clients generated with the operation registry
+/// enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperationInfo {
+    /// The name of the client method for the operation.
+    pub operation_id: &'static str,
+    /// The HTTP method, e.g. `GET`.
+    pub method: &'static str,
+    /// The path template relative to the base URL, e.g. `/widgets/{id}`.
+    pub path: &'static str,
+}
+
+/// Server-side performance metric from a `Server-Timing` header; see
+/// [`ResponseValue::server_timing`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerTiming {
+    /// The name of the metric, e.g. `db`.
+    pub name: String,
+    /// The duration in milliseconds, if given.
+    pub dur: Option<f64>,
+    /// A human-readable description, if given.
+    pub desc: Option<String>,
+}
+
+/// OAuth 2.0 bearer token error from a `WWW-Authenticate` header, as defined
+/// by RFC 6750; see [`ResponseValue::bearer_error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BearerError {
+    /// The error code, e.g. `invalid_token` or `insufficient_scope`.
+    pub error: String,
+    /// A human-readable description, if given.
+    pub error_description: Option<String>,
+    /// A URI identifying a page that describes the error, if given.
+    pub error_uri: Option<String>,
+    /// The scope required to access the resource, if given.
+    pub scope: Option<String>,
+}
+
+impl BearerError {
+    /// Indicates that the token is expired, revoked, or otherwise invalid,
+    /// so that it may be worth refreshing it.
+    pub fn is_invalid_token(&self) -> bool {
+        self.error == "invalid_token"
+    }
+
+    /// Indicates that the token is valid but lacks the required scope, so
+    /// that refreshing it won't help.
+    pub fn is_insufficient_scope(&self) -> bool {
+        self.error == "insufficient_scope"
+    }
+}
+
+/// Cookie set by a `Set-Cookie` response header; see
+/// [`ResponseValue::cookies`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    /// The name of the cookie.
+    pub name: String,
+    /// The value of the cookie, as it should be sent back in a `Cookie`
+    /// header.
+    pub value: String,
+    /// The `Path` attribute, if given.
+    pub path: Option<String>,
+    /// The `Domain` attribute, if given.
+    pub domain: Option<String>,
+    /// The `Expires` attribute, if given as a valid HTTP date.
+    pub expires: Option<std::time::SystemTime>,
+    /// The `Max-Age` attribute in seconds, if given; zero or less means the
+    /// cookie has expired.
+    pub max_age: Option<i64>,
+    /// Whether the `Secure` attribute is present.
+    pub secure: bool,
+    /// Whether the `HttpOnly` attribute is present.
+    pub http_only: bool,
+    /// The `SameSite` attribute, e.g. `Lax`, if given.
+    pub same_site: Option<String>,
+}
+
+type ResponseFixup = dyn Fn(&mut serde_json::Value) + Send + Sync;
+
+/// Functions that adjust the JSON response bodies of operations, identified
+/// by their operation IDs, before they are deserialized.
+///
+/// This is an escape hatch for servers whose responses don't quite match
+/// their API description, e.g. a field that is sometimes a string and
+/// sometimes a number, that would otherwise force the generated types to be
+/// forked. Bodies of operations with fixups are parsed into an intermediate
+/// [`serde_json::Value`] rather than deserialized directly, which costs an
+/// extra allocation for each value in the body; other operations are
+/// unaffected.
+#[derive(Clone, Default)]
+pub struct ResponseFixups(
+    std::sync::Arc<
+        std::collections::HashMap<String, Vec<std::sync::Arc<ResponseFixup>>>,
+    >,
+);
+
+impl ResponseFixups {
+    /// Creates an empty set of fixups.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `fixup` for the operation `operation_id`; the fixups of an
+    /// operation are applied in the order they were added.
+    pub fn add<F>(&mut self, operation_id: &str, fixup: F)
+    where
+        F: Fn(&mut serde_json::Value) + Send + Sync + 'static,
+    {
+        std::sync::Arc::make_mut(&mut self.0)
+            .entry(operation_id.to_string())
+            .or_default()
+            .push(std::sync::Arc::new(fixup));
+    }
+
+    fn get(&self, operation_id: &str) -> &[std::sync::Arc<ResponseFixup>] {
+        self.0.get(operation_id).map_or(&[], Vec::as_slice)
+    }
+}
+
+impl std::fmt::Debug for ResponseFixups {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+/// Typed value returned by generated client methods.
+///
+/// This is used for successful responses and may appear in error responses
+/// generated from the server (see [`Error::ErrorResponse`])
+#[derive(Clone)]
+pub struct ResponseValue<T> {
+    inner: T,
+    status: reqwest::StatusCode,
+    headers: reqwest::header::HeaderMap,
+    url: Option<reqwest::Url>,
+    connection: ConnectionInfo,
+    /// The body as received, if it was retained; see [`RetainBodies`].
+    raw: Option<Bytes>,
+}
+
+/// Details of the connection that served a response.
+#[derive(Clone, Default)]
+struct ConnectionInfo {
+    #[cfg(all(
+        any(feature = "tls-info", feature = "tls-info-rustls"),
+        not(target_arch = "wasm32")
+    ))]
+    tls: Option<reqwest::tls::TlsInfo>,
+}
+
+impl ConnectionInfo {
+    #[cfg_attr(
+        not(all(
+            any(feature = "tls-info", feature = "tls-info-rustls"),
+            not(target_arch = "wasm32")
+        )),
+        allow(unused_variables)
+    )]
+    fn new(response: &reqwest::Response) -> Self {
+        Self {
+            #[cfg(all(
+                any(feature = "tls-info", feature = "tls-info-rustls"),
+                not(target_arch = "wasm32")
+            ))]
+            tls: response
+                .extensions()
+                .get::<reqwest::tls::TlsInfo>()
+                .cloned(),
+        }
+    }
+}
+
+impl<T: DeserializeOwned> ResponseValue<T> {
+    /// Deserializes the JSON body of `response`.
+    ///
+    /// If the `Content-Type` of the response isn't a JSON media type
+    /// (`application/json` or one with a `+json` suffix), as when a
+    /// misbehaving server produces an HTML error page with a `200 OK` status,
+    /// the body is left unread and the response is returned as
+    /// [`Error::UnexpectedResponse`] for inspection.
+    #[doc(hidden)]
+    pub async fn from_response<E: std::fmt::Debug>(
+        response: reqwest::Response,
+    ) -> Result<Self, Error<E>> {
+        Self::from_response_inspect(
+            response,
+            true,
+            |_, _| (),
+            serde_json::from_slice,
+        )
+        .await
+    }
+
+    /// Like `from_response`, but attempts to deserialize the body whatever
+    /// its `Content-Type`, for APIs that omit the header or label JSON bodies
+    /// with some other media type.
+    pub async fn from_response_lenient<E: std::fmt::Debug>(
+        response: reqwest::Response,
+    ) -> Result<Self, Error<E>> {
+        Self::from_response_inspect(
+            response,
+            false,
+            |_, _| (),
+            serde_json::from_slice,
+        )
+        .await
+    }
+
+    /// Like `from_response`, but reports the body to the hook set with
+    /// [`set_debug_hook`], and if `lenient` is set, decodes it whatever its
+    /// `Content-Type`, like `from_response_lenient`.
+    #[doc(hidden)]
+    pub async fn from_response_debug<E: std::fmt::Debug>(
+        response: reqwest::Response,
+        operation_id: &str,
+        lenient: bool,
+    ) -> Result<Self, Error<E>> {
+        Self::from_response_inspect(
+            response,
+            !lenient,
+            |status, full| debug_response_body(operation_id, status, full),
+            serde_json::from_slice,
+        )
+        .await
+    }
+
+    /// Like `from_response`, but applies the fixups registered for
+    /// `operation_id` to the body before deserializing it, reports the body
+    /// to the hook set with [`set_debug_hook`] if `debug` is set, and decodes
+    /// it whatever its `Content-Type` if `lenient` is set.
+    #[doc(hidden)]
+    pub async fn from_response_fixup<E: std::fmt::Debug>(
+        response: reqwest::Response,
+        operation_id: &str,
+        fixups: &ResponseFixups,
+        debug: bool,
+        lenient: bool,
+    ) -> Result<Self, Error<E>> {
+        Self::from_response_inspect(
+            response,
+            !lenient,
+            |status, full| {
+                if debug {
+                    debug_response_body(operation_id, status, full)
+                }
+            },
+            |full| {
+                let fixups = fixups.get(operation_id);
+                if fixups.is_empty() {
+                    return serde_json::from_slice(full);
+                }
+                serde_json::from_slice(full).and_then(
+                    |mut value: serde_json::Value| {
+                        fixups.iter().for_each(|fixup| fixup(&mut value));
+                        serde_json::from_value(value)
+                    },
+                )
+            },
+        )
+        .await
+    }
+
+    /// Like `from_response`, but decodes the body as MessagePack rather than
+    /// JSON, as for operations that produce `application/msgpack`.
+    ///
+    /// Decoding failures are reported as [`Error::InvalidResponseEncoding`]
+    /// with the error from `rmp-serde`.
+    #[cfg(feature = "msgpack")]
+    pub async fn from_response_msgpack<E: std::fmt::Debug>(
+        response: reqwest::Response,
+    ) -> Result<Self, Error<E>> {
+        Self::from_response_inspect(
+            response,
+            false,
+            |_, _| (),
+            |full| -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+                Ok(rmp_serde::from_slice(full)?)
+            },
+        )
+        .await
+    }
+
+    /// Like `from_response`, but decodes the body as CBOR rather than JSON,
+    /// as for operations that produce `application/cbor`.
+    ///
+    /// Decoding failures are reported as [`Error::InvalidResponseEncoding`]
+    /// with the error from `ciborium`.
+    #[cfg(feature = "cbor")]
+    pub async fn from_response_cbor<E: std::fmt::Debug>(
+        response: reqwest::Response,
+    ) -> Result<Self, Error<E>> {
+        Self::from_response_inspect(
+            response,
+            false,
+            |_, _| (),
+            |full| -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+                Ok(ciborium::from_reader(full)?)
+            },
+        )
+        .await
+    }
+
+    /// Reads the body of `response`, passes it to `inspect`, and decodes it
+    /// with `decode`. If `json` is set and the `Content-Type` of the response
+    /// isn't a JSON media type, the body is left unread and the response is
+    /// returned as [`Error::UnexpectedResponse`].
+    async fn from_response_inspect<E: std::fmt::Debug, D: PayloadError>(
+        mut response: reqwest::Response,
+        json: bool,
+        inspect: impl FnOnce(reqwest::StatusCode, &Bytes),
+        decode: impl FnOnce(&[u8]) -> Result<T, D>,
+    ) -> Result<Self, Error<E>> {
+        if json && !is_json_content_type(response.headers()) {
+            return Err(Error::UnexpectedResponse(response));
+        }
+        let _permit = take_permit(&mut response);
+        let recorded = take_recorded(&mut response);
+        let status = response.status();
+        let headers = response.headers().clone();
+        let url = Some(response.url().clone());
+        let connection = ConnectionInfo::new(&response);
+        let retain = response.extensions_mut().remove::<RetainBody>().is_some();
+        let full = response.bytes().await.map_err(Error::ResponseBodyError)?;
+        if let Some(recorded) = recorded {
+            recorded.body(&full);
+        }
+        let raw = retain.then(|| full.clone());
+        inspect(status, &full);
+        let inner = decode(&full).map_err(|e| e.into_error(full))?;
+
+        Ok(Self {
+            inner,
+            status,
+            headers,
+            url,
+            connection,
+            raw,
+        })
+    }
+}
+
+impl<T: DeserializeOwned + Serialize> ResponseValue<T> {
+    /// Like `from_response_fixup`, but if `strict` is set, a body with fields
+    /// that `T` doesn't describe is rejected as
+    /// [`Error::InvalidResponsePayload`], to catch drift between a server and
+    /// its API description.
+    ///
+    /// Unknown fields are found by comparing the body to `T` serialized
+    /// again, so fields whose value is `null` or empty aren't reported, as
+    /// types commonly omit them when serializing.
+    #[doc(hidden)]
+    pub async fn from_response_strict<E: std::fmt::Debug>(
+        response: reqwest::Response,
+        operation_id: &str,
+        fixups: Option<&ResponseFixups>,
+        debug: bool,
+        strict: bool,
+        lenient: bool,
+    ) -> Result<Self, Error<E>> {
+        Self::from_response_inspect(
+            response,
+            !lenient,
+            |status, full| {
+                if debug {
+                    debug_response_body(operation_id, status, full)
+                }
+            },
+            |full| -> Result<T, serde_json::Error> {
+                let mut value: serde_json::Value =
+                    serde_json::from_slice(full)?;
+                if let Some(fixups) = fixups {
+                    let fixups = fixups.get(operation_id);
+                    fixups.iter().for_each(|fixup| fixup(&mut value));
+                }
+                if !strict {
+                    return serde_json::from_value(value);
+                }
+                let inner: T = serde::Deserialize::deserialize(&value)?;
+                let known = serde_json::to_value(&inner)?;
+                match unknown_field(&value, &known) {
+                    None => Ok(inner),
+                    Some(path) => {
+                        Err(<serde_json::Error as serde::de::Error>::custom(
+                            format!("unknown field at {}", path),
+                        ))
+                    }
+                }
+            },
+        )
+        .await
+    }
+}
+
+/// Finds, as a JSON pointer, a field of `value` with no counterpart in
+/// `known`, ignoring fields that are `null` or empty.
+fn unknown_field(
+    value: &serde_json::Value,
+    known: &serde_json::Value,
+) -> Option<String> {
+    use serde_json::Value;
+
+    let is_empty = |value: &Value| match value {
+        Value::Null => true,
+        Value::Array(items) => items.is_empty(),
+        Value::Object(fields) => fields.is_empty(),
+        _ => false,
+    };
+    let escape = |key: &str| key.replace('~', "~0").replace('/', "~1");
+
+    match (value, known) {
+        (Value::Object(fields), Value::Object(known)) => {
+            fields.iter().find_map(|(key, value)| match known.get(key) {
+                None if is_empty(value) => None,
+                None => Some(format!("/{}", escape(key))),
+                Some(known) => unknown_field(value, known)
+                    .map(|path| format!("/{}{}", escape(key), path)),
+            })
+        }
+        (Value::Array(items), Value::Array(known)) => {
+            items.iter().zip(known).enumerate().find_map(
+                |(index, (value, known))| {
+                    unknown_field(value, known)
+                        .map(|path| format!("/{}{}", index, path))
+                },
+            )
+        }
+        _ => None,
+    }
+}
+
+impl ResponseValue<String> {
+    /// Reads the body of `response` as text, e.g. for operations that
+    /// produce `text/plain`.
+    ///
+    /// The body is decoded according to the `charset` parameter of its
+    /// `Content-Type` header: `UTF-8`, `US-ASCII`, and `ISO-8859-1` are
+    /// supported, and any other or missing charset is treated as UTF-8. A
+    /// body that isn't valid in its charset is reported as
+    /// [`Error::InvalidResponsePayload`].
+    pub async fn text<E: std::fmt::Debug>(
+        response: reqwest::Response,
+    ) -> Result<Self, Error<E>> {
+        let charset = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_content_type)
+            .and_then(|(_, mut params)| params.remove("charset"));
+        // Text bodies aren't JSON, so their Content-Type isn't checked.
+        Self::from_response_inspect(
+            response,
+            false,
+            |_, _| (),
+            |full| decode_text(full, charset.as_deref()),
+        )
+        .await
+    }
+}
+
+impl<T: DeserializeOwned + Default> ResponseValue<T> {
+    /// Deserializes the body of `response` like a generated client does, but
+    /// produces `T::default()` if the status is `204 No Content` or the body
+    /// is empty.
+    ///
+    /// This suits endpoints that sometimes omit a body for which there is a
+    /// sensible default, such as an empty list.
+    pub async fn from_response_or_default<E: std::fmt::Debug>(
+        mut response: reqwest::Response,
+    ) -> Result<Self, Error<E>> {
+        let _permit = take_permit(&mut response);
+        let recorded = take_recorded(&mut response);
+        let status = response.status();
+        let headers = response.headers().clone();
+        let url = Some(response.url().clone());
+        let connection = ConnectionInfo::new(&response);
+        let retain = response.extensions_mut().remove::<RetainBody>().is_some();
+        let full = response.bytes().await.map_err(Error::ResponseBodyError)?;
+        if let Some(recorded) = recorded {
+            recorded.body(&full);
+        }
+        let raw = retain.then(|| full.clone());
+        let inner =
+            if status == reqwest::StatusCode::NO_CONTENT || full.is_empty() {
+                T::default()
+            } else {
+                serde_json::from_slice(&full)
+                    .map_err(|e| Error::InvalidResponsePayload(full, e))?
+            };
+
+        Ok(Self {
+            inner,
+            status,
+            headers,
+            url,
+            connection,
+            raw,
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ResponseValue<reqwest::Upgraded> {
+    #[doc(hidden)]
+    pub async fn upgrade<E: std::fmt::Debug>(
+        response: reqwest::Response,
+    ) -> Result<Self, Error<E>> {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let url = Some(response.url().clone());
+        let connection = ConnectionInfo::new(&response);
+        if status == reqwest::StatusCode::SWITCHING_PROTOCOLS {
+            let inner =
+                response.upgrade().await.map_err(Error::InvalidUpgrade)?;
+
+            Ok(Self {
+                inner,
+                status,
+                headers,
+                url,
+                connection,
+                raw: None,
+            })
+        } else {
+            Err(Error::UnexpectedResponse(response))
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl ResponseValue<ByteStream> {
+    #[doc(hidden)]
+    pub fn stream(mut response: reqwest::Response) -> Self {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let url = Some(response.url().clone());
+        let connection = ConnectionInfo::new(&response);
+        let inner = match take_permit(&mut response) {
+            Some(permit) => ByteStream(Box::pin(HoldPermit {
+                inner: Box::pin(response.bytes_stream()),
+                permit: Some(permit),
+            })),
+            None => ByteStream(Box::pin(response.bytes_stream())),
+        };
+        Self {
+            inner,
+            status,
+            headers,
+            url,
+            connection,
+            raw: None,
+        }
+    }
+}
+
+impl ResponseValue<()> {
+    #[doc(hidden)]
+    pub fn empty(response: reqwest::Response) -> Self {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let url = Some(response.url().clone());
+        let connection = ConnectionInfo::new(&response);
+        // TODO is there anything we want to do to confirm that there is no
+        // content?
+        Self {
+            inner: (),
+            status,
+            headers,
+            url,
+            connection,
+            raw: None,
+        }
+    }
+}
+
+/// Body of a response received by a blocking client, which is read as it's
+/// consumed; see [`ResponseValue::stream_blocking`].
+///
+/// This is the blocking counterpart of [`ByteStream`], and implements
+/// [`std::io::Read`], e.g. to copy a download straight to a file with
+/// `std::io::copy`.
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub struct BlockingBody(reqwest::blocking::Response);
+
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+impl BlockingBody {
+    /// Consumes the `BlockingBody`, returning the response it reads from.
+    pub fn into_inner(self) -> reqwest::blocking::Response {
+        self.0
+    }
+}
+
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+impl std::io::Read for BlockingBody {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        std::io::Read::read(&mut self.0, buf)
+    }
+}
+
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+impl<T: DeserializeOwned> ResponseValue<T> {
+    /// Deserializes the JSON body of `response`, received by a blocking
+    /// client, like `from_response`, or like `from_response_lenient` if
+    /// `lenient` is set.
+    #[doc(hidden)]
+    pub fn from_response_blocking<E: std::fmt::Debug>(
+        response: reqwest::blocking::Response,
+        lenient: bool,
+    ) -> Result<Self, Error<E>> {
+        if !lenient && !is_json_content_type(response.headers()) {
+            return Err(Error::unexpected_blocking(response));
+        }
+        let status = response.status();
+        let headers = response.headers().clone();
+        let url = Some(response.url().clone());
+        let full = response.bytes().map_err(Error::ResponseBodyError)?;
+        let inner = serde_json::from_slice(&full)
+            .map_err(|e| Error::InvalidResponsePayload(full, e))?;
+
+        Ok(Self {
+            inner,
+            status,
+            headers,
+            url,
+            connection: ConnectionInfo::default(),
+            raw: None,
+        })
+    }
+}
+
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+impl ResponseValue<BlockingBody> {
+    #[doc(hidden)]
+    pub fn stream_blocking(response: reqwest::blocking::Response) -> Self {
+        Self {
+            status: response.status(),
+            headers: response.headers().clone(),
+            url: Some(response.url().clone()),
+            connection: ConnectionInfo::default(),
+            raw: None,
+            inner: BlockingBody(response),
+        }
+    }
+}
+
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+impl ResponseValue<()> {
+    #[doc(hidden)]
+    pub fn empty_blocking(response: reqwest::blocking::Response) -> Self {
+        Self {
+            inner: (),
+            status: response.status(),
+            headers: response.headers().clone(),
+            url: Some(response.url().clone()),
+            connection: ConnectionInfo::default(),
+            raw: None,
+        }
+    }
+}
+
+impl<T> ResponseValue<T> {
+    /// Creates a [`ResponseValue`] from the inner type, status, and headers.
+    ///
+    /// Useful for generating test fixtures.
+    pub fn new(
+        inner: T,
+        status: reqwest::StatusCode,
+        headers: reqwest::header::HeaderMap,
+    ) -> Self {
+        Self {
+            inner,
+            status,
+            headers,
+            url: None,
+            connection: ConnectionInfo::default(),
+            raw: None,
+        }
+    }
+
+    /// Consumes the ResponseValue, returning the wrapped value.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Consumes the ResponseValue, returning the wrapped value, status, and
+    /// headers; this is the inverse of [`ResponseValue::new`].
+    pub fn into_parts(
+        self,
+    ) -> (T, reqwest::StatusCode, reqwest::header::HeaderMap) {
+        (self.inner, self.status, self.headers)
+    }
+
+    /// Consumes the ResponseValue, returning the wrapped value unless `pred`
+    /// produces an error.
+    ///
+    /// This is useful for APIs that report failures in the body of a
+    /// successful response:
+    /// ```ignore
+    /// let widget = client
+    ///     .get_widget(id)
+    ///     .await?
+    ///     .into_result_if(|rv| rv.error.clone().map(MyError::Api))?;
+    /// ```
+    pub fn into_result_if<E2, F>(self, pred: F) -> Result<T, E2>
+    where
+        F: FnOnce(&ResponseValue<T>) -> Option<E2>,
+    {
+        match pred(&self) {
+            Some(e) => Err(e),
+            None => Ok(self.inner),
+        }
+    }
+
+    /// Gets the status from this response.
+    pub fn status(&self) -> reqwest::StatusCode {
+        self.status
+    }
+
+    /// Gets the headers from this response.
+    pub fn headers(&self) -> &reqwest::header::HeaderMap {
+        &self.headers
+    }
+
+    /// Gets the parsed value of the Content-Length header, if present and
+    /// valid.
+    pub fn content_length(&self) -> Option<u64> {
+        self.headers
+            .get(reqwest::header::CONTENT_LENGTH)?
+            .to_str()
+            .ok()?
+            .parse::<u64>()
+            .ok()
+    }
+
+    /// Returns how long the server asked clients to wait, from the
+    /// `Retry-After` header: on a successful response such as `202 Accepted`
+    /// for an asynchronous job, the interval at which to poll for its
+    /// completion.
+    ///
+    /// This parses the header as [`Error::retry_after`] does, and is `None`
+    /// if the header is absent or can't be parsed.
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        let value = self.headers.get(reqwest::header::RETRY_AFTER)?;
+        parse_retry_after(value.to_str().ok()?)
+    }
+
+    /// Gets the identifier the server assigned to this request, taken from
+    /// the `X-Request-ID` header or, failing that, an echoed
+    /// `X-Correlation-ID` header.
+    pub fn request_id(&self) -> Option<&str> {
+        ["x-request-id", CORRELATION_ID]
+            .iter()
+            .find_map(|name| self.headers.get(*name)?.to_str().ok())
+    }
+
+    /// Gets the idempotency key the server echoed in the `Idempotency-Key`
+    /// header, if any.
+    pub fn idempotency_key(&self) -> Option<&str> {
+        self.headers.get("idempotency-key")?.to_str().ok()
+    }
+
+    /// Indicates whether the server deduplicated this request and replayed
+    /// the response to an earlier request with the same idempotency key, as
+    /// reported by the `Idempotent-Replayed` header.
+    ///
+    /// This is `None` if the header is absent or isn't `true` or `false`.
+    pub fn idempotency_replayed(&self) -> Option<bool> {
+        let value = self.headers.get("idempotent-replayed")?.to_str().ok()?;
+        if value.trim().eq_ignore_ascii_case("true") {
+            Some(true)
+        } else if value.trim().eq_ignore_ascii_case("false") {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the metrics reported by the server in its `Server-Timing`
+    /// headers, such as time spent in a database or cache, in the order they
+    /// appear.
+    ///
+    /// Durations that aren't valid numbers are ignored, as are values that
+    /// aren't valid header strings.
+    pub fn server_timing(&self) -> Vec<ServerTiming> {
+        self.headers
+            .get_all("server-timing")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| split_header_list(value, ','))
+            .filter_map(parse_server_timing)
+            .collect()
+    }
+
+    /// Gets the cookies set by the `Set-Cookie` headers of the response, in
+    /// the order they appear.
+    ///
+    /// Headers without a `name=value` pair are ignored, as are unknown
+    /// attributes. The cookies can be sent back by joining their names and
+    /// values into a `Cookie` header.
+    pub fn cookies(&self) -> Vec<Cookie> {
+        self.headers
+            .get_all(reqwest::header::SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(parse_set_cookie)
+            .collect()
+    }
+
+    /// Gets the error reported by the `Bearer` challenge of the
+    /// `WWW-Authenticate` headers, typically on a `401 Unauthorized` or
+    /// `403 Forbidden` response.
+    ///
+    /// This is `None` if there is no `Bearer` challenge or it has no `error`
+    /// attribute, as when the request carried no token at all.
+    pub fn bearer_error(&self) -> Option<BearerError> {
+        self.headers
+            .get_all(reqwest::header::WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(parse_bearer_error)
+    }
+
+    /// Gets the time the response has spent in caches, from the `Age`
+    /// header.
+    pub fn age(&self) -> Option<std::time::Duration> {
+        let age = self.headers.get(reqwest::header::AGE)?.to_str().ok()?;
+        age.trim().parse().ok().map(std::time::Duration::from_secs)
+    }
+
+    /// Gets the time for which the response is fresh, from the `max-age`
+    /// directive of the `Cache-Control` header.
+    pub fn max_age(&self) -> Option<std::time::Duration> {
+        let (_, max_age) = self
+            .cache_directives()
+            .into_iter()
+            .find(|(name, _)| name == "max-age")?;
+        max_age.parse().ok().map(std::time::Duration::from_secs)
+    }
+
+    /// Gets the time at which the response was generated, from the `Date`
+    /// header.
+    pub fn header_date(&self) -> Option<std::time::SystemTime> {
+        let date = self.headers.get(reqwest::header::DATE)?.to_str().ok()?;
+        parse_http_date(date)
+    }
+
+    /// Indicates whether the response is stale, i.e. should be revalidated
+    /// before it's reused, following RFC 9111 as it applies to a private
+    /// cache.
+    ///
+    /// The freshness lifetime is taken from `Cache-Control: max-age` or
+    /// failing that from `Expires` relative to `Date`, and compared with the
+    /// age of the response, the greater of its `Age` header and the time
+    /// since its `Date`. A `no-cache` directive or an invalid `Expires` makes
+    /// the response stale. This is `None` if there isn't enough information
+    /// to decide, such as when there is no freshness lifetime or neither
+    /// `Age` nor `Date` is present; no heuristic lifetime is assumed.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_stale(&self) -> Option<bool> {
+        if self
+            .cache_directives()
+            .iter()
+            .any(|(name, _)| name == "no-cache")
+        {
+            return Some(true);
+        }
+
+        let date = self.header_date();
+        let lifetime = match self.max_age() {
+            Some(max_age) => max_age,
+            None => {
+                let expires = self.headers.get(reqwest::header::EXPIRES)?;
+                let Some(expires) =
+                    expires.to_str().ok().and_then(parse_http_date)
+                else {
+                    return Some(true);
+                };
+                expires.duration_since(date?).unwrap_or_default()
+            }
+        };
+
+        let now = std::time::SystemTime::now();
+        let apparent_age =
+            date.map(|date| now.duration_since(date).unwrap_or_default());
+        let age = match (self.age(), apparent_age) {
+            (Some(age), Some(apparent_age)) => age.max(apparent_age),
+            (age, apparent_age) => age.or(apparent_age)?,
+        };
+        Some(age >= lifetime)
+    }
+
+    /// Gets the directives of the `Cache-Control` headers, with lower-cased
+    /// names and unquoted values.
+    fn cache_directives(&self) -> Vec<(String, String)> {
+        self.headers
+            .get_all(reqwest::header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| split_header_list(value, ','))
+            .map(|directive| match directive.split_once('=') {
+                Some((name, value)) => (
+                    name.trim().to_ascii_lowercase(),
+                    unquote_header_value(value),
+                ),
+                None => (directive.trim().to_ascii_lowercase(), String::new()),
+            })
+            .collect()
+    }
+
+    /// Gets the body of this response as received, if it was retained by
+    /// [`RetainBodies`].
+    pub fn raw_body(&self) -> Option<&Bytes> {
+        self.raw.as_ref()
+    }
+
+    /// Gets the number of body bytes retained by this value, or `None` if
+    /// the body wasn't retained; see [`RetainBodies`].
+    ///
+    /// This accounts only for the retained body, not for the decoded value
+    /// or the headers, and is meant for gauging the memory cost of
+    /// retention.
+    pub fn retained_len(&self) -> Option<usize> {
+        self.raw.as_ref().map(Bytes::len)
+    }
+
+    /// Gets information about the TLS connection that served this response.
+    ///
+    /// This is `None` for plaintext connections, for values constructed with
+    /// [`ResponseValue::new`], and unless the `reqwest::Client` was built with
+    /// TLS info enabled (see [`ClientBuilder::tls_info`]). Note that `reqwest`
+    /// exposes only the peer certificate, not the negotiated version or cipher.
+    #[cfg(all(
+        any(feature = "tls-info", feature = "tls-info-rustls"),
+        not(target_arch = "wasm32")
+    ))]
+    pub fn tls_info(&self) -> Option<&reqwest::tls::TlsInfo> {
+        self.connection.tls.as_ref()
+    }
+
+    /// Gets the final URL of this response, after any redirects were
+    /// followed.
+    ///
+    /// This is `None` for values constructed with [`ResponseValue::new`].
+    pub fn final_url(&self) -> Option<&reqwest::Url> {
+        self.url.as_ref()
+    }
+
+    /// Gets the target of the `Location` header, e.g. of a redirect or of the
+    /// resource created by a `201 Created` response, if present.
+    ///
+    /// A relative target is resolved against the final URL of the response;
+    /// if that's not available, only an absolute target is returned.
+    pub fn location(&self) -> Option<reqwest::Url> {
+        let target =
+            self.headers.get(reqwest::header::LOCATION)?.to_str().ok()?;
+        match &self.url {
+            Some(base) => base.join(target).ok(),
+            None => reqwest::Url::parse(target).ok(),
+        }
+    }
+
+    /// Gets the target of the `Link` header entry with the given relation
+    /// type (e.g. `next`, `prev`, `first`, or `last`), if present.
+    ///
+    /// Relative targets are resolved against the final URL of the response;
+    /// if that's not available, only absolute targets are returned.
+    pub fn link(&self, rel: &str) -> Option<reqwest::Url> {
+        self.headers
+            .get_all(reqwest::header::LINK)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| split_header_list(value, ','))
+            .find_map(|link| {
+                let (target, params) =
+                    link.trim().strip_prefix('<')?.split_once('>')?;
+                let matched =
+                    parse_header_params(params).iter().any(|(name, value)| {
+                        name == "rel"
+                            && value
+                                .split_ascii_whitespace()
+                                .any(|r| r.eq_ignore_ascii_case(rel))
+                    });
+                if !matched {
+                    return None;
+                }
+                match &self.url {
+                    Some(base) => base.join(target).ok(),
+                    None => reqwest::Url::parse(target).ok(),
+                }
+            })
+    }
+
+    /// Gets the filename suggested by the `Content-Disposition` header, if
+    /// present.
+    ///
+    /// The extended `filename*` parameter (RFC 5987) is preferred over the
+    /// plain `filename` parameter when both are present. Note that the value
+    /// comes from the server and should be sanitized before it's used as a
+    /// path.
+    pub fn suggested_filename(&self) -> Option<String> {
+        let value = self
+            .headers
+            .get(reqwest::header::CONTENT_DISPOSITION)?
+            .to_str()
+            .ok()?;
+        content_disposition_filename(value)
+    }
+
+    /// Gets the media type of the `Content-Type` header, such as
+    /// `application/json`, without its parameters; see
+    /// [`ResponseValue::content_type_params`].
+    ///
+    /// The media type is lower-cased, since it's case-insensitive. It's
+    /// `None` if the header is absent or malformed.
+    pub fn content_type(&self) -> Option<String> {
+        let value = self.headers.get(reqwest::header::CONTENT_TYPE)?;
+        let (essence, _) = parse_content_type(value.to_str().ok()?)?;
+        Some(essence)
+    }
+
+    /// Gets the parameters of the `Content-Type` header, such as `charset`,
+    /// the `boundary` of a multipart body, or the `version` of a vendor media
+    /// type.
+    ///
+    /// Parameter names are lower-cased, since they're case-insensitive, and
+    /// quoted values are unquoted. The map is empty if the header is absent
+    /// or malformed.
+    pub fn content_type_params(
+        &self,
+    ) -> std::collections::HashMap<String, String> {
+        self.headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_content_type)
+            .map(|(_, params)| params)
+            .unwrap_or_default()
+    }
+
+    /// Converts the wrapped value with a fallible function, preserving the
+    /// status and headers on success.
+    ///
+    /// This is useful for checking invariants that the API description can't
+    /// express while converting into a domain type.
+    pub fn validate<U, E2, F>(self, f: F) -> Result<ResponseValue<U>, E2>
+    where
+        F: FnOnce(T) -> Result<U, E2>,
+    {
+        let Self {
+            inner,
+            status,
+            headers,
+            url,
+            connection,
+            raw,
+        } = self;
+
+        Ok(ResponseValue {
+            inner: f(inner)?,
+            status,
+            headers,
+            url,
+            connection,
+            raw,
+        })
+    }
+
+    #[doc(hidden)]
+    pub fn map<U: std::fmt::Debug, F, E>(
+        self,
+        f: F,
+    ) -> Result<ResponseValue<U>, E>
+    where
+        F: FnOnce(T) -> U,
+    {
+        let Self {
+            inner,
+            status,
+            headers,
+            url,
+            connection,
+            raw,
+        } = self;
+
+        Ok(ResponseValue {
+            inner: f(inner),
+            status,
+            headers,
+            url,
+            connection,
+            raw,
+        })
+    }
+
+    /// Transforms the body with a fallible function, keeping the status and
+    /// headers on success just as [`ResponseValue::map`] does; an error from
+    /// `f` is returned as is.
+    ///
+    /// This is the same conversion as [`ResponseValue::validate`], with its
+    /// type parameters in the order of [`ResponseValue::map`].
+    pub fn try_map<U, F, E2>(self, f: F) -> Result<ResponseValue<U>, E2>
+    where
+        F: FnOnce(T) -> Result<U, E2>,
+    {
+        self.validate(f)
+    }
+
+    /// Transforms the body with an async function, e.g. one that decrypts a
+    /// field by calling out to a key management service, keeping the status
+    /// and headers just as [`ResponseValue::map`] does.
+    pub async fn map_async<U, F, Fut, E>(
+        self,
+        f: F,
+    ) -> Result<ResponseValue<U>, E>
+    where
+        F: FnOnce(T) -> Fut,
+        Fut: std::future::Future<Output = U>,
+    {
+        let Self {
+            inner,
+            status,
+            headers,
+            url,
+            connection,
+            raw,
+        } = self;
+
+        Ok(ResponseValue {
+            inner: f(inner).await,
+            status,
+            headers,
+            url,
+            connection,
+            raw,
+        })
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T: DeserializeOwned, E> ResponseValue<JsonArrayStream<T, E>> {
+    #[doc(hidden)]
+    pub fn json_array_stream(response: reqwest::Response) -> Self {
+        let ResponseValue {
+            inner,
+            status,
+            headers,
+            url,
+            connection,
+            raw,
+        } = ResponseValue::stream(response);
+        Self {
+            inner: inner.json_array_stream(),
+            status,
+            headers,
+            url,
+            connection,
+            raw,
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl ResponseValue<ByteStream> {
+    /// Consumes the `ResponseValue`, returning the wrapped [`Stream`].
+    pub fn into_inner_stream(self) -> InnerByteStream {
+        self.into_inner().into_inner()
+    }
+
+    /// Collects the body like [`ByteStream::collect_with_limit`], reserving
+    /// space for it up front according to its `Content-Length` header. The
+    /// header isn't trusted: the limit applies however long the body
+    /// actually is.
+    pub async fn collect_with_limit(
+        self,
+        max: usize,
+    ) -> Result<Bytes, CollectError> {
+        let reserve = self
+            .content_length()
+            .map_or(0, |len| usize::try_from(len).unwrap_or(usize::MAX));
+        self.into_inner().collect_limited(max, reserve).await
+    }
+
+    /// Consumes the `ResponseValue`, collecting the whole body into a single
+    /// buffer.
+    ///
+    /// Space is reserved up front according to the `Content-Length` header,
+    /// though no more than 64 MiB as the header isn't trusted; failures of
+    /// the underlying stream are reported as [`Error::CommunicationError`].
+    /// Use [`ResponseValue::collect_with_limit`] for bodies that may be
+    /// unreasonably large.
+    pub async fn into_bytes(self) -> Result<Bytes, Error<()>> {
+        const MAX_RESERVE: usize = 64 << 20;
+        let reserve = self.content_length().map_or(0, |len| {
+            usize::try_from(len).unwrap_or(usize::MAX).min(MAX_RESERVE)
+        });
+        let mut stream = self.into_inner();
+        let mut buf = bytes::BytesMut::with_capacity(reserve);
+        while let Some(chunk) = stream.next_chunk().await {
+            buf.extend_from_slice(&chunk.map_err(Error::CommunicationError)?);
+        }
+        Ok(buf.freeze())
+    }
+
+    /// Consumes the `ResponseValue`, parsing its body as newline-delimited
+    /// JSON like [`ByteStream::json_lines`].
+    ///
+    /// Failures of the underlying stream are reported as
+    /// [`Error::CommunicationError`], as with [`ResponseValue::into_bytes`].
+    pub fn into_json_lines<T>(self) -> JsonLinesStream<T, ()>
+    where
+        T: DeserializeOwned,
+    {
+        JsonLinesStream {
+            body_error: Error::CommunicationError,
+            ..self.into_inner().json_lines()
+        }
+    }
+
+    /// Consumes the `ResponseValue`, returning its body decompressed
+    /// according to its `Content-Encoding` header.
+    ///
+    /// This is for clients whose `reqwest::Client` doesn't decompress
+    /// responses itself: they can choose between the body as it was sent
+    /// (e.g. to store it compressed) via [`ResponseValue::into_inner`], and
+    /// decompressed via this method. The `gzip` and `deflate` encodings are
+    /// supported, as are a missing or `identity` encoding; for any other
+    /// encoding, the `ResponseValue` is returned unchanged as the error.
+    ///
+    /// The resulting stream ends after the first error, whether from the
+    /// underlying stream or from malformed compressed data.
+    #[cfg(feature = "decompression")]
+    pub fn decoded(
+        self,
+    ) -> Result<
+        impl Stream<Item = Result<Bytes, ChunkError<std::io::Error>>>,
+        Self,
+    > {
+        self.decode_body(false)
+    }
+
+    /// Like [`ResponseValue::decoded`], but tolerates servers that claim an
+    /// encoding they didn't apply: if the body can't be decoded before any
+    /// decoded data has been produced, it is passed through unchanged and a
+    /// warning is logged.
+    ///
+    /// Errors in data that follows successfully decoded data are reported as
+    /// they are by `decoded`. A body that is compressed without a
+    /// `Content-Encoding` header is likewise passed through unchanged.
+    #[cfg(feature = "decompression")]
+    pub fn decompressed_lenient(
+        self,
+    ) -> Result<
+        impl Stream<Item = Result<Bytes, ChunkError<std::io::Error>>>,
+        Self,
+    > {
+        self.decode_body(true)
+    }
+
+    #[cfg(feature = "decompression")]
+    fn decode_body(self, lenient: bool) -> Result<Decoded, Self> {
+        let encoding = self
+            .headers
+            .get(reqwest::header::CONTENT_ENCODING)
+            .map(|value| value.to_str().ok());
+        let decoder = match encoding {
+            None => ContentDecoder::new(None),
+            Some(Some(encoding)) => ContentDecoder::new(Some(encoding)),
+            Some(None) => None,
+        };
+        match decoder {
+            Some(decoder) => Ok(Decoded {
+                inner: Some(self.into_inner_stream()),
+                decoder,
+                pending: lenient.then(bytes::BytesMut::new),
+            }),
+            None => Err(self),
+        }
+    }
+}
+
+/// A value in a response body that does not conform to its schema.
+#[cfg(feature = "validation")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value within the body.
+    pub path: String,
+    /// Description of the violation.
+    pub message: String,
+}
+
+#[cfg(feature = "validation")]
+impl<T: Serialize> ResponseValue<T> {
+    /// Validates the body against a JSON Schema, such as the constants in the
+    /// `response_schemas` module of a client generated with response schemas
+    /// enabled:
+    /// ```ignore
+    /// let schema = serde_json::from_str(response_schemas::GET_WIDGET)?;
+    /// let widget = client.get_widget(id).await?;
+    /// widget.json_schema_validate(&schema)?;
+    /// ```
+    ///
+    /// The body is re-serialized for validation, so fields that the generated
+    /// types don't capture are not checked.
+    pub fn json_schema_validate(
+        &self,
+        schema: &serde_json::Value,
+    ) -> Result<(), Vec<SchemaViolation>> {
+        let violation = |message: String| {
+            vec![SchemaViolation {
+                path: String::new(),
+                message,
+            }]
+        };
+        let instance = serde_json::to_value(&self.inner)
+            .map_err(|e| violation(e.to_string()))?;
+        let compiled = jsonschema::JSONSchema::compile(schema)
+            .map_err(|e| violation(format!("invalid schema: {}", e)))?;
+
+        let violations = match compiled.validate(&instance) {
+            Ok(()) => return Ok(()),
+            Err(errors) => errors
+                .map(|e| SchemaViolation {
+                    path: e.instance_path.to_string(),
+                    message: e.to_string(),
+                })
+                .collect(),
+        };
+        Err(violations)
+    }
+}
+
+impl<T> Deref for ResponseValue<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for ResponseValue<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<T> AsRef<T> for ResponseValue<T> {
+    fn as_ref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for ResponseValue<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// An RFC 7807 problem details object, the body of an
+/// `application/problem+json` response.
+///
+/// Generated clients use it as the error type of operations whose
+/// `application/problem+json` responses have no schema. Members beyond the
+/// standard ones are gathered into `extensions`.
+#[derive(
+    Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize,
+)]
+pub struct ProblemDetails {
+    /// A URI reference that identifies the problem type; `about:blank` is
+    /// implied when it's absent.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    /// A short summary of the problem type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The status code generated by the origin server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// An explanation specific to this occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// A URI reference that identifies this occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Any other members of the object.
+    #[serde(flatten)]
+    pub extensions: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Error produced by generated client methods.
+///
+/// The type parameter may be a struct if there's a single expected error type
+/// or an enum if there are multiple valid error types. It can be the unit type
+/// if there are no structured returns expected.
+pub enum Error<E = ()> {
+    /// The request did not conform to API requirements.
+    InvalidRequest(String),
+
+    /// A server error either due to the data, or with the connection.
+    CommunicationError(reqwest::Error),
+
+    /// An expected response when upgrading connection.
+    InvalidUpgrade(reqwest::Error),
+
+    /// A documented, expected error response.
+    ErrorResponse(ResponseValue<E>),
+
+    /// Encountered an error reading the body for an expected response.
+    ResponseBodyError(reqwest::Error),
+
+    /// An expected response code whose deserialization failed, with the body
+    /// that was received.
+    ///
+    /// The error's display includes a preview of the body, truncated to 256
+    /// bytes. Decoding errors from streamed bodies carry only the item that
+    /// failed; the status and headers of the response, which aren't kept
+    /// here, can be recorded with [`RecordRequests`].
+    InvalidResponsePayload(Bytes, serde_json::Error),
+
+    /// An expected response code whose body, in an encoding other than JSON
+    /// such as MessagePack or CBOR, couldn't be decoded, with the body that
+    /// was received.
+    ///
+    /// The error's display includes a preview of the body, truncated to 256
+    /// bytes.
+    InvalidResponseEncoding(Bytes, Box<dyn std::error::Error + Send + Sync>),
+
+    /// A response not listed in the API description. This may represent a
+    /// success or failure response; check `status().is_success()`.
+    ///
+    /// The body is left unread, so it isn't displayed; see
+    /// [`Error::into_unexpected_with_body`].
+    UnexpectedResponse(reqwest::Response),
+
+    /// An [`Error::UnexpectedResponse`] whose body has been read by
+    /// [`Error::into_unexpected_with_body`].
+    ///
+    /// The error's display includes a preview of the body, truncated to 256
+    /// bytes.
+    UnexpectedResponseBody {
+        /// The status of the response.
+        status: reqwest::StatusCode,
+        /// The headers of the response.
+        headers: reqwest::header::HeaderMap,
+        /// The body of the response.
+        body: Bytes,
+    },
+
+    /// An error occurred in the processing of a request pre-hook.
+    PreHookError(String),
+
+    /// A custom [`Transport`] failed to execute the request.
+    TransportError(TransportError),
+}
+
+impl<E> Error<E> {
+    /// Returns the status code, if the error was generated from a response.
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        match self {
+            Error::InvalidRequest(_) => None,
+            Error::PreHookError(_) => None,
+            Error::CommunicationError(e) => e.status(),
+            Error::ErrorResponse(rv) => Some(rv.status()),
+            Error::InvalidUpgrade(e) => e.status(),
+            Error::ResponseBodyError(e) => e.status(),
+            Error::InvalidResponsePayload(_, _) => None,
+            Error::InvalidResponseEncoding(_, _) => None,
             Error::UnexpectedResponse(r) => Some(r.status()),
+            Error::UnexpectedResponseBody { status, .. } => Some(*status),
+            Error::TransportError(_) => None,
+        }
+    }
+
+    /// Returns true if the server rejected the request because its body was
+    /// too large (`413 Payload Too Large`), in which case the request may
+    /// succeed if the payload is shrunk or split.
+    pub fn is_payload_too_large(&self) -> bool {
+        self.status() == Some(reqwest::StatusCode::PAYLOAD_TOO_LARGE)
+    }
+
+    /// Returns how long the server asked clients to wait before retrying,
+    /// typically on a `429 Too Many Requests` or `503 Service Unavailable`
+    /// response, from its `Retry-After` header.
+    ///
+    /// Both the delay-seconds form (`Retry-After: 120`) and the HTTP-date
+    /// form are supported; a date in the past produces a zero duration. This
+    /// is `None` if the error didn't come from a response, or the header is
+    /// absent or can't be parsed.
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        let headers = match self {
+            Error::ErrorResponse(rv) => rv.headers(),
+            Error::UnexpectedResponse(r) => r.headers(),
+            Error::UnexpectedResponseBody { headers, .. } => headers,
+            _ => return None,
+        };
+        let value = headers.get(reqwest::header::RETRY_AFTER)?;
+        parse_retry_after(value.to_str().ok()?)
+    }
+
+    /// Returns true if the server rejected the request as semantically
+    /// invalid (`422 Unprocessable Entity`), typically with field-level
+    /// errors that [`Error::validation_errors`] can extract.
+    pub fn is_validation_error(&self) -> bool {
+        self.status() == Some(reqwest::StatusCode::UNPROCESSABLE_ENTITY)
+    }
+
+    /// Returns true if the connection failed while establishing TLS, for
+    /// example because the server's certificate expired, didn't match the
+    /// host name, or wasn't signed by a trusted authority, or because no
+    /// common protocol version could be negotiated.
+    ///
+    /// `reqwest` doesn't classify TLS failures, so this inspects the chain of
+    /// underlying errors; [`Error::tls_error_message`] gives the description
+    /// from the TLS implementation.
+    pub fn is_tls_error(&self) -> bool {
+        self.tls_error_message().is_some()
+    }
+
+    /// Returns the TLS implementation's description of a failure to
+    /// establish TLS, if that is what caused this error.
+    pub fn tls_error_message(&self) -> Option<String> {
+        match self {
+            Error::CommunicationError(e) => tls_failure(e),
+            _ => None,
+        }
+    }
+
+    /// Creates an [`Error::ErrorResponse`] from its parts, just as
+    /// [`ResponseValue::new`] does for success responses.
+    ///
+    /// This is useful for testing code that handles typed error responses.
+    pub fn error_response(
+        value: E,
+        status: reqwest::StatusCode,
+        headers: reqwest::header::HeaderMap,
+    ) -> Self {
+        Error::ErrorResponse(ResponseValue::new(value, status, headers))
+    }
+
+    /// Reports a response received by a blocking client as
+    /// [`Error::UnexpectedResponse`], reading its body so that it can be held
+    /// as a `reqwest::Response`.
+    ///
+    /// The final URL of the response isn't preserved. If the body can't be
+    /// read, the error is reported as [`Error::ResponseBodyError`] instead.
+    #[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+    #[doc(hidden)]
+    pub fn unexpected_blocking(response: reqwest::blocking::Response) -> Self {
+        let mut builder = http::Response::builder()
+            .status(response.status())
+            .version(response.version());
+        if let Some(headers) = builder.headers_mut() {
+            *headers = response.headers().clone();
+        }
+        match response.bytes() {
+            Ok(body) => Error::UnexpectedResponse(
+                builder.body(body).expect("valid response parts").into(),
+            ),
+            Err(e) => Error::ResponseBodyError(e),
+        }
+    }
+
+    /// Converts an error produced by a [`Transport`].
+    ///
+    /// Errors from `reqwest` are reported as [`Error::CommunicationError`]
+    /// just as they are for clients that don't use a custom transport.
+    pub fn from_transport(e: TransportError) -> Self {
+        match e.downcast::<reqwest::Error>() {
+            Ok(e) => Error::CommunicationError(*e),
+            Err(e) => Error::TransportError(e),
         }
     }
 
@@ -288,19 +4363,346 @@ impl<E> Error<E> {
                 inner: _,
                 status,
                 headers,
+                url,
+                connection,
+                raw,
             }) => Error::ErrorResponse(ResponseValue {
                 inner: (),
                 status,
                 headers,
+                url,
+                connection,
+                raw,
             }),
             Error::InvalidUpgrade(e) => Error::InvalidUpgrade(e),
             Error::ResponseBodyError(e) => Error::ResponseBodyError(e),
             Error::InvalidResponsePayload(b, e) => {
                 Error::InvalidResponsePayload(b, e)
             }
+            Error::InvalidResponseEncoding(b, e) => {
+                Error::InvalidResponseEncoding(b, e)
+            }
             Error::UnexpectedResponse(r) => Error::UnexpectedResponse(r),
+            Error::UnexpectedResponseBody {
+                status,
+                headers,
+                body,
+            } => Error::UnexpectedResponseBody {
+                status,
+                headers,
+                body,
+            },
+            Error::TransportError(e) => Error::TransportError(e),
+        }
+    }
+
+    /// Reads the body of an [`Error::UnexpectedResponse`], producing an
+    /// [`Error::UnexpectedResponseBody`] that includes the body when it's
+    /// displayed; any other error is returned unchanged.
+    ///
+    /// If the body can't be read, the error is reported as
+    /// [`Error::ResponseBodyError`] instead.
+    /// ```ignore
+    /// if let Err(e) = client.get_widget(id).await {
+    ///     eprintln!("{}", e.into_unexpected_with_body().await);
+    /// }
+    /// ```
+    pub async fn into_unexpected_with_body(self) -> Self {
+        let Error::UnexpectedResponse(response) = self else {
+            return self;
+        };
+        let status = response.status();
+        let headers = response.headers().clone();
+        match response.bytes().await {
+            Ok(body) => Error::UnexpectedResponseBody {
+                status,
+                headers,
+                body,
+            },
+            Err(e) => Error::ResponseBodyError(e),
+        }
+    }
+
+    /// Takes the documented error response, with its status and headers, if
+    /// this is an [`Error::ErrorResponse`].
+    pub fn into_error_response(self) -> Option<ResponseValue<E>> {
+        match self {
+            Error::ErrorResponse(rv) => Some(rv),
+            _ => None,
         }
     }
+
+    /// Takes the typed body of a documented error response, if this is an
+    /// [`Error::ErrorResponse`].
+    pub fn error_body(self) -> Option<E> {
+        self.into_error_response().map(ResponseValue::into_inner)
+    }
+
+    /// Attaches a human-readable note, such as the operation that failed and
+    /// what it was for, that is displayed ahead of this error.
+    pub fn with_note(self, note: impl Into<String>) -> NotedError<E> {
+        NotedError {
+            note: note.into(),
+            error: self,
+        }
+    }
+}
+
+impl<E: Serialize> Error<E> {
+    /// Produces a structured representation of this error for JSON logging.
+    ///
+    /// The value is an object with the name of the variant (`kind`), the
+    /// status code (`status`) if the error came from a response, and a
+    /// human-readable `message`. Documented error responses also include their
+    /// serialized `body`. Header values are never included as they may carry
+    /// credentials.
+    pub fn to_log_value(&self) -> serde_json::Value {
+        let (kind, message) = match self {
+            Error::InvalidRequest(s) => ("InvalidRequest", s.clone()),
+            Error::CommunicationError(e) => {
+                ("CommunicationError", e.to_string())
+            }
+            Error::InvalidUpgrade(e) => ("InvalidUpgrade", e.to_string()),
+            Error::ErrorResponse(rv) => {
+                ("ErrorResponse", format!("error response: {}", rv.status))
+            }
+            Error::ResponseBodyError(e) => ("ResponseBodyError", e.to_string()),
+            Error::InvalidResponsePayload(_, e) => {
+                ("InvalidResponsePayload", e.to_string())
+            }
+            Error::InvalidResponseEncoding(_, e) => {
+                ("InvalidResponseEncoding", e.to_string())
+            }
+            Error::UnexpectedResponse(r) => (
+                "UnexpectedResponse",
+                format!("unexpected response: {}", r.status()),
+            ),
+            Error::UnexpectedResponseBody { status, .. } => (
+                "UnexpectedResponseBody",
+                format!("unexpected response: {}", status),
+            ),
+            Error::PreHookError(s) => ("PreHookError", s.clone()),
+            Error::TransportError(e) => ("TransportError", e.to_string()),
+        };
+
+        let mut value = serde_json::Map::new();
+        value.insert("kind".to_string(), kind.into());
+        if let Some(status) = self.status() {
+            value.insert("status".to_string(), status.as_u16().into());
+        }
+        value.insert("message".to_string(), message.into());
+        if let Error::ErrorResponse(rv) = self {
+            if let Ok(body) = serde_json::to_value(&rv.inner) {
+                value.insert("body".to_string(), body);
+            }
+        }
+
+        serde_json::Value::Object(value)
+    }
+}
+
+impl<E: Serialize> Error<E> {
+    /// Extracts the field-level errors from the body of a documented
+    /// `422 Unprocessable Entity` response; see [`ValidationErrors`] for the
+    /// body shapes that are recognized.
+    pub fn validation_errors(&self) -> Option<ValidationErrors> {
+        match self {
+            Error::ErrorResponse(rv) if self.is_validation_error() => {
+                ValidationErrors::from_json(
+                    &serde_json::to_value(&rv.inner).ok()?,
+                )
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Field-level validation errors, as typically found in the body of a
+/// `422 Unprocessable Entity` response; see [`Error::validation_errors`].
+///
+/// The shape of such bodies varies between frameworks; these are recognized:
+/// - JSON:API, `{"errors": [{"source": {"pointer": "/data/attributes/name"},
+///   "detail": "..."}]}`
+/// - Rails, `{"errors": {"name": ["..."]}}` or just `{"name": ["..."]}`
+/// - FastAPI, `{"detail": [{"loc": ["body", "name"], "msg": "..."}]}`
+/// - lists of `{"field": "name", "message": "..."}` under `errors`
+///
+/// Errors that don't relate to a particular field are listed under the
+/// empty string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationErrors {
+    /// The error messages for each field, by field name.
+    pub field_errors: std::collections::BTreeMap<String, Vec<String>>,
+}
+
+impl ValidationErrors {
+    /// Extracts validation errors from a response body, or returns `None` if
+    /// its shape isn't recognized.
+    pub fn from_json(body: &serde_json::Value) -> Option<Self> {
+        let mut errors = Self::default();
+        match body.get("errors").or_else(|| body.get("detail")) {
+            Some(serde_json::Value::Array(items)) => {
+                for item in items {
+                    let (field, message) = validation_error_item(item)?;
+                    errors.push(field, message);
+                }
+            }
+            Some(serde_json::Value::Object(fields)) => {
+                errors.extend_fields(fields)?
+            }
+            Some(_) => return None,
+            None => errors.extend_fields(body.as_object()?)?,
+        }
+        (!errors.field_errors.is_empty()).then_some(errors)
+    }
+
+    fn push(&mut self, field: String, message: String) {
+        self.field_errors.entry(field).or_default().push(message);
+    }
+
+    /// Adds Rails-style errors, each field mapped to a message or a list of
+    /// messages.
+    fn extend_fields(
+        &mut self,
+        fields: &serde_json::Map<String, serde_json::Value>,
+    ) -> Option<()> {
+        for (field, messages) in fields {
+            let field = if field == "base" { "" } else { field };
+            match messages {
+                serde_json::Value::String(message) => {
+                    self.push(field.to_string(), message.clone())
+                }
+                serde_json::Value::Array(messages) => {
+                    for message in messages {
+                        self.push(field.to_string(), message.as_str()?.into());
+                    }
+                }
+                _ => return None,
+            }
+        }
+        Some(())
+    }
+}
+
+/// Gets the field and message of an element of an array of errors, in any of
+/// the shapes recognized by [`ValidationErrors::from_json`].
+fn validation_error_item(item: &serde_json::Value) -> Option<(String, String)> {
+    let text = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| item.get(*name).and_then(|v| v.as_str()))
+            .map(str::to_string)
+    };
+    let message = text(&["detail", "msg", "message", "title", "code"])?;
+
+    let field = if let Some(source) = item.get("source") {
+        // JSON:API identifies the field with a JSON pointer to the request
+        // document, or the name of a query parameter.
+        let pointer = source.get("pointer").and_then(|v| v.as_str());
+        let parameter = source.get("parameter").and_then(|v| v.as_str());
+        match (pointer, parameter) {
+            (Some(pointer), _) => pointer
+                .strip_prefix("/data/attributes/")
+                .or_else(|| pointer.strip_prefix('/'))
+                .unwrap_or(pointer)
+                .to_string(),
+            (None, Some(parameter)) => parameter.to_string(),
+            (None, None) => String::new(),
+        }
+    } else if let Some(loc) = item.get("loc").and_then(|v| v.as_array()) {
+        // FastAPI locations start with where the value came from.
+        let loc = match loc.first().and_then(|v| v.as_str()) {
+            Some("body" | "query" | "path" | "header" | "cookie") => &loc[1..],
+            _ => &loc[..],
+        };
+        loc.iter()
+            .map(|part| match part {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(".")
+    } else {
+        text(&["field"]).unwrap_or_default()
+    };
+
+    Some((field, message))
+}
+
+impl<E> Error<E> {
+    /// Returns true if this error is likely transient, so that the request
+    /// may succeed if it's retried: a connection failure or timeout, or a
+    /// response, documented or not, with the status 408 Request Timeout, 425
+    /// Too Early, 429 Too Many Requests, or any 5xx. Every other error,
+    /// including [`Error::InvalidRequest`], is not.
+    ///
+    /// This is a default policy only: whether to retry also depends on
+    /// whether the operation is idempotent, and on how long the server asks
+    /// clients to wait (see [`Error::retry_after`]).
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::CommunicationError(e) => e.is_connect() || e.is_timeout(),
+            Error::ErrorResponse(rv) => is_retriable(rv.status()),
+            Error::UnexpectedResponse(r) => is_retriable(r.status()),
+            Error::UnexpectedResponseBody { status, .. } => {
+                is_retriable(*status)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Finds the innermost error in the chain that describes a TLS failure. The
+/// errors come from whichever TLS implementation `reqwest` was built with, so
+/// we recognize them by their messages; TLS is established while connecting,
+/// so only connection errors are considered.
+fn tls_failure(e: &reqwest::Error) -> Option<String> {
+    const TLS_TERMS: &[&str] =
+        &["tls", "ssl", "certificate", "handshake", "x509"];
+
+    if !e.is_connect() {
+        return None;
+    }
+
+    let mut found = None;
+    let mut source = std::error::Error::source(e);
+    while let Some(err) = source {
+        let message = err.to_string();
+        let lower = message.to_ascii_lowercase();
+        if TLS_TERMS.iter().any(|term| lower.contains(term)) {
+            found = Some(message);
+        }
+        source = err.source();
+    }
+    found
+}
+
+/// Parses a `Retry-After` header value, either a number of seconds or an
+/// HTTP date. Dates can't be compared with the current time on `wasm32`, so
+/// only the number of seconds is supported there.
+fn parse_retry_after(value: &str) -> Option<std::time::Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse() {
+        return Some(std::time::Duration::from_secs(secs));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let date = parse_http_date(value)?;
+        Some(
+            date.duration_since(std::time::SystemTime::now())
+                .unwrap_or_default(),
+        )
+    }
+    #[cfg(target_arch = "wasm32")]
+    None
+}
+
+/// Classifies response statuses that indicate a transient condition for
+/// which a retry is appropriate: 408 Request Timeout, 425 Too Early, 429 Too
+/// Many Requests, and any 5xx.
+fn is_retriable(status: reqwest::StatusCode) -> bool {
+    matches!(status.as_u16(), 408 | 425 | 429) || status.is_server_error()
 }
 
 impl<E> From<reqwest::Error> for Error<E> {
@@ -324,9 +4726,16 @@ where
             Error::InvalidRequest(s) => {
                 write!(f, "Invalid Request: {}", s)
             }
-            Error::CommunicationError(e) => {
-                write!(f, "Communication Error: {}", e)
-            }
+            Error::CommunicationError(e) => match tls_failure(e) {
+                Some(tls) => {
+                    write!(
+                        f,
+                        "Communication Error: TLS failure: {}: {}",
+                        tls, e
+                    )
+                }
+                None => write!(f, "Communication Error: {}", e),
+            },
             Error::ErrorResponse(rve) => {
                 write!(f, "Error Response: ")?;
                 rve.fmt_info(f)
@@ -338,14 +4747,49 @@ where
                 write!(f, "Invalid Response Body Bytes: {}", e)
             }
             Error::InvalidResponsePayload(b, e) => {
-                write!(f, "Invalid Response Payload ({:?}): {}", b, e)
+                write!(
+                    f,
+                    "Invalid Response Payload ({}): {}",
+                    BodyPreview(b),
+                    e
+                )
+            }
+            Error::InvalidResponseEncoding(b, e) => {
+                write!(
+                    f,
+                    "Invalid Response Encoding ({}): {}",
+                    BodyPreview(b),
+                    e
+                )
             }
             Error::UnexpectedResponse(r) => {
-                write!(f, "Unexpected Response: {:?}", r)
+                write!(
+                    f,
+                    "Unexpected Response: status: {}; url: {}; headers: {:?}",
+                    r.status(),
+                    r.url(),
+                    RedactedHeaders(r.headers()),
+                )
+            }
+            Error::UnexpectedResponseBody {
+                status,
+                headers,
+                body,
+            } => {
+                write!(
+                    f,
+                    "Unexpected Response: status: {}; headers: {:?}; body: {}",
+                    status,
+                    RedactedHeaders(headers),
+                    BodyPreview(body),
+                )
             }
             Error::PreHookError(s) => {
                 write!(f, "Pre-hook Error: {}", s)
             }
+            Error::TransportError(e) => {
+                write!(f, "Transport Error: {}", e)
+            }
         }
     }
 }
@@ -362,7 +4806,9 @@ where
         write!(
             f,
             "status: {}; headers: {:?}; value: {:?}",
-            self.status, self.headers, self.inner,
+            self.status,
+            RedactedHeaders(&self.headers),
+            self.inner,
         )
     }
 }
@@ -372,11 +4818,202 @@ impl ErrorFormat for ResponseValue<ByteStream> {
         write!(
             f,
             "status: {}; headers: {:?}; value: <stream>",
-            self.status, self.headers,
+            self.status,
+            RedactedHeaders(&self.headers),
+        )
+    }
+}
+
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+impl ErrorFormat for ResponseValue<BlockingBody> {
+    fn fmt_info(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "status: {}; headers: {:?}; value: <stream>",
+            self.status,
+            RedactedHeaders(&self.headers),
         )
     }
 }
 
+static REDACTED_HEADERS: std::sync::RwLock<
+    Option<Vec<reqwest::header::HeaderName>>,
+> = std::sync::RwLock::new(None);
+
+static DEFAULT_REDACTED_HEADERS: [reqwest::header::HeaderName; 4] = [
+    reqwest::header::AUTHORIZATION,
+    reqwest::header::PROXY_AUTHORIZATION,
+    reqwest::header::COOKIE,
+    reqwest::header::SET_COOKIE,
+];
+
+/// Sets the headers whose values are replaced with `<redacted>` when an
+/// [`Error`] is formatted with `Debug` or `Display`.
+///
+/// By default `Authorization`, `Proxy-Authorization`, `Cookie`, and
+/// `Set-Cookie` are redacted. This setting is process-wide; passing an empty
+/// list disables redaction, which may be useful while debugging but risks
+/// writing credentials to logs.
+pub fn set_redacted_headers<I>(names: I)
+where
+    I: IntoIterator<Item = reqwest::header::HeaderName>,
+{
+    let names = names.into_iter().collect();
+    *REDACTED_HEADERS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(names);
+}
+
+/// JSON body of a request or response, as reported to the hook set with
+/// [`set_debug_hook`].
+#[cfg(feature = "debug-schema")]
+#[derive(Debug)]
+pub enum DebugSnapshot<'a> {
+    /// The body of a request, as serialized.
+    Request {
+        /// The operation being invoked.
+        operation_id: &'a str,
+        /// The body.
+        body: &'a serde_json::Value,
+    },
+    /// The body of a response, before it is deserialized into the type of
+    /// the operation's response. Bodies that aren't valid JSON are reported
+    /// as strings.
+    Response {
+        /// The operation that was invoked.
+        operation_id: &'a str,
+        /// The status of the response.
+        status: reqwest::StatusCode,
+        /// The body.
+        body: &'a serde_json::Value,
+    },
+}
+
+#[cfg(feature = "debug-schema")]
+type DebugHook = Box<dyn Fn(&DebugSnapshot<'_>) + Send + Sync>;
+
+#[cfg(feature = "debug-schema")]
+static DEBUG_HOOK: std::sync::RwLock<Option<DebugHook>> =
+    std::sync::RwLock::new(None);
+
+/// Sets a process-wide hook that is called with the JSON request and
+/// response bodies of operations, e.g. to compare them against the API
+/// description while developing.
+///
+/// Only clients generated with the `debug_schema` setting report bodies. The
+/// bodies of all requests and responses are serialized or parsed an extra
+/// time, so this isn't intended for production use.
+#[cfg(feature = "debug-schema")]
+pub fn set_debug_hook<F>(hook: F)
+where
+    F: Fn(&DebugSnapshot<'_>) + Send + Sync + 'static,
+{
+    *DEBUG_HOOK
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+        Some(Box::new(hook));
+}
+
+/// Reports a request body to the debug hook, if the `debug-schema` feature
+/// is enabled and a hook is set, and passes it through.
+#[doc(hidden)]
+#[cfg_attr(not(feature = "debug-schema"), allow(unused_variables))]
+pub fn debug_request_body<'a, T: Serialize + ?Sized>(
+    operation_id: &str,
+    body: &'a T,
+) -> &'a T {
+    #[cfg(feature = "debug-schema")]
+    if let Some(hook) = DEBUG_HOOK
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .as_ref()
+    {
+        if let Ok(body) = serde_json::to_value(body) {
+            hook(&DebugSnapshot::Request {
+                operation_id,
+                body: &body,
+            });
+        }
+    }
+    body
+}
+
+#[cfg_attr(not(feature = "debug-schema"), allow(unused_variables))]
+fn debug_response_body(
+    operation_id: &str,
+    status: reqwest::StatusCode,
+    full: &Bytes,
+) {
+    #[cfg(feature = "debug-schema")]
+    if let Some(hook) = DEBUG_HOOK
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .as_ref()
+    {
+        let body = serde_json::from_slice(full).unwrap_or_else(|_| {
+            serde_json::Value::String(String::from_utf8_lossy(full).into())
+        });
+        hook(&DebugSnapshot::Response {
+            operation_id,
+            status,
+            body: &body,
+        });
+    }
+}
+
+/// Formats the start of a body for diagnostics: as a quoted string if it's
+/// UTF-8 and in hex otherwise, with its length if it was truncated.
+struct BodyPreview<'a>(&'a [u8]);
+
+impl std::fmt::Display for BodyPreview<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const LIMIT: usize = 256;
+        let shown = &self.0[..self.0.len().min(LIMIT)];
+        match std::str::from_utf8(shown) {
+            Ok(s) => write!(f, "{:?}", s)?,
+            // Truncation may have split a character.
+            Err(e) if e.error_len().is_none() && shown.len() < self.0.len() => {
+                let s = std::str::from_utf8(&shown[..e.valid_up_to()]).unwrap();
+                write!(f, "{:?}", s)?
+            }
+            Err(_) => {
+                f.write_str("0x")?;
+                for byte in shown {
+                    write!(f, "{:02x}", byte)?;
+                }
+            }
+        }
+        if shown.len() < self.0.len() {
+            write!(f, "... ({} bytes)", self.0.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats a [`reqwest::header::HeaderMap`] with the values of sensitive
+/// headers redacted.
+struct RedactedHeaders<'a>(&'a reqwest::header::HeaderMap);
+
+impl std::fmt::Debug for RedactedHeaders<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let configured = REDACTED_HEADERS
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let redacted =
+            configured.as_deref().unwrap_or(&DEFAULT_REDACTED_HEADERS);
+
+        let mut map = f.debug_map();
+        for (name, value) in self.0 {
+            if redacted.contains(name) {
+                map.entry(name, &format_args!("<redacted>"));
+            } else {
+                map.entry(name, value);
+            }
+        }
+        map.finish()
+    }
+}
+
 impl<E> std::fmt::Debug for Error<E>
 where
     ResponseValue<E>: ErrorFormat,
@@ -389,14 +5026,124 @@ impl<E> std::error::Error for Error<E>
 where
     ResponseValue<E>: ErrorFormat,
 {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        match self {
-            Error::CommunicationError(e) => Some(e),
-            Error::InvalidUpgrade(e) => Some(e),
-            Error::ResponseBodyError(e) => Some(e),
-            Error::InvalidResponsePayload(_b, e) => Some(e),
-            _ => None,
-        }
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::CommunicationError(e) => Some(e),
+            Error::InvalidUpgrade(e) => Some(e),
+            Error::ResponseBodyError(e) => Some(e),
+            Error::InvalidResponsePayload(_b, e) => Some(e),
+            Error::InvalidResponseEncoding(_b, e) => Some(e.as_ref()),
+            Error::TransportError(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// An [`Error`] with a human-readable note, produced by [`Error::with_note`].
+///
+/// This displays as the note followed by the error. Its source is that of
+/// the error, so that reporters such as `anyhow` that print the chain of
+/// sources don't repeat the error.
+pub struct NotedError<E = ()> {
+    note: String,
+    error: Error<E>,
+}
+
+impl<E> NotedError<E> {
+    /// Gets the note.
+    pub fn note(&self) -> &str {
+        &self.note
+    }
+
+    /// Gets the error.
+    pub fn error(&self) -> &Error<E> {
+        &self.error
+    }
+
+    /// Consumes the `NotedError`, returning the error without its note.
+    pub fn into_error(self) -> Error<E> {
+        self.error
+    }
+}
+
+impl<E> std::fmt::Display for NotedError<E>
+where
+    ResponseValue<E>: ErrorFormat,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.note, self.error)
+    }
+}
+
+impl<E> std::fmt::Debug for NotedError<E>
+where
+    ResponseValue<E>: ErrorFormat,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+impl<E> std::error::Error for NotedError<E>
+where
+    ResponseValue<E>: ErrorFormat,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        std::error::Error::source(&self.error)
+    }
+}
+
+impl<E> Error<E> {
+    /// The [`std::io::ErrorKind`] that best describes this error, for
+    /// conversion into [`std::io::Error`].
+    fn io_error_kind(&self) -> std::io::ErrorKind {
+        use std::io::ErrorKind;
+
+        match self {
+            Error::CommunicationError(e) if e.is_timeout() => {
+                return ErrorKind::TimedOut
+            }
+            Error::CommunicationError(e) if e.is_connect() => {
+                return ErrorKind::ConnectionRefused
+            }
+            Error::ResponseBodyError(e) if e.is_timeout() => {
+                return ErrorKind::TimedOut
+            }
+            Error::InvalidRequest(_) => return ErrorKind::InvalidInput,
+            Error::InvalidResponsePayload(_, _)
+            | Error::InvalidResponseEncoding(_, _) => {
+                return ErrorKind::InvalidData
+            }
+            _ => {}
+        }
+
+        match self.status().map(|status| status.as_u16()) {
+            Some(404 | 410) => ErrorKind::NotFound,
+            Some(401 | 403) => ErrorKind::PermissionDenied,
+            Some(408 | 504) => ErrorKind::TimedOut,
+            Some(400 | 422) => ErrorKind::InvalidInput,
+            Some(409) => ErrorKind::AlreadyExists,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// Converts a client error for code that works in terms of
+/// [`std::io::Result`].
+///
+/// The [`std::io::ErrorKind`] is chosen from the cause: timeouts become
+/// `TimedOut`, failures to connect become `ConnectionRefused`, and error
+/// responses are mapped by status (`404 Not Found` becomes `NotFound`,
+/// `403 Forbidden` becomes `PermissionDenied`, and so on). Anything else is
+/// `Other`. The original error is kept as the inner error, so it can be
+/// recovered with [`std::io::Error::into_inner`] and `downcast`.
+impl<E> From<Error<E>> for std::io::Error
+where
+    E: Send + Sync + 'static,
+    ResponseValue<E>: ErrorFormat,
+{
+    fn from(e: Error<E>) -> Self {
+        std::io::Error::new(e.io_error_kind(), e)
     }
 }
 
@@ -414,21 +5161,514 @@ const PATH_SET: &percent_encoding::AsciiSet = &percent_encoding::CONTROLS
     .add(b'/')
     .add(b'%');
 
+/// Rejects a request whose body is larger than the API accepts, as declared
+/// with the `x-max-request-size` extension. Bodies of unknown length, such as
+/// streams, aren't checked.
+#[doc(hidden)]
+pub fn check_request_size(
+    request: &reqwest::Request,
+    max: u64,
+) -> Result<(), String> {
+    check_body_size(request.body().and_then(|body| body.as_bytes()), max)
+}
+
+#[doc(hidden)]
+/// Like [`check_request_size`], given the buffered body of a request if it
+/// has one, for requests that aren't `reqwest::Request`s.
+pub fn check_body_size(body: Option<&[u8]>, max: u64) -> Result<(), String> {
+    let len = match body {
+        Some(bytes) => bytes.len() as u64,
+        None => return Ok(()),
+    };
+    if len > max {
+        Err(format!(
+            "request body of {} bytes exceeds the maximum of {} bytes",
+            len, max,
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 #[doc(hidden)]
 /// Percent encode input string.
 pub fn encode_path(pc: &str) -> String {
     percent_encoding::utf8_percent_encode(pc, PATH_SET).to_string()
 }
 
+/// The style of a path parameter, as in OpenAPI, for [`encode_path_array`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathStyle<'a> {
+    /// Comma-separated values, e.g. `3,4,5`.
+    Simple,
+    /// Values prefixed with a period, e.g. `.3,4,5`, or `.3.4.5` exploded.
+    Label,
+    /// Values prefixed with the parameter's name, e.g. `;id=3,4,5`, or
+    /// `;id=3;id=4;id=5` exploded.
+    Matrix(&'a str),
+}
+
+#[doc(hidden)]
+/// Percent encode the values of an array path parameter, joined according to
+/// `style` and `explode`.
+///
+/// Each value is encoded like [`encode_path`], and also has the delimiters
+/// of the style encoded, so that values containing them survive the round
+/// trip. As in RFC 6570, an empty array encodes as an empty string for every
+/// style.
+pub fn encode_path_array(
+    values: &[impl AsRef<str>],
+    style: PathStyle,
+    explode: bool,
+) -> String {
+    const SIMPLE_SET: &percent_encoding::AsciiSet = &PATH_SET.add(b',');
+    const LABEL_SET: &percent_encoding::AsciiSet = &SIMPLE_SET.add(b'.');
+    const MATRIX_SET: &percent_encoding::AsciiSet =
+        &SIMPLE_SET.add(b';').add(b'=');
+
+    if values.is_empty() {
+        return String::new();
+    }
+    let set = match style {
+        PathStyle::Simple => SIMPLE_SET,
+        PathStyle::Label => LABEL_SET,
+        PathStyle::Matrix(_) => MATRIX_SET,
+    };
+    let encoded = values.iter().map(|value| {
+        percent_encoding::utf8_percent_encode(value.as_ref(), set).to_string()
+    });
+    match style {
+        PathStyle::Simple => encoded.collect::<Vec<_>>().join(","),
+        PathStyle::Label if explode => {
+            encoded.map(|value| format!(".{}", value)).collect()
+        }
+        PathStyle::Label => {
+            format!(".{}", encoded.collect::<Vec<_>>().join(","))
+        }
+        PathStyle::Matrix(name) => {
+            let name = percent_encoding::utf8_percent_encode(name, MATRIX_SET);
+            if explode {
+                encoded
+                    .map(|value| format!(";{}={}", name, value))
+                    .collect()
+            } else {
+                let joined = encoded.collect::<Vec<_>>().join(",");
+                format!(";{}={}", name, joined)
+            }
+        }
+    }
+}
+
+// Everything but the unreserved characters of RFC 3986, so that encoded
+// names and values can't be mistaken for the delimiters around them.
+const QUERY_SET: &percent_encoding::AsciiSet =
+    &percent_encoding::NON_ALPHANUMERIC
+        .remove(b'-')
+        .remove(b'.')
+        .remove(b'_')
+        .remove(b'~');
+
+#[doc(hidden)]
+/// Percent encode the values of an array query parameter as `form` style
+/// pairs of name and value: a pair for each value if `explode` is set, as in
+/// `id=3&id=4`, and otherwise a single pair of the comma-separated values, as
+/// in `id=3,4`.
+///
+/// The names and values are encoded, including any commas in the values, so
+/// the pairs are to be added to a query as they are with [`append_query`]. An
+/// empty array produces no pairs if `explode` is set, and otherwise a pair
+/// with an empty value.
+pub fn encode_query(
+    name: &str,
+    values: &[impl AsRef<str>],
+    explode: bool,
+) -> Vec<(String, String)> {
+    let name = percent_encoding::utf8_percent_encode(name, QUERY_SET);
+    let encoded = values.iter().map(|value| {
+        percent_encoding::utf8_percent_encode(value.as_ref(), QUERY_SET)
+            .to_string()
+    });
+    if explode {
+        encoded.map(|value| (name.to_string(), value)).collect()
+    } else {
+        vec![(name.to_string(), encoded.collect::<Vec<_>>().join(","))]
+    }
+}
+
+#[doc(hidden)]
+/// Appends pairs produced by [`encode_query`] to the query of a request,
+/// after any parameters it already has.
+pub fn append_query(
+    request: &mut reqwest::Request,
+    pairs: Vec<(String, String)>,
+) {
+    append_query_to_url(request.url_mut(), pairs)
+}
+
+#[doc(hidden)]
+/// Like [`append_query`], for requests that aren't `reqwest::Request`s.
+pub fn append_query_to_url(
+    url: &mut reqwest::Url,
+    pairs: Vec<(String, String)>,
+) {
+    if pairs.is_empty() {
+        return;
+    }
+    let mut query = url.query().unwrap_or_default().to_string();
+    for (name, value) in pairs {
+        if !query.is_empty() {
+            query.push('&');
+        }
+        query.push_str(&name);
+        query.push('=');
+        query.push_str(&value);
+    }
+    url.set_query(Some(&query));
+}
+
+/// Priority hint for a request, conveyed to the server with the `Priority`
+/// header of RFC 9218 (Extensible Prioritization Scheme for HTTP).
+///
+/// `reqwest` doesn't send HTTP/2 priority frames, so the header is the only
+/// signal; servers and intermediaries that don't implement RFC 9218 ignore
+/// it, in which case setting a priority has no effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Priority {
+    urgency: u8,
+    incremental: bool,
+}
+
+impl Priority {
+    /// Priority for latency-sensitive requests, such as those a user is
+    /// waiting on.
+    pub const INTERACTIVE: Priority = Priority::new(1);
+    /// The priority servers assume for requests without a hint.
+    pub const DEFAULT: Priority = Priority::new(3);
+    /// Priority for bulk transfers that shouldn't hold up other requests.
+    pub const BACKGROUND: Priority = Priority::new(6);
+
+    /// Creates a priority with the given urgency, from 0 (most urgent) to 7
+    /// (least urgent); larger values are treated as 7.
+    pub const fn new(urgency: u8) -> Self {
+        Self {
+            urgency: if urgency > 7 { 7 } else { urgency },
+            incremental: false,
+        }
+    }
+
+    /// Indicates whether the response can be used incrementally as it
+    /// arrives, e.g. a streamed download, so that the server may interleave
+    /// it with other responses of the same urgency.
+    pub const fn incremental(mut self, incremental: bool) -> Self {
+        self.incremental = incremental;
+        self
+    }
+
+    /// Gets the urgency, from 0 (most urgent) to 7 (least urgent).
+    pub fn urgency(&self) -> u8 {
+        self.urgency
+    }
+
+    fn header_value(&self) -> reqwest::header::HeaderValue {
+        let value = if self.incremental {
+            format!("u={}, i", self.urgency)
+        } else {
+            format!("u={}", self.urgency)
+        };
+        reqwest::header::HeaderValue::from_str(&value).unwrap()
+    }
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority::DEFAULT
+    }
+}
+
+/// The body of a request sent with [`RequestBuilderExt::upload_progress`].
+#[cfg(all(feature = "stream", not(target_arch = "wasm32")))]
+struct UploadProgress {
+    chunks: std::vec::IntoIter<Bytes>,
+    sent: u64,
+    total: Option<u64>,
+    // `reqwest` requires bodies to be `Sync`.
+    f: std::sync::Mutex<Box<dyn FnMut(u64, Option<u64>) + Send>>,
+}
+
+#[cfg(all(feature = "stream", not(target_arch = "wasm32")))]
+impl Stream for UploadProgress {
+    type Item = Result<Bytes, std::io::Error>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let Some(chunk) = this.chunks.next() else {
+            return std::task::Poll::Ready(None);
+        };
+        this.sent += chunk.len() as u64;
+        let f = this.f.get_mut().unwrap_or_else(|e| e.into_inner());
+        f(this.sent, this.total);
+        std::task::Poll::Ready(Some(Ok(chunk)))
+    }
+}
+
 #[doc(hidden)]
 pub trait RequestBuilderExt<E> {
+    #[cfg(feature = "form")]
     fn form_urlencoded<T: Serialize + ?Sized>(
         self,
         body: &T,
     ) -> Result<RequestBuilder, Error<E>>;
+
+    /// Sets an `application/cbor` body, serialized as CBOR.
+    #[cfg(feature = "cbor")]
+    fn cbor<T: Serialize + ?Sized>(
+        self,
+        body: &T,
+    ) -> Result<RequestBuilder, Error<E>>;
+
+    /// Sets a `multipart/form-data` body with a part for each pair of name
+    /// and raw value, sent with the default content type.
+    #[cfg(feature = "multipart")]
+    fn form_from_raw<S, T, I>(
+        self,
+        iter: I,
+    ) -> Result<RequestBuilder, Error<E>>
+    where
+        S: AsRef<str>,
+        T: AsRef<[u8]>,
+        I: IntoIterator<Item = (S, T)>;
+
+    /// Like [`RequestBuilderExt::form_from_raw`], but sends each part with
+    /// the content type given alongside it, if any, as servers that expect
+    /// a mix of, say, JSON and binary parts require.
+    ///
+    /// A content type that isn't a valid MIME type produces
+    /// [`Error::InvalidRequest`].
+    #[cfg(feature = "multipart")]
+    fn form_from_raw_typed<S, T, M, I>(
+        self,
+        iter: I,
+    ) -> Result<RequestBuilder, Error<E>>
+    where
+        S: AsRef<str>,
+        T: AsRef<[u8]>,
+        M: AsRef<str>,
+        I: IntoIterator<Item = (S, T, Option<M>)>;
+
+    /// Like [`RequestBuilderExt::form_from_raw`], but gives each part the
+    /// file name alongside it, if any, as servers that handle uploads
+    /// commonly expect.
+    ///
+    /// Names that aren't plain ASCII are sent in the `filename*` parameter of
+    /// the part's `Content-Disposition`, encoded as RFC 6266 describes.
+    #[cfg(feature = "multipart")]
+    fn form_from_raw_files<S, F, T, I>(
+        self,
+        iter: I,
+    ) -> Result<RequestBuilder, Error<E>>
+    where
+        S: AsRef<str>,
+        F: AsRef<str>,
+        T: AsRef<[u8]>,
+        I: IntoIterator<Item = (S, Option<F>, T)>;
+
+    /// Sets the `X-Correlation-ID` header used to tie this request to
+    /// related work in distributed traces and server logs.
+    fn correlation_id(self, id: &str) -> Result<RequestBuilder, Error<E>>;
+
+    /// Sets a body that is already serialized as JSON, sending its bytes
+    /// exactly as given.
+    ///
+    /// The body isn't checked to be valid JSON.
+    fn raw_json<B: Into<Bytes>>(self, body: B) -> RequestBuilder;
+
+    /// Sets a hint of the request's priority relative to other requests on
+    /// the same connection; see [`Priority`] for its caveats.
+    fn priority(self, priority: Priority) -> RequestBuilder;
+
+    /// Sets the `Content-MD5` header to the base64-encoded MD5 digest of
+    /// `body`, for storage APIs that verify the integrity of uploads. This
+    /// doesn't set the body, which must be exactly `body`.
+    ///
+    /// A streamed body can't be hashed as it's sent, since the header goes
+    /// first: hash it in a first pass (e.g. as it's written to disk) and set
+    /// the header from that digest, or buffer the body.
+    #[cfg(feature = "content-md5")]
+    fn with_content_md5(self, body: &[u8]) -> RequestBuilder;
+
+    /// Calls `f` with the number of body bytes sent so far and the total, if
+    /// known, as the transport reads the body: the callback for downloads is
+    /// [`ByteStream::collect_with_progress`].
+    ///
+    /// A buffered body is sent as a stream of chunks of up to 64 KiB, with
+    /// its `Content-Length` kept and its length used as the total if `total`
+    /// is `None`. As a result the request can no longer be cloned, e.g. to be
+    /// retried. `reqwest` doesn't expose the stream of a body that is already
+    /// streamed, so such bodies are sent unchanged and `f` isn't called; wrap
+    /// the stream itself to track its progress.
+    #[cfg(all(feature = "stream", not(target_arch = "wasm32")))]
+    fn upload_progress<F>(
+        self,
+        total: Option<u64>,
+        f: F,
+    ) -> Result<RequestBuilder, Error<E>>
+    where
+        F: FnMut(u64, Option<u64>) + Send + 'static;
+
+    /// Sets a GraphQL-over-HTTP JSON body, of the form
+    /// `{ "query": ..., "operationName": ..., "variables": ... }`, for APIs
+    /// that expose GraphQL through a single operation.
+    ///
+    /// `operation_name` selects the operation to run when `query` defines
+    /// several, and is omitted from the body if `None`.
+    fn graphql<V: Serialize>(
+        self,
+        query: &str,
+        operation_name: Option<&str>,
+        variables: V,
+    ) -> Result<RequestBuilder, Error<E>>;
+
+    /// Sets a JSON Merge Patch (RFC 7396) body describing the changes from
+    /// `old` to `new`; fields that are unchanged are omitted, and fields
+    /// present in `old` but absent from `new` are sent as `null`.
+    ///
+    /// Merge patches can't express setting a field to `null`, so a field
+    /// that becomes `null` is likewise removed.
+    fn merge_patch_diff<T: Serialize>(
+        self,
+        old: &T,
+        new: &T,
+    ) -> Result<RequestBuilder, Error<E>>;
+}
+
+/// A `multipart/form-data` body, as RFC 7578 describes, built a part at a
+/// time.
+#[cfg(feature = "multipart")]
+struct MultipartForm {
+    boundary: String,
+    body: Vec<u8>,
+}
+
+#[cfg(feature = "multipart")]
+impl MultipartForm {
+    fn new() -> Self {
+        use std::hash::{BuildHasher, Hasher};
+
+        // Each `RandomState` is seeded differently, which makes for a
+        // boundary that's vanishingly unlikely to appear in a part.
+        let random = || {
+            std::collections::hash_map::RandomState::new()
+                .build_hasher()
+                .finish()
+        };
+        Self {
+            boundary: format!("{:016x}{:016x}", random(), random()),
+            body: Vec::new(),
+        }
+    }
+
+    /// Adds a part with the given name, and file name and content type if
+    /// any. Without a content type, the part is taken to be `text/plain`.
+    ///
+    /// A file name that isn't plain ASCII is also sent in the `filename*`
+    /// parameter, encoded as RFC 6266 describes, with an ASCII approximation
+    /// in `filename` for servers that don't understand it.
+    fn part(
+        &mut self,
+        name: &str,
+        file_name: Option<&str>,
+        content_type: Option<&str>,
+        value: &[u8],
+    ) {
+        let mut headers = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+            self.boundary,
+            escape_form_param(name),
+        );
+        if let Some(file_name) = file_name {
+            let ascii = file_name
+                .chars()
+                .map(|c| if c.is_ascii() { c } else { '_' })
+                .collect::<String>();
+            headers.push_str(&format!(
+                "; filename=\"{}\"",
+                escape_form_param(&ascii),
+            ));
+            if !file_name.is_ascii() {
+                headers.push_str(&format!(
+                    "; filename*=UTF-8''{}",
+                    percent_encoding::utf8_percent_encode(
+                        file_name,
+                        EXT_VALUE_SET,
+                    ),
+                ));
+            }
+        }
+        headers.push_str("\r\n");
+        if let Some(content_type) = content_type {
+            headers.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        headers.push_str("\r\n");
+
+        self.body.extend_from_slice(headers.as_bytes());
+        self.body.extend_from_slice(value);
+        self.body.extend_from_slice(b"\r\n");
+    }
+
+    /// Sets the form as the body of the request.
+    fn finish(mut self, builder: RequestBuilder) -> RequestBuilder {
+        self.body
+            .extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        builder
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", self.boundary),
+            )
+            .body(self.body)
+    }
+}
+
+/// Characters percent-encoded in an RFC 8187 extended parameter value: all
+/// but `attr-char`.
+#[cfg(feature = "multipart")]
+const EXT_VALUE_SET: &percent_encoding::AsciiSet =
+    &percent_encoding::NON_ALPHANUMERIC
+        .remove(b'!')
+        .remove(b'#')
+        .remove(b'$')
+        .remove(b'&')
+        .remove(b'+')
+        .remove(b'-')
+        .remove(b'.')
+        .remove(b'^')
+        .remove(b'_')
+        .remove(b'`')
+        .remove(b'|')
+        .remove(b'~');
+
+/// Escapes a parameter of a `Content-Disposition` header in a form part as
+/// HTML forms do, percent-encoding quotes and line breaks.
+#[cfg(feature = "multipart")]
+fn escape_form_param(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Returns true if `value` is a valid MIME type, such as `image/png`, that
+/// can be sent as a header value.
+#[cfg(feature = "multipart")]
+fn is_mime_type(value: &str) -> bool {
+    reqwest::header::HeaderValue::from_str(value).is_ok()
+        && parse_content_type(value).is_some()
 }
 
 impl<E> RequestBuilderExt<E> for RequestBuilder {
+    #[cfg(feature = "form")]
     fn form_urlencoded<T: Serialize + ?Sized>(
         self,
         body: &T,
@@ -444,4 +5684,555 @@ impl<E> RequestBuilderExt<E> for RequestBuilder {
                 Error::InvalidRequest("failed to serialize body".to_string())
             })?))
     }
+
+    #[cfg(feature = "cbor")]
+    fn cbor<T: Serialize + ?Sized>(self, body: &T) -> Result<Self, Error<E>> {
+        let mut buf = Vec::new();
+        ciborium::into_writer(body, &mut buf).map_err(|_| {
+            Error::InvalidRequest("failed to serialize body".to_string())
+        })?;
+        Ok(self
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                reqwest::header::HeaderValue::from_static("application/cbor"),
+            )
+            .body(buf))
+    }
+
+    #[cfg(feature = "multipart")]
+    fn form_from_raw<S, T, I>(self, iter: I) -> Result<Self, Error<E>>
+    where
+        S: AsRef<str>,
+        T: AsRef<[u8]>,
+        I: IntoIterator<Item = (S, T)>,
+    {
+        self.form_from_raw_typed(
+            iter.into_iter()
+                .map(|(name, value)| (name, value, None::<&str>)),
+        )
+    }
+
+    #[cfg(feature = "multipart")]
+    fn form_from_raw_typed<S, T, M, I>(self, iter: I) -> Result<Self, Error<E>>
+    where
+        S: AsRef<str>,
+        T: AsRef<[u8]>,
+        M: AsRef<str>,
+        I: IntoIterator<Item = (S, T, Option<M>)>,
+    {
+        let mut form = MultipartForm::new();
+        for (name, value, mime) in iter {
+            let mime = mime.as_ref().map(AsRef::as_ref);
+            if let Some(mime) = mime.filter(|mime| !is_mime_type(mime)) {
+                return Err(Error::InvalidRequest(format!(
+                    "invalid content type {:?} for part {:?}",
+                    mime,
+                    name.as_ref(),
+                )));
+            }
+            form.part(name.as_ref(), None, mime, value.as_ref());
+        }
+        Ok(form.finish(self))
+    }
+
+    #[cfg(feature = "multipart")]
+    fn form_from_raw_files<S, F, T, I>(self, iter: I) -> Result<Self, Error<E>>
+    where
+        S: AsRef<str>,
+        F: AsRef<str>,
+        T: AsRef<[u8]>,
+        I: IntoIterator<Item = (S, Option<F>, T)>,
+    {
+        let mut form = MultipartForm::new();
+        for (name, file_name, value) in iter {
+            let file_name = file_name.as_ref().map(AsRef::as_ref);
+            form.part(name.as_ref(), file_name, None, value.as_ref());
+        }
+        Ok(form.finish(self))
+    }
+
+    fn correlation_id(self, id: &str) -> Result<Self, Error<E>> {
+        let value = reqwest::header::HeaderValue::from_str(id)?;
+        Ok(self.header(CORRELATION_ID, value))
+    }
+
+    fn raw_json<B: Into<Bytes>>(self, body: B) -> Self {
+        self.header(
+            reqwest::header::CONTENT_TYPE,
+            reqwest::header::HeaderValue::from_static("application/json"),
+        )
+        .body(body.into())
+    }
+
+    fn priority(self, priority: Priority) -> Self {
+        self.header("priority", priority.header_value())
+    }
+
+    #[cfg(feature = "content-md5")]
+    fn with_content_md5(self, body: &[u8]) -> Self {
+        use base64::Engine;
+        use md5::Digest;
+
+        let digest = md5::Md5::digest(body);
+        let encoded = base64::engine::general_purpose::STANDARD.encode(digest);
+        // Base64 is always a valid header value.
+        let value = reqwest::header::HeaderValue::from_str(&encoded).unwrap();
+        self.header("content-md5", value)
+    }
+
+    #[cfg(all(feature = "stream", not(target_arch = "wasm32")))]
+    fn upload_progress<F>(
+        self,
+        total: Option<u64>,
+        f: F,
+    ) -> Result<Self, Error<E>>
+    where
+        F: FnMut(u64, Option<u64>) + Send + 'static,
+    {
+        const CHUNK: usize = 64 << 10;
+
+        let (client, request) = self.build_split();
+        let mut request = request?;
+        let Some(bytes) = request
+            .body()
+            .and_then(reqwest::Body::as_bytes)
+            .map(Bytes::copy_from_slice)
+        else {
+            return Ok(RequestBuilder::from_parts(client, request));
+        };
+        let len = bytes.len();
+        request
+            .headers_mut()
+            .entry(reqwest::header::CONTENT_LENGTH)
+            .or_insert_with(|| len.into());
+        let chunks = (0..len)
+            .step_by(CHUNK)
+            .map(|start| bytes.slice(start..len.min(start + CHUNK)))
+            .collect::<Vec<_>>();
+        *request.body_mut() =
+            Some(reqwest::Body::wrap_stream(UploadProgress {
+                chunks: chunks.into_iter(),
+                sent: 0,
+                total: total.or(Some(len as u64)),
+                f: std::sync::Mutex::new(Box::new(f)),
+            }));
+        Ok(RequestBuilder::from_parts(client, request))
+    }
+
+    fn graphql<V: Serialize>(
+        self,
+        query: &str,
+        operation_name: Option<&str>,
+        variables: V,
+    ) -> Result<Self, Error<E>> {
+        let variables = serde_json::to_value(variables).map_err(|e| {
+            Error::InvalidRequest(format!(
+                "failed to serialize GraphQL variables: {}",
+                e
+            ))
+        })?;
+        let mut body = serde_json::Map::new();
+        body.insert("query".to_string(), query.into());
+        if let Some(operation_name) = operation_name {
+            body.insert("operationName".to_string(), operation_name.into());
+        }
+        body.insert("variables".to_string(), variables);
+        Ok(self.json(&body))
+    }
+
+    fn merge_patch_diff<T: Serialize>(
+        self,
+        old: &T,
+        new: &T,
+    ) -> Result<Self, Error<E>> {
+        let to_value = |value| {
+            serde_json::to_value(value).map_err(|e| {
+                Error::InvalidRequest(format!(
+                    "failed to serialize body: {}",
+                    e
+                ))
+            })
+        };
+        let patch = merge_patch(&to_value(old)?, &to_value(new)?);
+        Ok(self
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                reqwest::header::HeaderValue::from_static(
+                    "application/merge-patch+json",
+                ),
+            )
+            .body(patch.to_string()))
+    }
+}
+
+const CORRELATION_ID: &str = "x-correlation-id";
+
+/// Computes the JSON Merge Patch that transforms `old` into `new`.
+fn merge_patch(
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+) -> serde_json::Value {
+    let (serde_json::Value::Object(old), serde_json::Value::Object(new)) =
+        (old, new)
+    else {
+        return new.clone();
+    };
+
+    let removed = old
+        .keys()
+        .filter(|key| !new.contains_key(*key))
+        .map(|key| (key.clone(), serde_json::Value::Null));
+    let changed = new.iter().filter_map(|(key, value)| match old.get(key) {
+        Some(old_value) if old_value == value => None,
+        Some(old_value) => Some((key.clone(), merge_patch(old_value, value))),
+        None => Some((key.clone(), value.clone())),
+    });
+    serde_json::Value::Object(removed.chain(changed).collect())
+}
+
+/// Splits a header value into its list elements, ignoring delimiters that
+/// appear within quoted strings or angle-bracketed URI references.
+fn split_header_list(value: &str, delim: char) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut bracketed = false;
+
+    for (i, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if quoted {
+            match c {
+                '\\' => escaped = true,
+                '"' => quoted = false,
+                _ => (),
+            }
+        } else if bracketed {
+            bracketed = c != '>';
+        } else if c == '"' {
+            quoted = true;
+        } else if c == '<' {
+            bracketed = true;
+        } else if c == delim {
+            items.push(&value[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    items.push(&value[start..]);
+
+    items
+        .into_iter()
+        .filter(|item| !item.trim().is_empty())
+        .collect()
+}
+
+/// Removes the surrounding quotes (and escapes) from a quoted-string; tokens
+/// are returned as is.
+fn unquote_header_value(value: &str) -> String {
+    let value = value.trim();
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => {
+            let mut out = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                if c == '\\' {
+                    if let Some(escaped) = chars.next() {
+                        out.push(escaped);
+                    }
+                } else {
+                    out.push(c);
+                }
+            }
+            out
+        }
+        None => value.to_string(),
+    }
+}
+
+/// Parses `;`-separated `name=value` parameters. Names are lower-cased and
+/// values are unquoted; parameters without a value have an empty value.
+fn parse_header_params(params: &str) -> Vec<(String, String)> {
+    split_header_list(params, ';')
+        .into_iter()
+        .map(|param| match param.split_once('=') {
+            Some((name, value)) => (
+                name.trim().to_ascii_lowercase(),
+                unquote_header_value(value),
+            ),
+            None => (param.trim().to_ascii_lowercase(), String::new()),
+        })
+        .collect()
+}
+
+/// Decodes a text body in the given charset; see [`ResponseValue::text`].
+fn decode_text(
+    body: &[u8],
+    charset: Option<&str>,
+) -> Result<String, serde_json::Error> {
+    let invalid = |charset: &str| {
+        <serde_json::Error as serde::de::Error>::custom(format!(
+            "invalid {} text",
+            charset
+        ))
+    };
+    match charset.map(str::to_ascii_lowercase).as_deref() {
+        Some("iso-8859-1" | "latin1" | "l1") => {
+            Ok(body.iter().map(|b| char::from(*b)).collect())
+        }
+        Some("us-ascii" | "ascii") if !body.is_ascii() => {
+            Err(invalid("US-ASCII"))
+        }
+        _ => String::from_utf8(body.to_vec()).map_err(|_| invalid("UTF-8")),
+    }
+}
+
+/// An error from decoding a response body, reported with the body as
+/// [`Error::InvalidResponsePayload`] for JSON or
+/// [`Error::InvalidResponseEncoding`] for other encodings.
+trait PayloadError {
+    fn into_error<E>(self, body: Bytes) -> Error<E>;
+}
+
+impl PayloadError for serde_json::Error {
+    fn into_error<E>(self, body: Bytes) -> Error<E> {
+        Error::InvalidResponsePayload(body, self)
+    }
+}
+
+impl PayloadError for Box<dyn std::error::Error + Send + Sync> {
+    fn into_error<E>(self, body: Bytes) -> Error<E> {
+        Error::InvalidResponseEncoding(body, self)
+    }
+}
+
+/// Returns true if the `Content-Type` in `headers` is a JSON media type.
+fn is_json_content_type(headers: &reqwest::header::HeaderMap) -> bool {
+    headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_content_type)
+        .is_some_and(|(essence, _)| {
+            essence == "application/json" || essence.ends_with("+json")
+        })
+}
+
+/// Splits a `Content-Type` header value into its lower-cased media type
+/// (e.g. `text/plain`) and its parameters. If a parameter is repeated, the
+/// first value is used.
+fn parse_content_type(
+    value: &str,
+) -> Option<(String, std::collections::HashMap<String, String>)> {
+    let (essence, params) = match value.split_once(';') {
+        Some((essence, params)) => (essence, params),
+        None => (value, ""),
+    };
+    let essence = essence.trim().to_ascii_lowercase();
+    match essence.split_once('/') {
+        Some((kind, subtype)) if !kind.is_empty() && !subtype.is_empty() => (),
+        _ => return None,
+    }
+
+    let mut map = std::collections::HashMap::new();
+    for (name, value) in parse_header_params(params) {
+        if !name.is_empty() {
+            map.entry(name).or_insert(value);
+        }
+    }
+    Some((essence, map))
+}
+
+/// Parses a `Set-Cookie` header value, e.g.
+/// `id=a3fWa; Path=/; Max-Age=2592000; HttpOnly`, as described by RFC 6265.
+fn parse_set_cookie(value: &str) -> Option<Cookie> {
+    let mut items = value.split(';');
+    let (name, value) = items.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut cookie = Cookie {
+        name: name.to_string(),
+        value: value.trim().to_string(),
+        path: None,
+        domain: None,
+        expires: None,
+        max_age: None,
+        secure: false,
+        http_only: false,
+        same_site: None,
+    };
+    for attribute in items {
+        let (name, value) = match attribute.split_once('=') {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => (attribute.trim(), ""),
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "path" => cookie.path = Some(value.to_string()),
+            "domain" => {
+                let domain = value.strip_prefix('.').unwrap_or(value);
+                cookie.domain = Some(domain.to_ascii_lowercase());
+            }
+            "expires" => cookie.expires = parse_http_date(value),
+            "max-age" => cookie.max_age = value.parse().ok(),
+            "secure" => cookie.secure = true,
+            "httponly" => cookie.http_only = true,
+            "samesite" => cookie.same_site = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    Some(cookie)
+}
+
+/// Parses the error attributes of the `Bearer` challenge in a
+/// `WWW-Authenticate` header value, which may hold several challenges, e.g.
+/// `Basic realm="api", Bearer realm="api", error="invalid_token"`.
+fn parse_bearer_error(value: &str) -> Option<BearerError> {
+    let mut bearer = false;
+    let mut params = std::collections::HashMap::new();
+    for item in split_header_list(value, ',') {
+        let item = item.trim();
+        // An item that starts with a token other than a parameter name
+        // begins a new challenge with that scheme.
+        let param = match item.split_once(' ') {
+            Some((scheme, rest))
+                if !scheme.contains('=')
+                    && !rest.trim_start().starts_with('=') =>
+            {
+                bearer = scheme.eq_ignore_ascii_case("bearer");
+                rest
+            }
+            None if !item.contains('=') => {
+                bearer = item.eq_ignore_ascii_case("bearer");
+                continue;
+            }
+            _ => item,
+        };
+        if let (true, Some((name, value))) = (bearer, param.split_once('=')) {
+            params
+                .entry(name.trim().to_ascii_lowercase())
+                .or_insert_with(|| unquote_header_value(value));
+        }
+    }
+
+    Some(BearerError {
+        error: params.remove("error")?,
+        error_description: params.remove("error_description"),
+        error_uri: params.remove("error_uri"),
+        scope: params.remove("scope"),
+    })
+}
+
+/// Parses a single metric of a `Server-Timing` header, e.g.
+/// `db;dur=53;desc="Database"`.
+fn parse_server_timing(metric: &str) -> Option<ServerTiming> {
+    let (name, params) = match metric.split_once(';') {
+        Some((name, params)) => (name, params),
+        None => (metric, ""),
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let params = parse_header_params(params);
+    let param = |key: &str| {
+        params
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    };
+    Some(ServerTiming {
+        name: name.to_string(),
+        dur: param("dur").and_then(|dur| dur.parse().ok()),
+        desc: param("desc").cloned(),
+    })
+}
+
+/// Parses an HTTP date in the preferred IMF-fixdate format, e.g.
+/// `Sun, 06 Nov 1994 08:49:37 GMT`. The obsolete RFC 850 and asctime formats
+/// aren't supported.
+fn parse_http_date(value: &str) -> Option<std::time::SystemTime> {
+    let (_, date) = value.trim().split_once(", ")?;
+    let mut parts = date.split(' ');
+    let day: u64 = parts.next()?.parse().ok()?;
+    let month = match parts.next()? {
+        "Jan" => 1,
+        "Feb" => 2,
+        "Mar" => 3,
+        "Apr" => 4,
+        "May" => 5,
+        "Jun" => 6,
+        "Jul" => 7,
+        "Aug" => 8,
+        "Sep" => 9,
+        "Oct" => 10,
+        "Nov" => 11,
+        "Dec" => 12,
+        _ => return None,
+    };
+    let year: u64 = parts.next()?.parse().ok()?;
+    let mut time = parts.next()?.split(':');
+    let hour: u64 = time.next()?.parse().ok()?;
+    let minute: u64 = time.next()?.parse().ok()?;
+    let second: u64 = time.next()?.parse().ok()?;
+    if parts.next()? != "GMT"
+        || parts.next().is_some()
+        || time.next().is_some()
+        || year < 1970
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    // Days since the epoch of the civil date, per Howard Hinnant's
+    // `days_from_civil`, simplified for years since 1970.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let secs = days * 86_400 + hour * 3_600 + minute * 60 + second;
+    Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+}
+
+/// Extracts the filename from a `Content-Disposition` header value.
+fn content_disposition_filename(value: &str) -> Option<String> {
+    // The disposition type (e.g. "attachment") precedes the parameters.
+    let (_, params) = value.split_once(';')?;
+    let params = parse_header_params(params);
+
+    params
+        .iter()
+        .find(|(name, _)| name == "filename*")
+        .and_then(|(_, value)| decode_ext_value(value))
+        .or_else(|| {
+            params
+                .iter()
+                .find(|(name, _)| name == "filename")
+                .map(|(_, value)| value.clone())
+        })
+}
+
+/// Decodes an RFC 5987 extended parameter value of the form
+/// `charset'language'percent-encoded-value`.
+fn decode_ext_value(value: &str) -> Option<String> {
+    let mut parts = value.splitn(3, '\'');
+    let charset = parts.next()?;
+    let _language = parts.next()?;
+    let decoded = percent_encoding::percent_decode_str(parts.next()?);
+
+    if charset.eq_ignore_ascii_case("utf-8") {
+        decoded.decode_utf8().ok().map(|s| s.into_owned())
+    } else if charset.eq_ignore_ascii_case("iso-8859-1") {
+        Some(decoded.map(char::from).collect())
+    } else {
+        None
+    }
 }
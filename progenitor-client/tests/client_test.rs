@@ -1,8 +1,3279 @@
 // Copyright 2023 Oxide Computer Company
 
-use progenitor_client::encode_path;
+use bytes::Bytes;
+use progenitor_client::{encode_path, ByteStream, Error, ResponseValue};
+use reqwest::header::{HeaderMap, HeaderValue};
+
+fn byte_stream(chunks: &[&'static str]) -> ByteStream {
+    let chunks = chunks
+        .iter()
+        .map(|chunk| Ok(Bytes::from_static(chunk.as_bytes())))
+        .collect::<Vec<_>>();
+    ByteStream::new(Box::pin(futures::stream::iter(chunks)))
+}
 
 #[test]
 fn test_path_segment_encoding() {
     assert_eq!(encode_path("192.168.0.0/24"), "192.168.0.0%2F24");
 }
+
+#[test]
+fn test_path_array_encoding() {
+    use progenitor_client::{encode_path_array, PathStyle};
+
+    let values = ["3", "4,5", "a.b;c=d"];
+    assert_eq!(
+        encode_path_array(&values, PathStyle::Simple, false),
+        "3,4%2C5,a.b;c=d",
+    );
+    assert_eq!(
+        encode_path_array(&values, PathStyle::Label, false),
+        ".3,4%2C5,a%2Eb;c=d",
+    );
+    assert_eq!(
+        encode_path_array(&values, PathStyle::Label, true),
+        ".3.4%2C5.a%2Eb;c=d",
+    );
+    assert_eq!(
+        encode_path_array(&values, PathStyle::Matrix("id"), false),
+        ";id=3,4%2C5,a.b%3Bc%3Dd",
+    );
+    assert_eq!(
+        encode_path_array(&values, PathStyle::Matrix("id"), true),
+        ";id=3;id=4%2C5;id=a.b%3Bc%3Dd",
+    );
+    assert_eq!(
+        encode_path_array(&["x/y"], PathStyle::Simple, false),
+        "x%2Fy",
+    );
+    let empty: [&str; 0] = [];
+    assert_eq!(encode_path_array(&empty, PathStyle::Matrix("id"), true), "");
+}
+
+#[test]
+fn test_query_array_encoding() {
+    use progenitor_client::{append_query, encode_query};
+
+    let values = ["a&b", "c=d", "e,f"];
+    let exploded = encode_query("tag", &values, true);
+    assert_eq!(
+        exploded,
+        [
+            ("tag".to_string(), "a%26b".to_string()),
+            ("tag".to_string(), "c%3Dd".to_string()),
+            ("tag".to_string(), "e%2Cf".to_string()),
+        ],
+    );
+    let joined = encode_query("tag", &values, false);
+    assert_eq!(
+        joined,
+        [("tag".to_string(), "a%26b,c%3Dd,e%2Cf".to_string())],
+    );
+
+    let empty: [&str; 0] = [];
+    assert!(encode_query("tag", &empty, true).is_empty());
+    assert_eq!(
+        encode_query("tag", &empty, false),
+        [("tag".to_string(), String::new())],
+    );
+
+    let mut request = reqwest::Client::new()
+        .get("http://localhost/items")
+        .query(&[("limit", "2")])
+        .build()
+        .unwrap();
+    append_query(&mut request, exploded);
+    append_query(&mut request, Vec::new());
+    assert_eq!(
+        request.url().as_str(),
+        "http://localhost/items?limit=2&tag=a%26b&tag=c%3Dd&tag=e%2Cf",
+    );
+    let tags = request
+        .url()
+        .query_pairs()
+        .filter(|(name, _)| name == "tag")
+        .map(|(_, value)| value.into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(tags, values);
+}
+
+#[test]
+fn test_link_header() {
+    let mut headers = HeaderMap::new();
+    headers.append(
+        reqwest::header::LINK,
+        HeaderValue::from_static(
+            "<https://api.example.com/items?page=2>; rel=\"next\", \
+             <https://api.example.com/items?page=9>; rel=\"last\"",
+        ),
+    );
+    headers.append(
+        reqwest::header::LINK,
+        HeaderValue::from_static(
+            "<https://api.example.com/items?page=1>; rel=\"first prev\"",
+        ),
+    );
+    headers.append(
+        reqwest::header::LINK,
+        HeaderValue::from_static("</relative>; rel=self"),
+    );
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, headers);
+
+    assert_eq!(
+        rv.link("next").unwrap().as_str(),
+        "https://api.example.com/items?page=2",
+    );
+    assert_eq!(
+        rv.link("LAST").unwrap().as_str(),
+        "https://api.example.com/items?page=9",
+    );
+    assert_eq!(
+        rv.link("prev").unwrap().as_str(),
+        "https://api.example.com/items?page=1",
+    );
+    // Relative targets can't be resolved without a final URL.
+    assert!(rv.link("self").is_none());
+    assert!(rv.link("up").is_none());
+}
+
+#[test]
+fn test_location_header() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        reqwest::header::LOCATION,
+        HeaderValue::from_static("https://api.example.com/widgets/7"),
+    );
+    let rv = ResponseValue::new((), reqwest::StatusCode::CREATED, headers);
+    assert_eq!(
+        rv.location().unwrap().as_str(),
+        "https://api.example.com/widgets/7",
+    );
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        reqwest::header::LOCATION,
+        HeaderValue::from_static("/widgets/7"),
+    );
+    let rv = ResponseValue::new((), reqwest::StatusCode::CREATED, headers);
+    // Relative targets can't be resolved without a final URL.
+    assert!(rv.location().is_none());
+
+    let response = http::Response::builder()
+        .status(303)
+        .header("location", "/widgets/7")
+        .body("")
+        .unwrap();
+    let rv = ResponseValue::empty(response.into());
+    let final_url = rv.final_url().unwrap().clone();
+    assert_eq!(
+        rv.location().unwrap(),
+        final_url.join("/widgets/7").unwrap()
+    );
+}
+
+#[test]
+fn test_count_bytes() {
+    let stream = byte_stream(&["abc", "", "defg"]);
+    let count = futures::executor::block_on(stream.count_bytes()).unwrap();
+    assert_eq!(count, 7);
+}
+
+#[test]
+fn test_byte_stream_is_stream() {
+    use futures::{Stream, StreamExt};
+
+    fn concat(stream: impl Stream<Item = reqwest::Result<Bytes>>) -> String {
+        let chunks = futures::executor::block_on(stream.collect::<Vec<_>>());
+        chunks
+            .into_iter()
+            .map(|chunk| String::from_utf8(chunk.unwrap().to_vec()).unwrap())
+            .collect()
+    }
+
+    assert_eq!(concat(byte_stream(&["abc", "def"])), "abcdef");
+
+    let mut stream = byte_stream(&["abc", "def"]);
+    assert_eq!(stream.size_hint(), (2, Some(2)));
+    let first = futures::executor::block_on(stream.next());
+    assert_eq!(first.unwrap().unwrap(), "abc");
+    assert_eq!(concat(stream), "def");
+}
+
+#[test]
+fn test_byte_stream_into_body() {
+    let request = reqwest::Client::new()
+        .put("http://localhost/object")
+        .body(byte_stream(&["abc", "def"]))
+        .build()
+        .unwrap();
+    // A streamed body isn't buffered.
+    assert!(request.body().unwrap().as_bytes().is_none());
+
+    let body: reqwest::Body = byte_stream(&["abc", "def"]).into();
+    let response: reqwest::Response =
+        http::Response::builder().body(body).unwrap().into();
+    let bytes = futures::executor::block_on(response.bytes()).unwrap();
+    assert_eq!(bytes, "abcdef");
+}
+
+#[test]
+fn test_inspect_progress() {
+    use futures::StreamExt;
+
+    let chunks = vec![
+        Ok(Bytes::from_static(b"abc")),
+        Ok(Bytes::new()),
+        Ok(Bytes::from_static(b"defg")),
+    ];
+    let mut progress = Vec::new();
+    let stream = ByteStream::new(Box::pin(futures::stream::iter(chunks)))
+        .inspect_progress(|received| progress.push(received));
+    let chunks = futures::executor::block_on(stream.collect::<Vec<_>>());
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(progress, vec![3, 3, 7]);
+
+    // Errors aren't reported as progress.
+    let body = reqwest::Body::wrap_stream(futures::stream::iter(vec![
+        Ok(Bytes::from_static(b"abc")),
+        Err(std::io::Error::new(std::io::ErrorKind::Other, "reset")),
+    ]));
+    let response: reqwest::Response =
+        http::Response::builder().body(body).unwrap().into();
+    let mut progress = Vec::new();
+    let stream = ByteStream::new(Box::pin(response.bytes_stream()))
+        .inspect_progress(|received| progress.push(received));
+    let chunks = futures::executor::block_on(stream.collect::<Vec<_>>());
+    assert!(chunks[0].is_ok());
+    assert!(chunks[1].is_err());
+    assert_eq!(progress, vec![3]);
+}
+
+#[test]
+fn test_map_chunks() {
+    use futures::StreamExt;
+
+    let stream = byte_stream(&["abc", "def"])
+        .map_chunks(|chunk| Bytes::from(chunk.to_ascii_uppercase()));
+    let chunks =
+        futures::executor::block_on(stream.into_inner().collect::<Vec<_>>());
+    let chunks = chunks.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+    assert_eq!(chunks, vec!["ABC", "DEF"]);
+
+    let stream = byte_stream(&["ok", "bad", "never"]).try_map_chunks(|chunk| {
+        if chunk == "bad" {
+            Err("bad chunk")
+        } else {
+            Ok(chunk)
+        }
+    });
+    let results = futures::executor::block_on(stream.collect::<Vec<_>>());
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_ref().unwrap(), "ok");
+    assert!(matches!(
+        results[1],
+        Err(progenitor_client::ChunkError::Map("bad chunk"))
+    ));
+}
+
+#[test]
+fn test_inspect_chunks() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let seen = Arc::new(AtomicUsize::new(0));
+    let stream = byte_stream(&["abc", "defg"]).inspect_chunks({
+        let seen = seen.clone();
+        move |chunk| {
+            seen.fetch_add(chunk.len(), Ordering::SeqCst);
+        }
+    });
+    let count = futures::executor::block_on(stream.count_bytes()).unwrap();
+    assert_eq!(count, 7);
+    assert_eq!(seen.load(Ordering::SeqCst), 7);
+}
+
+#[test]
+fn test_collect_with_progress() {
+    let mut progress = Vec::new();
+    let body = futures::executor::block_on(
+        byte_stream(&["abc", "defg"])
+            .collect_with_progress(Some(7), |n, total| {
+                progress.push((n, total))
+            }),
+    )
+    .unwrap();
+    assert_eq!(body, "abcdefg");
+    assert_eq!(progress, [(3, Some(7)), (7, Some(7)), (7, Some(7))]);
+}
+
+#[test]
+fn test_on_complete() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let stream = byte_stream(&["abc", "def"]).on_complete({
+        let calls = calls.clone();
+        move |result| {
+            assert!(result.is_ok());
+            calls.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+    let count = futures::executor::block_on(stream.count_bytes()).unwrap();
+    assert_eq!(count, 6);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_json_array_stream() {
+    use futures::StreamExt;
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Item {
+        name: String,
+        tags: Vec<u32>,
+    }
+
+    let stream = byte_stream(&[
+        " [ {\"name\": \"a, [b]\", \"ta",
+        "gs\": [1, 2]}",
+        ", {\"name\": \"\\\"q\\\"\", \"tags\": []} ]",
+    ])
+    .json_array_stream::<Item, ()>();
+    let items = futures::executor::block_on(stream.collect::<Vec<_>>())
+        .into_iter()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        items,
+        vec![
+            Item {
+                name: "a, [b]".to_string(),
+                tags: vec![1, 2],
+            },
+            Item {
+                name: "\"q\"".to_string(),
+                tags: vec![],
+            },
+        ],
+    );
+
+    let stream = byte_stream(&["[]"]).json_array_stream::<u32, ()>();
+    let items = futures::executor::block_on(stream.collect::<Vec<_>>());
+    assert!(items.is_empty());
+
+    let stream = byte_stream(&["[1, 2"]).json_array_stream::<u32, ()>();
+    let items = futures::executor::block_on(stream.collect::<Vec<_>>());
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].as_ref().unwrap(), &1);
+    assert!(matches!(items[1], Err(Error::InvalidResponsePayload(..))));
+}
+
+#[test]
+fn test_from_response_or_default() {
+    fn decode(status: u16, body: &'static str) -> Vec<u32> {
+        let response =
+            http::Response::builder().status(status).body(body).unwrap();
+        futures::executor::block_on(
+            ResponseValue::<Vec<u32>>::from_response_or_default::<()>(
+                response.into(),
+            ),
+        )
+        .unwrap()
+        .into_inner()
+    }
+
+    assert_eq!(decode(200, "[1, 2]"), vec![1, 2]);
+    assert_eq!(decode(200, ""), Vec::<u32>::new());
+    assert_eq!(decode(204, ""), Vec::<u32>::new());
+}
+
+#[test]
+fn test_suggested_filename() {
+    fn filename(value: &'static str) -> Option<String> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::CONTENT_DISPOSITION,
+            HeaderValue::from_static(value),
+        );
+        ResponseValue::new((), reqwest::StatusCode::OK, headers)
+            .suggested_filename()
+    }
+
+    assert_eq!(
+        filename("attachment; filename=\"report; final.pdf\"").as_deref(),
+        Some("report; final.pdf"),
+    );
+    assert_eq!(
+        filename("attachment; filename=plain.txt").as_deref(),
+        Some("plain.txt"),
+    );
+    assert_eq!(
+        filename(
+            "attachment; filename=\"fallback.txt\"; \
+             filename*=UTF-8''%E2%82%AC%20rates.txt"
+        )
+        .as_deref(),
+        Some("\u{20ac} rates.txt"),
+    );
+    assert_eq!(
+        filename("attachment; filename*=iso-8859-1'en'%A3%20rates.txt")
+            .as_deref(),
+        Some("\u{a3} rates.txt"),
+    );
+    assert_eq!(filename("inline"), None);
+}
+
+#[test]
+fn test_content_type_params() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        reqwest::header::CONTENT_TYPE,
+        HeaderValue::from_static(
+            "multipart/mixed; Boundary=\"a;b\"; charset=utf-8; charset=latin1",
+        ),
+    );
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, headers);
+    let params = rv.content_type_params();
+    assert_eq!(params.len(), 2);
+    assert_eq!(params["boundary"], "a;b");
+    assert_eq!(params["charset"], "utf-8");
+
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, HeaderMap::new());
+    assert!(rv.content_type_params().is_empty());
+}
+
+#[test]
+fn test_content_type() {
+    let content_type = |value: &'static str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::CONTENT_TYPE,
+            HeaderValue::from_static(value),
+        );
+        ResponseValue::new((), reqwest::StatusCode::OK, headers).content_type()
+    };
+
+    assert_eq!(
+        content_type("Text/HTML; charset=utf-8").as_deref(),
+        Some("text/html")
+    );
+    assert_eq!(
+        content_type("application/problem+json").as_deref(),
+        Some("application/problem+json")
+    );
+    assert_eq!(content_type("json"), None);
+
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, HeaderMap::new());
+    assert_eq!(rv.content_type(), None);
+}
+
+#[test]
+fn test_error_log_value() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        reqwest::header::AUTHORIZATION,
+        HeaderValue::from_static("Bearer secret"),
+    );
+    let e = Error::ErrorResponse(ResponseValue::new(
+        serde_json::json!({ "code": "denied" }),
+        reqwest::StatusCode::FORBIDDEN,
+        headers,
+    ));
+
+    let value = e.to_log_value();
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "kind": "ErrorResponse",
+            "status": 403,
+            "message": "error response: 403 Forbidden",
+            "body": { "code": "denied" },
+        }),
+    );
+    assert!(!value.to_string().contains("secret"));
+
+    let e = Error::<()>::InvalidRequest("missing body".to_string());
+    assert_eq!(
+        e.to_log_value(),
+        serde_json::json!({
+            "kind": "InvalidRequest",
+            "message": "missing body",
+        }),
+    );
+}
+
+#[test]
+fn test_error_body() {
+    let error = || {
+        Error::ErrorResponse(ResponseValue::new(
+            "denied".to_string(),
+            reqwest::StatusCode::FORBIDDEN,
+            HeaderMap::new(),
+        ))
+    };
+
+    let rv = error().into_error_response().unwrap();
+    assert_eq!(rv.status(), reqwest::StatusCode::FORBIDDEN);
+    assert_eq!(error().error_body().as_deref(), Some("denied"));
+
+    let e = || Error::<String>::InvalidRequest("missing body".to_string());
+    assert!(e().into_error_response().is_none());
+    assert_eq!(e().error_body(), None);
+}
+
+#[test]
+fn test_invalid_payload_preview() {
+    let decode = |body: Vec<u8>| {
+        let response: reqwest::Response = http::Response::builder()
+            .header("content-type", "application/json")
+            .body(body)
+            .unwrap()
+            .into();
+        futures::executor::block_on(ResponseValue::<u32>::from_response::<()>(
+            response,
+        ))
+        .unwrap_err()
+        .to_string()
+    };
+
+    let display = decode(b"{\"count\": \"x\"}".to_vec());
+    assert!(display.starts_with(
+        "Invalid Response Payload (\"{\\\"count\\\": \\\"x\\\"}\"): "
+    ));
+
+    // The preview doesn't split the character that straddles its end.
+    let display = decode(format!("a{}", "é".repeat(200)).into_bytes());
+    let preview = format!("(\"a{}\"... (401 bytes))", "é".repeat(127));
+    assert!(display.contains(&preview), "{}", display);
+
+    let display = decode(vec![0xff, 0x00]);
+    assert!(display.contains("(0xff00)"), "{}", display);
+}
+
+#[test]
+fn test_unexpected_with_body() {
+    let response: reqwest::Response = http::Response::builder()
+        .status(500)
+        .header("x-request-id", "abc")
+        .body("oops")
+        .unwrap()
+        .into();
+    let error = Error::<()>::UnexpectedResponse(response);
+    let error = futures::executor::block_on(error.into_unexpected_with_body());
+
+    match &error {
+        Error::UnexpectedResponseBody { headers, body, .. } => {
+            assert_eq!(headers["x-request-id"], "abc");
+            assert_eq!(body.as_ref(), b"oops");
+        }
+        _ => panic!("unexpected error {:?}", error),
+    }
+    assert_eq!(
+        error.status(),
+        Some(reqwest::StatusCode::INTERNAL_SERVER_ERROR)
+    );
+    assert!(error.is_retryable());
+    let display = error.to_string();
+    assert!(display.contains("body: \"oops\""), "{}", display);
+
+    // Other variants are passed through untouched.
+    let error = Error::<()>::InvalidRequest("bad".to_string());
+    let error = futures::executor::block_on(error.into_unexpected_with_body());
+    assert!(matches!(error, Error::InvalidRequest(s) if s == "bad"));
+}
+
+#[test]
+fn test_problem_details() {
+    use progenitor_client::ProblemDetails;
+
+    let response: reqwest::Response = http::Response::builder()
+        .status(403)
+        .header("content-type", "application/problem+json")
+        .body(
+            r#"{
+                "type": "https://example.com/probs/out-of-credit",
+                "title": "You do not have enough credit.",
+                "status": 403,
+                "balance": 30
+            }"#,
+        )
+        .unwrap()
+        .into();
+    let problem = futures::executor::block_on(
+        ResponseValue::<ProblemDetails>::from_response::<()>(response),
+    )
+    .unwrap()
+    .into_inner();
+
+    assert_eq!(
+        problem.type_.as_deref(),
+        Some("https://example.com/probs/out-of-credit")
+    );
+    assert_eq!(
+        problem.title.as_deref(),
+        Some("You do not have enough credit.")
+    );
+    assert_eq!(problem.status, Some(403));
+    assert_eq!(problem.detail, None);
+    assert_eq!(problem.extensions["balance"], 30);
+
+    let value = serde_json::to_value(&problem).unwrap();
+    assert_eq!(value["type"], "https://example.com/probs/out-of-credit");
+    assert_eq!(value["balance"], 30);
+    assert!(value.get("detail").is_none());
+}
+
+#[test]
+fn test_error_with_note() {
+    use std::error::Error as _;
+
+    let e = Error::<()>::InvalidRequest("missing body".to_string())
+        .with_note("creating instance \"web-1\"");
+    assert_eq!(e.note(), "creating instance \"web-1\"");
+    assert_eq!(
+        e.to_string(),
+        "creating instance \"web-1\": Invalid Request: missing body",
+    );
+    assert!(e.source().is_none());
+    assert!(matches!(e.into_error(), Error::InvalidRequest(_)));
+}
+
+#[test]
+fn test_error_redacts_headers() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        reqwest::header::AUTHORIZATION,
+        HeaderValue::from_static("Bearer secret"),
+    );
+    headers.insert(
+        reqwest::header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    let e = Error::ErrorResponse(ResponseValue::new(
+        (),
+        reqwest::StatusCode::FORBIDDEN,
+        headers,
+    ));
+
+    let display = e.to_string();
+    assert!(!display.contains("secret"));
+    assert!(display.contains("\"authorization\": <redacted>"));
+    assert!(display.contains("\"application/json\""));
+    assert_eq!(format!("{:?}", e), display);
+}
+
+#[cfg(feature = "content-md5")]
+#[test]
+fn test_content_md5() {
+    use progenitor_client::RequestBuilderExt;
+
+    let body = b"hello world";
+    let request = RequestBuilderExt::<()>::with_content_md5(
+        reqwest::Client::new().put("http://localhost/object"),
+        body,
+    )
+    .body(&body[..])
+    .build()
+    .unwrap();
+    assert_eq!(request.headers()["content-md5"], "XrY7u+Ae7tCTyyK7j1rNww==");
+}
+
+#[cfg(feature = "digest")]
+#[test]
+fn test_verify_digest() {
+    use futures::StreamExt;
+    use progenitor_client::{ChunkError, DigestAlgo};
+
+    let hex = |s: &str| {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect::<Vec<_>>()
+    };
+    let sha256 =
+        hex("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+    let md5 = hex("5eb63bbbe01eeed093cb22bb8f5acdc3");
+    let verify = |algo, expected: &[u8]| {
+        futures::executor::block_on(
+            byte_stream(&["hello", " ", "world"])
+                .verify_digest(algo, expected)
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    let chunks = verify(DigestAlgo::Sha256, &sha256);
+    let chunks = chunks.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+    assert_eq!(chunks, vec!["hello", " ", "world"]);
+    assert!(verify(DigestAlgo::Md5, &md5).iter().all(Result::is_ok));
+
+    // The chunks are passed through before the mismatch is reported.
+    let mut chunks = verify(DigestAlgo::Sha256, &md5);
+    assert_eq!(chunks.len(), 4);
+    let Some(Err(ChunkError::Map(mismatch))) = chunks.pop() else {
+        panic!("expected a mismatch");
+    };
+    assert_eq!(mismatch.algo, DigestAlgo::Sha256);
+    assert_eq!(mismatch.expected, md5);
+    assert_eq!(mismatch.actual, sha256);
+    assert!(mismatch.to_string().starts_with(
+        "Sha256 digest mismatch: expected 5eb63bbbe01eeed093cb22bb8f5acdc3, \
+        got b94d27b9"
+    ));
+}
+
+/// Builds a byte-stream response with the given `Content-Encoding`, whose
+/// body is sent in `chunks`.
+#[cfg(feature = "decompression")]
+fn encoded_response(
+    encoding: Option<&'static str>,
+    chunks: &[&[u8]],
+) -> ResponseValue<ByteStream> {
+    let chunks = chunks
+        .iter()
+        .map(|chunk| Ok::<_, reqwest::Error>(Bytes::copy_from_slice(chunk)))
+        .collect::<Vec<_>>();
+    let mut headers = HeaderMap::new();
+    if let Some(encoding) = encoding {
+        headers.insert("content-encoding", HeaderValue::from_static(encoding));
+    }
+    ResponseValue::new(
+        ByteStream::new(Box::pin(futures::stream::iter(chunks))),
+        reqwest::StatusCode::OK,
+        headers,
+    )
+}
+
+#[cfg(feature = "decompression")]
+fn gzip(data: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut encoder =
+        flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[cfg(feature = "decompression")]
+#[test]
+fn test_decoded() {
+    use futures::StreamExt;
+    use progenitor_client::ChunkError;
+    use std::io::Write;
+
+    let decoded = |rv: ResponseValue<ByteStream>| {
+        let Ok(stream) = rv.decoded() else {
+            panic!("expected a supported encoding");
+        };
+        futures::executor::block_on(stream.collect::<Vec<_>>())
+    };
+    let body = |rv| {
+        decoded(rv)
+            .into_iter()
+            .map(|chunk| chunk.unwrap())
+            .collect::<Vec<_>>()
+            .concat()
+    };
+
+    // Compressed bodies are decoded across chunk boundaries.
+    let compressed = gzip(b"hello world");
+    let (head, tail) = compressed.split_at(5);
+    for encoding in ["gzip", "x-gzip", "GZIP"] {
+        let rv = encoded_response(Some(encoding), &[head, tail]);
+        assert_eq!(body(rv), b"hello world");
+    }
+    let mut encoder = flate2::write::ZlibEncoder::new(
+        Vec::new(),
+        flate2::Compression::fast(),
+    );
+    encoder.write_all(b"hello world").unwrap();
+    let compressed = encoder.finish().unwrap();
+    let rv = encoded_response(Some("deflate"), &[&compressed]);
+    assert_eq!(body(rv), b"hello world");
+
+    // Unencoded bodies are passed through.
+    for encoding in [None, Some("identity")] {
+        let rv = encoded_response(encoding, &[b"hello", b" world"]);
+        assert_eq!(body(rv), b"hello world");
+    }
+
+    // Unsupported and stacked encodings give back the response.
+    for encoding in ["br", "gzip, br", "gzip, gzip"] {
+        let rv = encoded_response(Some(encoding), &[&compressed]);
+        let rv = rv.decoded().map(|_| ()).unwrap_err();
+        assert_eq!(rv.headers()["content-encoding"], encoding);
+    }
+
+    // Malformed data ends the stream with an error.
+    let chunks =
+        decoded(encoded_response(Some("gzip"), &[b"not gzip", b"at all"]));
+    assert_eq!(chunks.len(), 1);
+    assert!(matches!(chunks[0], Err(ChunkError::Map(_))));
+}
+
+#[cfg(feature = "decompression")]
+#[test]
+fn test_decompressed_lenient() {
+    use futures::StreamExt;
+
+    let body = |rv: ResponseValue<ByteStream>| {
+        let Ok(stream) = rv.decompressed_lenient() else {
+            panic!("expected a supported encoding");
+        };
+        futures::executor::block_on(stream.collect::<Vec<_>>())
+            .into_iter()
+            .map(|chunk| chunk.unwrap())
+            .collect::<Vec<_>>()
+            .concat()
+    };
+
+    let compressed = gzip(b"hello world");
+    let (head, tail) = compressed.split_at(5);
+    let rv = encoded_response(Some("gzip"), &[head, tail]);
+    assert_eq!(body(rv), b"hello world");
+
+    // A body that isn't encoded as claimed is passed through, in full.
+    let rv = encoded_response(Some("gzip"), &[b"hello", b" world"]);
+    assert_eq!(body(rv), b"hello world");
+
+    // As is a compressed body without an encoding.
+    let rv = encoded_response(None, &[&compressed]);
+    assert_eq!(body(rv), compressed);
+
+    let rv = encoded_response(Some("br"), &[&compressed]);
+    assert!(rv.decompressed_lenient().is_err());
+}
+
+#[cfg(feature = "debug-schema")]
+#[test]
+fn test_debug_hook() {
+    use progenitor_client::{
+        debug_request_body, set_debug_hook, DebugSnapshot,
+    };
+    use std::sync::{Arc, Mutex};
+
+    // The hook is process-wide, so only record this test's operation.
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    set_debug_hook({
+        let seen = seen.clone();
+        move |snapshot: &DebugSnapshot<'_>| match snapshot {
+            DebugSnapshot::Request {
+                operation_id: "debug_hook",
+                body,
+            } => seen.lock().unwrap().push((None, (*body).clone())),
+            DebugSnapshot::Response {
+                operation_id: "debug_hook",
+                status,
+                body,
+            } => seen.lock().unwrap().push((Some(*status), (*body).clone())),
+            _ => {}
+        }
+    });
+
+    let body = serde_json::json!({ "name": "widget" });
+    assert_eq!(debug_request_body("debug_hook", &body), &body);
+
+    let response = |body: &'static str| {
+        reqwest::Response::from(
+            http::Response::builder()
+                .status(201)
+                .header("content-type", "application/json")
+                .body(body)
+                .unwrap(),
+        )
+    };
+    futures::executor::block_on(async {
+        let value = ResponseValue::<Vec<u32>>::from_response_debug::<()>(
+            response("[1, 2]"),
+            "debug_hook",
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(*value, vec![1, 2]);
+
+        // A body that isn't JSON is reported as a string.
+        ResponseValue::<Vec<u32>>::from_response_debug::<()>(
+            response("oops"),
+            "debug_hook",
+            false,
+        )
+        .await
+        .unwrap_err();
+    });
+
+    assert_eq!(
+        *seen.lock().unwrap(),
+        [
+            (None, body),
+            (
+                Some(reqwest::StatusCode::CREATED),
+                serde_json::json!([1, 2]),
+            ),
+            (
+                Some(reqwest::StatusCode::CREATED),
+                serde_json::Value::String("oops".to_string()),
+            ),
+        ],
+    );
+}
+
+#[test]
+fn test_upload_progress() {
+    use progenitor_client::RequestBuilderExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const LEN: usize = 150_000;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .unwrap();
+
+    let progress = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let head = runtime.block_on(async {
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = vec![0; 16 << 10];
+            let head = loop {
+                let n = stream.read(&mut chunk).await.unwrap();
+                assert!(n > 0, "request ended early");
+                buf.extend_from_slice(&chunk[..n]);
+                let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n")
+                else {
+                    continue;
+                };
+                if buf.len() >= end + 4 + LEN {
+                    break String::from_utf8_lossy(&buf[..end]).to_lowercase();
+                }
+            };
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .await
+                .unwrap();
+            head
+        });
+
+        let recorded = progress.clone();
+        let response = RequestBuilderExt::<()>::upload_progress(
+            reqwest::Client::new()
+                .put(format!("http://{}/upload", addr))
+                .body(vec![7u8; LEN]),
+            None,
+            move |sent, total| recorded.lock().unwrap().push((sent, total)),
+        )
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+        server.await.unwrap()
+    });
+
+    assert!(head.contains("content-length: 150000"), "{}", head);
+    assert!(!head.contains("transfer-encoding"), "{}", head);
+    let total = Some(LEN as u64);
+    assert_eq!(
+        *progress.lock().unwrap(),
+        [(65536, total), (131072, total), (LEN as u64, total)],
+    );
+}
+
+#[test]
+fn test_correlation_id() {
+    use progenitor_client::RequestBuilderExt;
+
+    let request = RequestBuilderExt::<()>::correlation_id(
+        reqwest::Client::new().get("http://localhost/"),
+        "abc-123",
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+    assert_eq!(request.headers()["x-correlation-id"], "abc-123");
+
+    let mut headers = HeaderMap::new();
+    headers.insert("x-correlation-id", HeaderValue::from_static("abc-123"));
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, headers);
+    assert_eq!(rv.request_id(), Some("abc-123"));
+}
+
+#[test]
+fn test_traceparent() {
+    use progenitor_client::{TraceparentTransport, Transport, TransportFn};
+    use std::sync::{Arc, Mutex};
+
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let transport = TraceparentTransport::new(TransportFn::new({
+        let sent = sent.clone();
+        move |request: reqwest::Request| {
+            sent.lock()
+                .unwrap()
+                .push(request.headers()["traceparent"].clone());
+            Ok(http::Response::new("").into())
+        }
+    }));
+    let send = |request: reqwest::RequestBuilder| {
+        futures::executor::block_on(
+            transport.execute(request.build().unwrap()),
+        )
+        .unwrap();
+    };
+
+    let client = reqwest::Client::new();
+    send(client.get("http://localhost/"));
+    send(client.get("http://localhost/"));
+    let existing = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+    send(
+        client
+            .get("http://localhost/")
+            .header("traceparent", existing),
+    );
+
+    let sent = sent.lock().unwrap();
+    for value in &sent[..2] {
+        // version-trace_id-parent_id-flags, in lowercase hex
+        let parts = value.to_str().unwrap().split('-').collect::<Vec<_>>();
+        assert_eq!(
+            parts.iter().map(|part| part.len()).collect::<Vec<_>>(),
+            [2, 32, 16, 2],
+        );
+        assert_eq!((parts[0], parts[3]), ("00", "00"));
+        assert!(parts.iter().all(|part| part
+            .chars()
+            .all(|c| matches!(c, '0'..='9' | 'a'..='f'))));
+    }
+    // Each request gets its own trace.
+    assert_ne!(sent[0], sent[1]);
+    // An existing header is passed through unchanged.
+    assert_eq!(sent[2], existing);
+}
+
+#[cfg(feature = "form")]
+#[test]
+fn test_form_urlencoded() {
+    use progenitor_client::RequestBuilderExt;
+
+    let form = |body: &[(&str, serde_json::Value)]| {
+        RequestBuilderExt::<()>::form_urlencoded(
+            reqwest::Client::new().post("http://localhost/widgets"),
+            body,
+        )
+    };
+
+    let request = form(&[
+        ("name", "blue widget".into()),
+        ("size", "s&m".into()),
+        ("count", 2.into()),
+    ])
+    .unwrap()
+    .build()
+    .unwrap();
+    assert_eq!(
+        request.headers()["content-type"],
+        "application/x-www-form-urlencoded",
+    );
+    assert_eq!(
+        request.body().unwrap().as_bytes(),
+        Some(&b"name=blue+widget&size=s%26m&count=2"[..]),
+    );
+
+    // Nested values have no form encoding.
+    let Err(Error::InvalidRequest(message)) =
+        form(&[("tags", serde_json::json!(["a", "b"]))])
+    else {
+        panic!("expected an invalid request");
+    };
+    assert_eq!(message, "failed to serialize body");
+}
+
+#[test]
+fn test_raw_json() {
+    use progenitor_client::RequestBuilderExt;
+
+    // The bytes are sent as given, including the spacing and key order.
+    let body = "{ \"b\": 1,  \"a\": [true] }";
+    let request = RequestBuilderExt::<()>::raw_json(
+        reqwest::Client::new().post("http://localhost/widgets"),
+        body,
+    )
+    .build()
+    .unwrap();
+    assert_eq!(request.headers()["content-type"], "application/json");
+    assert_eq!(request.body().unwrap().as_bytes(), Some(body.as_bytes()));
+}
+
+#[test]
+fn test_propagate_headers() {
+    use progenitor_client::{PropagateHeaders, Transport, TransportFn};
+    use reqwest::header::HeaderName;
+    use std::sync::{Arc, Mutex};
+
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let inner = TransportFn::new({
+        let sent = sent.clone();
+        move |request: reqwest::Request| {
+            sent.lock().unwrap().push(request.headers().clone());
+            Ok(http::Response::new("").into())
+        }
+    });
+    let incoming = Arc::new(Mutex::new(None));
+    let transport = PropagateHeaders::new(
+        inner,
+        &[HeaderName::from_static("x-tenant-id")],
+        {
+            let incoming = incoming.clone();
+            move || incoming.lock().unwrap().clone()
+        },
+    );
+    let send = |request: reqwest::RequestBuilder| {
+        futures::executor::block_on(
+            transport.execute(request.build().unwrap()),
+        )
+        .unwrap();
+        sent.lock().unwrap().pop().unwrap()
+    };
+    let client = reqwest::Client::new();
+
+    // Without a context, nothing is added.
+    let headers = send(client.get("http://localhost/"));
+    assert!(headers.get("x-tenant-id").is_none());
+
+    let mut context = HeaderMap::new();
+    context.append("x-tenant-id", HeaderValue::from_static("acme"));
+    context.append("x-tenant-id", HeaderValue::from_static("globex"));
+    context.insert("authorization", HeaderValue::from_static("secret"));
+    *incoming.lock().unwrap() = Some(context);
+
+    // Only the named headers are copied, with all of their values.
+    let headers = send(client.get("http://localhost/"));
+    assert_eq!(
+        headers.get_all("x-tenant-id").iter().collect::<Vec<_>>(),
+        ["acme", "globex"],
+    );
+    assert!(headers.get("authorization").is_none());
+
+    // Headers the request already sets are left alone.
+    let headers = send(
+        client
+            .get("http://localhost/")
+            .header("x-tenant-id", "initech"),
+    );
+    assert_eq!(
+        headers.get_all("x-tenant-id").iter().collect::<Vec<_>>(),
+        ["initech"],
+    );
+}
+
+#[test]
+fn test_retriable_statuses() {
+    let factory =
+        progenitor_client::BodyFactory::new(|| reqwest::Body::from(""));
+    let attempts = |status: u16| {
+        let mut count = 0;
+        let status = reqwest::StatusCode::from_u16(status).unwrap();
+        let _ = futures::executor::block_on(factory.retry(3, |_| {
+            count += 1;
+            let rv = ResponseValue::new((), status, HeaderMap::new());
+            async move { Err::<(), _>(Error::ErrorResponse(rv)) }
+        }));
+        count
+    };
+
+    for status in [408, 425, 429, 500, 502, 503, 504] {
+        assert_eq!(attempts(status), 3, "{} should be retried", status);
+    }
+    for status in [400, 401, 403, 404, 409, 413, 422] {
+        assert_eq!(attempts(status), 1, "{} should not be retried", status);
+    }
+}
+
+#[test]
+fn test_error_response() {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-request-id", HeaderValue::from_static("r1"));
+    let e = Error::error_response(
+        "conflict".to_string(),
+        reqwest::StatusCode::CONFLICT,
+        headers,
+    );
+    assert_eq!(e.status(), Some(reqwest::StatusCode::CONFLICT));
+
+    let rv = e.into_error_response().unwrap();
+    assert_eq!(rv.headers()["x-request-id"], "r1");
+    assert_eq!(rv.into_inner(), "conflict");
+}
+
+#[test]
+fn test_is_payload_too_large() {
+    let error_response = |status| {
+        Error::error_response("rejected".to_string(), status, HeaderMap::new())
+    };
+    assert!(error_response(reqwest::StatusCode::PAYLOAD_TOO_LARGE)
+        .is_payload_too_large());
+    assert!(!error_response(reqwest::StatusCode::BAD_REQUEST)
+        .is_payload_too_large());
+
+    // The status of an undocumented response counts too.
+    let response = http::Response::builder()
+        .status(413)
+        .body("too large")
+        .unwrap();
+    let e = Error::<()>::UnexpectedResponse(response.into());
+    assert!(e.is_payload_too_large());
+
+    let e = Error::<()>::InvalidRequest("too large".to_string());
+    assert!(!e.is_payload_too_large());
+}
+
+#[test]
+fn test_priority() {
+    use progenitor_client::{Priority, RequestBuilderExt};
+
+    let request = RequestBuilderExt::<()>::priority(
+        reqwest::Client::new().get("http://localhost/"),
+        Priority::BACKGROUND.incremental(true),
+    )
+    .build()
+    .unwrap();
+    assert_eq!(request.headers()["priority"], "u=6, i");
+    assert_eq!(Priority::new(12).urgency(), 7);
+}
+
+#[test]
+fn test_graphql() {
+    use progenitor_client::RequestBuilderExt;
+
+    let request = RequestBuilderExt::<()>::graphql(
+        reqwest::Client::new().post("http://localhost/graphql"),
+        "query Widget($id: ID!) { widget(id: $id) { name } }",
+        Some("Widget"),
+        serde_json::json!({ "id": "w1" }),
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+    assert_eq!(request.headers()["content-type"], "application/json");
+    let body: serde_json::Value =
+        serde_json::from_slice(request.body().unwrap().as_bytes().unwrap())
+            .unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "query": "query Widget($id: ID!) { widget(id: $id) { name } }",
+            "operationName": "Widget",
+            "variables": { "id": "w1" },
+        }),
+    );
+}
+
+#[test]
+fn test_server_timing() {
+    use progenitor_client::ServerTiming;
+
+    let mut headers = HeaderMap::new();
+    headers.append(
+        "server-timing",
+        HeaderValue::from_static(
+            "cache;desc=\"Cache, Read\";dur=23.2, db;dur=53",
+        ),
+    );
+    headers.append("server-timing", HeaderValue::from_static("miss, ;dur=1"));
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, headers);
+    assert_eq!(
+        rv.server_timing(),
+        vec![
+            ServerTiming {
+                name: "cache".to_string(),
+                dur: Some(23.2),
+                desc: Some("Cache, Read".to_string()),
+            },
+            ServerTiming {
+                name: "db".to_string(),
+                dur: Some(53.0),
+                desc: None,
+            },
+            ServerTiming {
+                name: "miss".to_string(),
+                dur: None,
+                desc: None,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_merge_patch_diff() {
+    use progenitor_client::RequestBuilderExt;
+
+    let old = serde_json::json!({
+        "name": "widget",
+        "size": { "width": 1, "height": 2 },
+        "color": "red",
+        "tags": ["a"],
+    });
+    let new = serde_json::json!({
+        "name": "widget",
+        "size": { "width": 3, "height": 2 },
+        "tags": ["a", "b"],
+        "owner": "me",
+    });
+    let request = RequestBuilderExt::<()>::merge_patch_diff(
+        reqwest::Client::new().patch("http://localhost/widgets/1"),
+        &old,
+        &new,
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+    assert_eq!(
+        request.headers()["content-type"],
+        "application/merge-patch+json"
+    );
+    let body: serde_json::Value =
+        serde_json::from_slice(request.body().unwrap().as_bytes().unwrap())
+            .unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "size": { "width": 3 },
+            "color": null,
+            "tags": ["a", "b"],
+            "owner": "me",
+        }),
+    );
+}
+
+#[test]
+fn test_idempotency_replayed() {
+    let mut headers = HeaderMap::new();
+    headers.insert("idempotency-key", HeaderValue::from_static("key-1"));
+    headers.insert("idempotent-replayed", HeaderValue::from_static("True"));
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, headers);
+    assert_eq!(rv.idempotency_key(), Some("key-1"));
+    assert_eq!(rv.idempotency_replayed(), Some(true));
+
+    let mut headers = HeaderMap::new();
+    headers.insert("idempotent-replayed", HeaderValue::from_static("false"));
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, headers);
+    assert_eq!(rv.idempotency_key(), None);
+    assert_eq!(rv.idempotency_replayed(), Some(false));
+
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, HeaderMap::new());
+    assert_eq!(rv.idempotency_replayed(), None);
+}
+
+#[test]
+fn test_batch() {
+    let operations = (0..5).map(|i| async move {
+        if i % 2 == 0 {
+            Ok(i)
+        } else {
+            Err(Error::<()>::InvalidRequest(format!("odd {}", i)))
+        }
+    });
+    let results =
+        futures::executor::block_on(progenitor_client::batch(operations, 2));
+
+    assert!(!results.is_all_ok());
+    assert_eq!(
+        results
+            .failures()
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>(),
+        vec![1, 3],
+    );
+    let values = results
+        .into_results()
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec![0, 2, 4]);
+}
+
+#[test]
+fn test_batch_limit() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let in_flight = AtomicUsize::new(0);
+    let max_in_flight = AtomicUsize::new(0);
+    let operations = (0..7).map(|i| {
+        let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+        async move {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_in_flight.fetch_max(now, Ordering::SeqCst);
+            // Yield once so that operations overlap.
+            let mut yielded = false;
+            futures::future::poll_fn(|cx| {
+                if yielded {
+                    return std::task::Poll::Ready(());
+                }
+                yielded = true;
+                cx.waker().wake_by_ref();
+                std::task::Poll::Pending
+            })
+            .await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok::<_, Error<()>>(i * 10)
+        }
+    });
+    let results =
+        futures::executor::block_on(progenitor_client::batch(operations, 3));
+
+    assert!(results.is_all_ok());
+    let values = results
+        .into_results()
+        .into_iter()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec![0, 10, 20, 30, 40, 50, 60]);
+    assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+}
+
+#[cfg(all(unix, feature = "unix-socket"))]
+#[test]
+fn test_unix_socket_transport() {
+    use futures::StreamExt;
+    use progenitor_client::{Transport, UnixSocketTransport};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let path = std::env::temp_dir()
+        .join(format!("progenitor-test-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .unwrap();
+
+    let body = runtime.block_on(async {
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 1024];
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(buf[..n].starts_with(b"GET /widgets?page=2 HTTP/1.1\r\n"));
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\n\
+                    transfer-encoding: chunked\r\n\
+                    \r\n\
+                    5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n",
+                )
+                .await
+                .unwrap();
+        });
+
+        let request = reqwest::Client::new()
+            .get("http://localhost/widgets?page=2")
+            .build()
+            .unwrap();
+        let response = UnixSocketTransport::new(&path)
+            .execute(request)
+            .await
+            .unwrap();
+        let mut stream = ResponseValue::stream(response).into_inner_stream();
+        let mut body = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            body.extend_from_slice(&chunk.unwrap());
+        }
+        server.await.unwrap();
+        body
+    });
+
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(body, b"hello world");
+}
+
+#[test]
+fn test_concurrency_limit() {
+    use progenitor_client::{ConcurrencyLimit, Transport, TransportFn};
+
+    let transport = ConcurrencyLimit::new(
+        TransportFn::new(|_request| Ok(http::Response::new("[]").into())),
+        1,
+    );
+    let request = |url: &str| reqwest::Client::new().get(url).build().unwrap();
+
+    futures::executor::block_on(async {
+        let first = transport
+            .execute(request("http://a.example/"))
+            .await
+            .unwrap();
+
+        // Another host has its own limit.
+        let other = transport.execute(request("http://b.example/")).await;
+        assert!(other.is_ok());
+
+        // The first response holds its host's only slot until it's dropped.
+        let mut second = transport.execute(request("http://a.example/"));
+        assert!(futures::poll!(&mut second).is_pending());
+        drop(first);
+        assert!(second.await.is_ok());
+    });
+}
+
+#[test]
+fn test_retry_budget() {
+    use progenitor_client::{BodyFactory, RetryBudget};
+
+    let budget = std::sync::Arc::new(RetryBudget::new(0.0, 1));
+    let factory =
+        BodyFactory::new(|| reqwest::Body::from("")).retry_budget(budget);
+    let mut attempts = 0;
+    let result = futures::executor::block_on(factory.retry(5, |_| {
+        attempts += 1;
+        let e = Error::error_response(
+            (),
+            reqwest::StatusCode::SERVICE_UNAVAILABLE,
+            HeaderMap::new(),
+        );
+        async move { Err::<(), _>(e) }
+    }));
+    assert!(result.is_err());
+    // Only the one retry allowed by the per-second reserve is made.
+    assert_eq!(attempts, 2);
+
+    let budget = RetryBudget::new(0.5, 0);
+    assert!(!budget.try_withdraw());
+    budget.deposit();
+    budget.deposit();
+    assert!(budget.try_withdraw());
+    assert!(!budget.try_withdraw());
+}
+
+#[test]
+fn test_is_stale() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "date",
+        HeaderValue::from_static("Sun, 06 Nov 1994 08:49:37 GMT"),
+    );
+    headers.insert("age", HeaderValue::from_static("30"));
+    headers.insert(
+        "cache-control",
+        HeaderValue::from_static("public, max-age=\"60\""),
+    );
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, headers);
+    assert_eq!(
+        rv.header_date(),
+        Some(UNIX_EPOCH + Duration::from_secs(784_111_777)),
+    );
+    assert_eq!(rv.age(), Some(Duration::from_secs(30)));
+    assert_eq!(rv.max_age(), Some(Duration::from_secs(60)));
+    // Long since generated, so stale however long it's been in caches.
+    assert_eq!(rv.is_stale(), Some(true));
+
+    let mut headers = HeaderMap::new();
+    headers.insert("age", HeaderValue::from_static("30"));
+    headers.insert("cache-control", HeaderValue::from_static("max-age=60"));
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, headers);
+    assert_eq!(rv.is_stale(), Some(false));
+
+    let mut headers = HeaderMap::new();
+    headers.insert("age", HeaderValue::from_static("30"));
+    headers.insert("expires", HeaderValue::from_static("0"));
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, headers);
+    assert_eq!(rv.is_stale(), Some(true));
+
+    let mut headers = HeaderMap::new();
+    headers.insert("cache-control", HeaderValue::from_static("max-age=60"));
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, headers);
+    assert_eq!(rv.is_stale(), None);
+}
+
+#[test]
+fn test_transport_fn() {
+    use progenitor_client::{Transport, TransportFn};
+
+    // Send requests as a generated client with a custom transport does.
+    let send = |transport: &dyn Transport, url: &str| {
+        let request = reqwest::Client::new()
+            .get(url)
+            .header("accept", "application/json")
+            .build()
+            .unwrap();
+        futures::executor::block_on(async {
+            let response = transport
+                .execute(request)
+                .await
+                .map_err(Error::<()>::from_transport)?;
+            ResponseValue::<Vec<String>>::from_response(response).await
+        })
+    };
+
+    let transport = TransportFn::new(|request| {
+        assert_eq!(request.method(), reqwest::Method::GET);
+        assert_eq!(request.headers()["accept"], "application/json");
+        let widgets = match request.url().path() {
+            "/widgets" => r#"["a", "b"]"#,
+            _ => "[]",
+        };
+        Ok(http::Response::builder()
+            .header("content-type", "application/json")
+            .header("x-served-by", "transport")
+            .body(widgets)
+            .unwrap()
+            .into())
+    });
+    let rv = send(&transport, "http://localhost/widgets").unwrap();
+    assert_eq!(rv.status(), reqwest::StatusCode::OK);
+    assert_eq!(rv.headers()["x-served-by"], "transport");
+    assert_eq!(rv.into_inner(), ["a", "b"]);
+
+    let transport = TransportFn::new(|_request| {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::ConnectionReset,
+            "socket closed",
+        )
+        .into())
+    });
+    match send(&transport, "http://localhost/widgets") {
+        Err(e @ Error::TransportError(_)) => {
+            assert_eq!(e.to_string(), "Transport Error: socket closed");
+            assert!(std::error::Error::source(&e).is_some());
+        }
+        other => panic!("unexpected result {:?}", other.map(|rv| rv.len())),
+    }
+}
+
+#[cfg(all(
+    any(feature = "tls-info", feature = "tls-info-rustls"),
+    not(target_arch = "wasm32")
+))]
+#[test]
+fn test_tls_info() {
+    use progenitor_client::ClientBuilder;
+
+    assert!(ClientBuilder::new().tls_info(true).build().is_ok());
+
+    // Responses that weren't received over TLS carry no TLS info.
+    let response: reqwest::Response = http::Response::builder()
+        .header("content-type", "application/json")
+        .body("[]")
+        .unwrap()
+        .into();
+    let rv = futures::executor::block_on(
+        ResponseValue::<Vec<u32>>::from_response::<()>(response),
+    )
+    .unwrap();
+    assert!(rv.tls_info().is_none());
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, HeaderMap::new());
+    assert!(rv.tls_info().is_none());
+}
+
+#[test]
+fn test_tls_error() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let get = |url: String| {
+        runtime.block_on(async {
+            let e = reqwest::get(url).await.unwrap_err();
+            Error::<()>::CommunicationError(e)
+        })
+    };
+
+    // A refused connection is a connection error, but not a TLS one.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let e = get(format!("http://{}/", addr));
+    assert!(!e.is_tls_error());
+    assert_eq!(e.tls_error_message(), None);
+    assert!(!e.to_string().contains("TLS failure"));
+
+    // Nor is a failure unrelated to the connection, whatever its message.
+    let e = Error::<()>::InvalidRequest("bad certificate name".to_string());
+    assert!(!e.is_tls_error());
+
+    // A server that answers a TLS handshake with plain HTTP.
+    #[cfg(feature = "tls-info")]
+    {
+        use std::io::Write;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let _ = stream.unwrap().write_all(
+                    b"HTTP/1.1 400 Bad Request\r\ncontent-length: 0\r\n\r\n",
+                );
+            }
+        });
+        let e = get(format!("https://{}/", addr));
+        assert!(e.is_tls_error());
+        let message = e.tls_error_message().unwrap();
+        assert!(e.to_string().starts_with(&format!(
+            "Communication Error: TLS failure: {}: ",
+            message
+        )));
+    }
+}
+
+#[test]
+fn test_default_query() {
+    use progenitor_client::{DefaultQuery, Transport, TransportFn};
+
+    let transport = DefaultQuery::new(
+        TransportFn::new(|request| {
+            Ok(http::Response::new(request.url().to_string()).into())
+        }),
+        &[("api-version", "2024-01-01"), ("partner", "a&b")],
+    );
+    let url = |url: &str| {
+        let request = reqwest::Client::new().get(url).build().unwrap();
+        futures::executor::block_on(async {
+            transport
+                .execute(request)
+                .await
+                .unwrap()
+                .text()
+                .await
+                .unwrap()
+        })
+    };
+
+    assert_eq!(
+        url("http://a.example/items"),
+        "http://a.example/items?api-version=2024-01-01&partner=a%26b",
+    );
+    assert_eq!(
+        url("http://a.example/items?api-version=1&limit=2"),
+        "http://a.example/items?api-version=1&limit=2&partner=a%26b",
+    );
+}
+
+#[test]
+fn test_close_on_cancel() {
+    use progenitor_client::{CloseOnCancel, Transport, TransportFn};
+
+    let transport = CloseOnCancel::new(TransportFn::new(|request| {
+        let connection = request.headers().get("connection").cloned();
+        Ok(http::Response::new(format!("{:?}", connection)).into())
+    }));
+    let request = reqwest::Client::new()
+        .get("http://a.example/")
+        .build()
+        .unwrap();
+    let body = futures::executor::block_on(async {
+        transport
+            .execute(request)
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap()
+    });
+    assert_eq!(body, "Some(\"close\")");
+}
+
+#[test]
+fn test_retain_bodies() {
+    use progenitor_client::{RetainBodies, Transport, TransportFn};
+
+    let inner = || {
+        TransportFn::new(|_request| {
+            Ok(http::Response::builder()
+                .header("content-type", "application/json")
+                .body("[1, 2, 3]")
+                .unwrap()
+                .into())
+        })
+    };
+    let request = || {
+        reqwest::Client::new()
+            .get("http://a.example/")
+            .build()
+            .unwrap()
+    };
+
+    futures::executor::block_on(async {
+        let transport = RetainBodies::new(inner());
+        let response = transport.execute(request()).await.unwrap();
+        let rv = ResponseValue::<Vec<u32>>::from_response::<()>(response)
+            .await
+            .unwrap();
+        assert_eq!(rv.raw_body().map(|b| &b[..]), Some(&b"[1, 2, 3]"[..]));
+        assert_eq!(rv.retained_len(), Some(9));
+        let rv = rv.map::<_, _, ()>(|v| v.len()).unwrap();
+        assert_eq!(rv.retained_len(), Some(9));
+
+        let response = inner().execute(request()).await.unwrap();
+        let rv = ResponseValue::<Vec<u32>>::from_response::<()>(response)
+            .await
+            .unwrap();
+        assert_eq!(rv.raw_body(), None);
+        assert_eq!(rv.retained_len(), None);
+    });
+}
+
+#[test]
+fn test_request_log() {
+    use progenitor_client::{
+        RecordRequests, RequestLog, Transport, TransportFn,
+    };
+
+    let log = std::sync::Arc::new(RequestLog::new(2, 4));
+    let transport = RecordRequests::new(
+        TransportFn::new(|_request| {
+            Ok(http::Response::builder()
+                .header("content-type", "application/json")
+                .header("set-cookie", "session=secret")
+                .body("[1, 2, 3]")
+                .unwrap()
+                .into())
+        }),
+        log.clone(),
+    );
+    let request = |path: &str| {
+        reqwest::Client::new()
+            .post(format!("http://a.example/{}", path))
+            .bearer_auth("secret")
+            .body("request body")
+            .build()
+            .unwrap()
+    };
+
+    futures::executor::block_on(async {
+        for path in ["first", "second", "third"] {
+            let response = transport.execute(request(path)).await.unwrap();
+            let _ = ResponseValue::<Vec<u32>>::from_response::<()>(response)
+                .await
+                .unwrap();
+        }
+    });
+
+    let recent = log.recent();
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[0].url.path(), "/second");
+    assert_eq!(recent[1].url.path(), "/third");
+    assert_eq!(recent[1].status, Some(reqwest::StatusCode::OK));
+    assert_eq!(recent[1].request_headers["authorization"], "<redacted>");
+    assert_eq!(recent[1].response_headers["set-cookie"], "<redacted>");
+    assert_eq!(recent[1].request_body.as_deref(), Some(&b"requ"[..]));
+    assert_eq!(recent[1].response_body.as_deref(), Some(&b"[1, "[..]));
+}
+
+#[test]
+fn test_bearer_error() {
+    use progenitor_client::BearerError;
+
+    let mut headers = HeaderMap::new();
+    headers.append(
+        "www-authenticate",
+        HeaderValue::from_static("Basic realm=\"api\""),
+    );
+    headers.append(
+        "www-authenticate",
+        HeaderValue::from_static(
+            "Negotiate, Bearer realm=\"api\", error=\"insufficient_scope\", \
+             error_description=\"Needs write, not read\", scope=\"write\"",
+        ),
+    );
+    let rv = ResponseValue::new((), reqwest::StatusCode::FORBIDDEN, headers);
+    let error = rv.bearer_error().unwrap();
+    assert_eq!(
+        error,
+        BearerError {
+            error: "insufficient_scope".to_string(),
+            error_description: Some("Needs write, not read".to_string()),
+            error_uri: None,
+            scope: Some("write".to_string()),
+        },
+    );
+    assert!(error.is_insufficient_scope());
+    assert!(!error.is_invalid_token());
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "www-authenticate",
+        HeaderValue::from_static("Bearer realm=\"api\""),
+    );
+    let rv = ResponseValue::new((), reqwest::StatusCode::UNAUTHORIZED, headers);
+    assert_eq!(rv.bearer_error(), None);
+}
+
+#[test]
+fn test_validation_errors() {
+    use progenitor_client::ValidationErrors;
+    use serde_json::json;
+
+    let errors = |value: serde_json::Value| {
+        let error = Error::error_response(
+            value,
+            reqwest::StatusCode::UNPROCESSABLE_ENTITY,
+            HeaderMap::new(),
+        );
+        assert!(error.is_validation_error());
+        error.validation_errors().map(|errors| {
+            errors
+                .field_errors
+                .into_iter()
+                .collect::<Vec<(String, Vec<String>)>>()
+        })
+    };
+    let field = |name: &str, messages: &[&str]| {
+        (
+            name.to_string(),
+            messages.iter().map(|m| m.to_string()).collect::<Vec<_>>(),
+        )
+    };
+
+    assert_eq!(
+        errors(json!({"errors": [
+            {
+                "source": {"pointer": "/data/attributes/name"},
+                "detail": "is blank",
+            },
+            {"source": {"parameter": "page"}, "title": "is invalid"},
+            {"detail": "is stale"},
+        ]})),
+        Some(vec![
+            field("", &["is stale"]),
+            field("name", &["is blank"]),
+            field("page", &["is invalid"]),
+        ]),
+    );
+    assert_eq!(
+        errors(
+            json!({"errors": {"name": ["is blank", "is short"], "base": "no"}})
+        ),
+        Some(vec![
+            field("", &["no"]),
+            field("name", &["is blank", "is short"])
+        ]),
+    );
+    assert_eq!(
+        errors(json!({"detail": [{"loc": ["body", "tags", 0], "msg": "bad"}]})),
+        Some(vec![field("tags.0", &["bad"])]),
+    );
+    assert_eq!(
+        errors(
+            json!({"errors": [{"field": "title", "code": "missing_field"}]})
+        ),
+        Some(vec![field("title", &["missing_field"])]),
+    );
+    assert_eq!(errors(json!({"message": 5})), None);
+    assert_eq!(ValidationErrors::from_json(&json!("oops")), None);
+
+    let error = Error::error_response(
+        (),
+        reqwest::StatusCode::BAD_REQUEST,
+        HeaderMap::new(),
+    );
+    assert!(!error.is_validation_error());
+}
+
+#[test]
+fn test_cookies() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let mut headers = HeaderMap::new();
+    headers.append(
+        "set-cookie",
+        HeaderValue::from_static(
+            "session=a3fWa; Path=/; Domain=.Example.com; \
+             Expires=Wed, 21 Oct 2015 07:28:00 GMT; Secure; HttpOnly",
+        ),
+    );
+    headers.append(
+        "set-cookie",
+        HeaderValue::from_static("theme=\"dark\"; Max-Age=0; SameSite=Lax"),
+    );
+    headers.append("set-cookie", HeaderValue::from_static("invalid"));
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, headers);
+
+    let cookies = rv.cookies();
+    assert_eq!(cookies.len(), 2);
+    assert_eq!(cookies[0].name, "session");
+    assert_eq!(cookies[0].value, "a3fWa");
+    assert_eq!(cookies[0].path.as_deref(), Some("/"));
+    assert_eq!(cookies[0].domain.as_deref(), Some("example.com"));
+    assert_eq!(
+        cookies[0].expires,
+        Some(UNIX_EPOCH + Duration::from_secs(1_445_412_480)),
+    );
+    assert!(cookies[0].secure && cookies[0].http_only);
+    assert_eq!(cookies[1].name, "theme");
+    assert_eq!(cookies[1].value, "\"dark\"");
+    assert_eq!(cookies[1].max_age, Some(0));
+    assert_eq!(cookies[1].same_site.as_deref(), Some("Lax"));
+    assert!(!cookies[1].secure);
+}
+
+#[test]
+fn test_error_retry_after() {
+    use std::time::Duration;
+
+    let error = |value: &'static str| {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static(value));
+        Error::error_response(
+            (),
+            reqwest::StatusCode::TOO_MANY_REQUESTS,
+            headers,
+        )
+    };
+    assert_eq!(error("120").retry_after(), Some(Duration::from_secs(120)));
+    assert_eq!(
+        error("Wed, 21 Oct 2015 07:28:00 GMT").retry_after(),
+        Some(Duration::ZERO),
+    );
+    let later = error("Fri, 31 Dec 9999 23:59:59 GMT").retry_after();
+    assert!(later.unwrap() > Duration::from_secs(86_400));
+    assert_eq!(error("soon").retry_after(), None);
+
+    let error = Error::error_response(
+        (),
+        reqwest::StatusCode::SERVICE_UNAVAILABLE,
+        HeaderMap::new(),
+    );
+    assert_eq!(error.retry_after(), None);
+}
+
+#[test]
+fn test_response_value_clone() {
+    let mut headers = HeaderMap::new();
+    headers.insert("etag", HeaderValue::from_static("\"v1\""));
+    let rv = ResponseValue::new(vec![1, 2], reqwest::StatusCode::OK, headers);
+    let copy = rv.clone();
+    assert_eq!(*copy, [1, 2]);
+    assert_eq!(copy.status(), rv.status());
+    assert_eq!(copy.headers(), rv.headers());
+}
+
+#[test]
+fn test_response_retry_after() {
+    use std::time::Duration;
+
+    let mut headers = HeaderMap::new();
+    headers.insert("retry-after", HeaderValue::from_static(" 5 "));
+    let rv = ResponseValue::new((), reqwest::StatusCode::ACCEPTED, headers);
+    assert_eq!(rv.retry_after(), Some(Duration::from_secs(5)));
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "retry-after",
+        HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+    );
+    let rv = ResponseValue::new((), reqwest::StatusCode::ACCEPTED, headers);
+    assert_eq!(rv.retry_after(), Some(Duration::ZERO));
+
+    let rv = ResponseValue::new((), reqwest::StatusCode::OK, HeaderMap::new());
+    assert_eq!(rv.retry_after(), None);
+}
+
+#[test]
+fn test_error_is_retryable() {
+    let error = |status: u16| {
+        Error::error_response(
+            (),
+            reqwest::StatusCode::from_u16(status).unwrap(),
+            HeaderMap::new(),
+        )
+    };
+    for status in [408, 425, 429, 500, 502, 503] {
+        assert!(error(status).is_retryable(), "{}", status);
+    }
+    for status in [400, 401, 404, 409, 422] {
+        assert!(!error(status).is_retryable(), "{}", status);
+    }
+    assert!(!Error::<()>::InvalidRequest("bad".to_string()).is_retryable());
+}
+
+#[test]
+fn test_io_error() {
+    use std::io::ErrorKind;
+
+    let kind = |status| {
+        let error = Error::error_response((), status, HeaderMap::new());
+        std::io::Error::from(error).kind()
+    };
+    assert_eq!(kind(reqwest::StatusCode::NOT_FOUND), ErrorKind::NotFound);
+    assert_eq!(
+        kind(reqwest::StatusCode::FORBIDDEN),
+        ErrorKind::PermissionDenied,
+    );
+    assert_eq!(
+        kind(reqwest::StatusCode::GATEWAY_TIMEOUT),
+        ErrorKind::TimedOut,
+    );
+    assert_eq!(kind(reqwest::StatusCode::IM_A_TEAPOT), ErrorKind::Other);
+
+    let error = std::io::Error::from(Error::<()>::InvalidRequest(
+        "missing name".to_string(),
+    ));
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    let inner = error.into_inner().unwrap();
+    let inner = inner.downcast::<Error>().unwrap();
+    assert!(matches!(*inner, Error::InvalidRequest(s) if s == "missing name"));
+}
+
+#[test]
+fn test_check_request_size() {
+    use progenitor_client::check_request_size;
+
+    let client = reqwest::Client::new();
+    let request = |body: &'static str| {
+        client
+            .post("http://localhost/upload")
+            .body(body)
+            .build()
+            .unwrap()
+    };
+    assert!(check_request_size(&request("abcd"), 4).is_ok());
+    let message = check_request_size(&request("abcde"), 4).unwrap_err();
+    assert!(message.contains("5 bytes"));
+    assert!(message.contains("maximum of 4 bytes"));
+
+    let empty = client.get("http://localhost/upload").build().unwrap();
+    assert!(check_request_size(&empty, 0).is_ok());
+}
+
+#[test]
+fn test_from_response_content_type() {
+    let response = |content_type: Option<&str>, body: &'static str| {
+        let mut builder = http::Response::builder();
+        if let Some(content_type) = content_type {
+            builder = builder.header("content-type", content_type);
+        }
+        reqwest::Response::from(builder.body(body).unwrap())
+    };
+
+    futures::executor::block_on(async {
+        for content_type in [
+            "application/json",
+            "application/json; charset=utf-8",
+            "application/problem+json",
+        ] {
+            let value = ResponseValue::<Vec<u32>>::from_response::<()>(
+                response(Some(content_type), "[1, 2]"),
+            )
+            .await
+            .unwrap();
+            assert_eq!(*value, vec![1, 2]);
+        }
+
+        let error = ResponseValue::<Vec<u32>>::from_response::<()>(response(
+            Some("text/html"),
+            "<html>oops</html>",
+        ))
+        .await
+        .unwrap_err();
+        let Error::UnexpectedResponse(response) = error else {
+            panic!("expected an unexpected response");
+        };
+        assert_eq!(response.text().await.unwrap(), "<html>oops</html>");
+
+        let error = ResponseValue::<Vec<u32>>::from_response::<()>(response(
+            None, "[1, 2]",
+        ))
+        .await
+        .unwrap_err();
+        assert!(matches!(error, Error::UnexpectedResponse(_)));
+
+        let value = ResponseValue::<Vec<u32>>::from_response_lenient::<()>(
+            response(None, "[1, 2]"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(*value, vec![1, 2]);
+
+        let value = ResponseValue::<Vec<u32>>::from_response_debug::<()>(
+            response(Some("text/plain"), "[1, 2]"),
+            "list",
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(*value, vec![1, 2]);
+        let error = ResponseValue::<Vec<u32>>::from_response_debug::<()>(
+            response(Some("text/plain"), "[1, 2]"),
+            "list",
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, Error::UnexpectedResponse(_)));
+    });
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_framed() {
+    use futures::StreamExt;
+    use tokio_util::codec::LinesCodec;
+
+    let lines = futures::executor::block_on(
+        byte_stream(&["one\ntw", "o\n", "three"])
+            .framed(LinesCodec::new())
+            .collect::<Vec<_>>(),
+    );
+    let lines = lines.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(lines, vec!["one", "two", "three"]);
+
+    let mut frames = byte_stream(&["toolong\nok\n"])
+        .framed(LinesCodec::new_with_max_length(4));
+    futures::executor::block_on(async {
+        assert!(frames.next().await.unwrap().is_err());
+        assert!(frames.next().await.is_none());
+    });
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_into_async_read() {
+    use tokio::io::AsyncReadExt;
+
+    let mut reader = byte_stream(&["hello, ", "", "world"]).into_async_read();
+    let mut contents = String::new();
+    futures::executor::block_on(reader.read_to_string(&mut contents)).unwrap();
+    assert_eq!(contents, "hello, world");
+}
+
+/// Splits the `multipart/form-data` body of `request` into the headers and
+/// value of each part.
+#[cfg(feature = "multipart")]
+fn form_parts(request: &reqwest::Request) -> Vec<(Vec<String>, String)> {
+    let content_type = request.headers()[reqwest::header::CONTENT_TYPE]
+        .to_str()
+        .unwrap();
+    let boundary = content_type
+        .strip_prefix("multipart/form-data; boundary=")
+        .unwrap();
+    let body = request.body().unwrap().as_bytes().unwrap();
+    let body = std::str::from_utf8(body).unwrap();
+    let body = body
+        .strip_prefix(&format!("--{}\r\n", boundary))
+        .unwrap()
+        .strip_suffix(&format!("\r\n--{}--\r\n", boundary))
+        .unwrap();
+    body.split(&format!("\r\n--{}\r\n", boundary))
+        .map(|part| {
+            let (headers, value) = part.split_once("\r\n\r\n").unwrap();
+            let headers = headers.split("\r\n").map(String::from).collect();
+            (headers, value.to_string())
+        })
+        .collect()
+}
+
+#[cfg(feature = "multipart")]
+#[test]
+fn test_form_from_raw_typed() {
+    use progenitor_client::RequestBuilderExt;
+
+    let builder = || reqwest::Client::new().post("http://localhost/upload");
+    let request = RequestBuilderExt::<()>::form_from_raw_typed(
+        builder(),
+        [
+            (
+                "metadata",
+                &br#"{"name": "a"}"#[..],
+                Some("application/json"),
+            ),
+            ("file", &b"\x00\x01"[..], Some("application/octet-stream")),
+            ("note", &b"plain"[..], None),
+        ],
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+    assert_eq!(
+        form_parts(&request),
+        [
+            (
+                vec![
+                    r#"Content-Disposition: form-data; name="metadata""#
+                        .to_string(),
+                    "Content-Type: application/json".to_string(),
+                ],
+                r#"{"name": "a"}"#.to_string(),
+            ),
+            (
+                vec![
+                    r#"Content-Disposition: form-data; name="file""#
+                        .to_string(),
+                    "Content-Type: application/octet-stream".to_string(),
+                ],
+                "\x00\x01".to_string(),
+            ),
+            (
+                vec![r#"Content-Disposition: form-data; name="note""#
+                    .to_string()],
+                "plain".to_string(),
+            ),
+        ],
+    );
+
+    let result = RequestBuilderExt::<()>::form_from_raw_typed(
+        builder(),
+        [("metadata", "{}", Some("not a mime type"))],
+    );
+    assert!(matches!(result, Err(Error::InvalidRequest(_))));
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_hedge() {
+    use progenitor_client::{Hedge, Transport, TransportError};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Answers the first request slowly and any others at once.
+    #[derive(Default)]
+    struct SlowFirst {
+        calls: AtomicUsize,
+    }
+
+    impl Transport for SlowFirst {
+        fn execute(
+            &self,
+            _request: reqwest::Request,
+        ) -> std::pin::Pin<
+            Box<
+                dyn std::future::Future<
+                        Output = Result<reqwest::Response, TransportError>,
+                    > + Send
+                    + '_,
+            >,
+        > {
+            let attempt = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Box::pin(async move {
+                if attempt == 1 {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                }
+                Ok(http::Response::builder()
+                    .header("x-attempt", attempt.to_string())
+                    .body("")
+                    .unwrap()
+                    .into())
+            })
+        }
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+    let request = |method| {
+        reqwest::Client::new()
+            .request(method, "http://localhost/report")
+            .build()
+            .unwrap()
+    };
+
+    let hedge = Hedge::new(SlowFirst::default(), Duration::from_millis(10));
+    let response = runtime
+        .block_on(hedge.execute(request(reqwest::Method::GET)))
+        .unwrap();
+    assert_eq!(response.headers()["x-attempt"], "2");
+
+    let hedge = Hedge::new(SlowFirst::default(), Duration::from_millis(10));
+    let response = runtime
+        .block_on(hedge.execute(request(reqwest::Method::POST)))
+        .unwrap();
+    assert_eq!(response.headers()["x-attempt"], "1");
+}
+
+#[cfg(feature = "multipart")]
+#[test]
+fn test_form_from_raw_files() {
+    use progenitor_client::RequestBuilderExt;
+
+    let request = RequestBuilderExt::<()>::form_from_raw_files(
+        reqwest::Client::new().post("http://localhost/upload"),
+        [
+            ("file", Some("r\u{e9}sum\u{e9}.pdf"), &b"%PDF"[..]),
+            ("note", None, &b"plain"[..]),
+            ("notes", Some("to do.txt"), &b"none"[..]),
+        ],
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+    let parts = form_parts(&request);
+    assert_eq!(parts.len(), 3);
+    assert_eq!(
+        parts[0],
+        (
+            vec![concat!(
+                r#"Content-Disposition: form-data; name="file"; "#,
+                r#"filename="r_sum_.pdf"; "#,
+                "filename*=UTF-8''r%C3%A9sum%C3%A9.pdf",
+            )
+            .to_string()],
+            "%PDF".to_string(),
+        ),
+    );
+    assert_eq!(
+        parts[1],
+        (
+            vec![r#"Content-Disposition: form-data; name="note""#.to_string()],
+            "plain".to_string(),
+        ),
+    );
+    // ASCII file names are sent as they are.
+    assert_eq!(
+        parts[2].0,
+        [concat!(
+            r#"Content-Disposition: form-data; name="notes"; "#,
+            r#"filename="to do.txt""#,
+        )],
+    );
+}
+
+#[test]
+fn test_map_async() {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-key-id", HeaderValue::from_static("k1"));
+    let value = ResponseValue::new(
+        "ciphertext".to_string(),
+        reqwest::StatusCode::CREATED,
+        headers,
+    );
+
+    let value = futures::executor::block_on(
+        value.map_async::<_, _, _, ()>(|body| async move { body.len() }),
+    )
+    .unwrap();
+    assert_eq!(*value, 10);
+    assert_eq!(value.status(), reqwest::StatusCode::CREATED);
+    assert_eq!(value.headers()["x-key-id"], "k1");
+}
+
+#[test]
+fn test_try_map() {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-total", HeaderValue::from_static("3"));
+    let value =
+        ResponseValue::new("42".to_string(), reqwest::StatusCode::OK, headers);
+
+    let parsed = value
+        .try_map::<u32, _, std::num::ParseIntError>(|body| body.parse())
+        .unwrap();
+    assert_eq!(*parsed, 42);
+    assert_eq!(parsed.status(), reqwest::StatusCode::OK);
+    assert_eq!(parsed.headers()["x-total"], "3");
+
+    let value = ResponseValue::new(
+        "x".to_string(),
+        reqwest::StatusCode::OK,
+        HeaderMap::new(),
+    );
+    let error = value.try_map(|body| body.parse::<u32>()).unwrap_err();
+    assert_eq!(error.to_string(), "invalid digit found in string");
+}
+
+#[test]
+fn test_from_response_strict() {
+    #[derive(Debug, serde::Deserialize, serde::Serialize)]
+    struct Job {
+        id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        summary: Option<String>,
+    }
+
+    let decode = |body: &'static str, strict: bool| {
+        let response: reqwest::Response = http::Response::builder()
+            .header("content-type", "application/json")
+            .body(body)
+            .unwrap()
+            .into();
+        futures::executor::block_on(
+            ResponseValue::<Vec<Job>>::from_response_strict::<()>(
+                response,
+                "list_jobs",
+                None,
+                false,
+                strict,
+                false,
+            ),
+        )
+    };
+
+    let body = r#"[{"id": "a", "summary": null}, {"id": "b", "owner": "c"}]"#;
+    assert_eq!(decode(body, false).unwrap().len(), 2);
+    match decode(body, true) {
+        Err(Error::InvalidResponsePayload(_, e)) => {
+            assert!(e.to_string().contains("/1/owner"), "{}", e);
+        }
+        other => panic!("unexpected result {:?}", other.map(|v| v.len())),
+    }
+    assert!(decode(r#"[{"id": "a", "summary": null}]"#, true).is_ok());
+}
+
+#[test]
+fn test_response_fixups() {
+    use progenitor_client::ResponseFixups;
+    type Counts = ResponseValue<std::collections::HashMap<String, u64>>;
+
+    let mut fixups = ResponseFixups::new();
+    fixups.add("get_count", |value| {
+        if let Some(count) = value.get_mut("count") {
+            if let Some(parsed) = count.as_str().and_then(|s| s.parse().ok()) {
+                *count = serde_json::Value::Number(parsed);
+            }
+        }
+    });
+
+    let decode = |operation_id: &'static str| {
+        let response: reqwest::Response = http::Response::builder()
+            .header("content-type", "application/json")
+            .body(r#"{"count": "3"}"#)
+            .unwrap()
+            .into();
+        let fixups = fixups.clone();
+        futures::executor::block_on(async move {
+            Counts::from_response_fixup::<()>(
+                response,
+                operation_id,
+                &fixups,
+                false,
+                false,
+            )
+            .await
+        })
+    };
+    assert_eq!(decode("get_count").unwrap()["count"], 3);
+    assert!(matches!(
+        decode("other"),
+        Err(Error::InvalidResponsePayload(..)),
+    ));
+}
+
+#[test]
+fn test_json_lines_at() {
+    use futures::StreamExt;
+
+    let lines = || {
+        byte_stream(&[
+            "{\"result\": {\"id\": 1}}\n\n{\"res",
+            "ult\": {\"id\": 2}}\r\n{\"event\": \"ping\"}\n",
+            "{\"result\": {\"id\": 3}}",
+        ])
+    };
+
+    let values = futures::executor::block_on(
+        lines()
+            .json_lines_at::<serde_json::Value, ()>("/result/id")
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(values.len(), 3);
+    assert_eq!(values[0].as_ref().unwrap(), 1);
+    assert_eq!(values[1].as_ref().unwrap(), 2);
+    assert!(matches!(values[2], Err(Error::InvalidResponsePayload(..))));
+
+    let values = futures::executor::block_on(
+        lines()
+            .json_lines_at::<u32, ()>("/result/id")
+            .skip_malformed(true)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(values, [1, 2, 3]);
+
+    let values = futures::executor::block_on(
+        byte_stream(&["1\n2\n"])
+            .json_lines::<u32, ()>()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(values, [1, 2]);
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_collect_with_timeout() {
+    use futures::StreamExt;
+    use progenitor_client::CollectError;
+    use std::time::Duration;
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+
+    let body = runtime
+        .block_on(
+            byte_stream(&["abc", "def"])
+                .collect_with_timeout(Duration::from_secs(10)),
+        )
+        .unwrap();
+    assert_eq!(body, "abcdef");
+
+    // A stream that stalls after its first chunks.
+    let stalled = ByteStream::new(Box::pin(
+        futures::stream::iter([
+            Ok::<_, reqwest::Error>(Bytes::from_static(b"abc")),
+            Ok(Bytes::from_static(b"def")),
+        ])
+        .chain(futures::stream::pending()),
+    ));
+    let (partial, error) = runtime
+        .block_on(stalled.collect_with_timeout(Duration::from_millis(50)))
+        .unwrap_err();
+    assert_eq!(partial, "abcdef");
+    assert!(matches!(error, CollectError::TimedOut));
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_pipe_to() {
+    use futures::StreamExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // Counts the chunks read from the underlying stream.
+    let counted = |read: &Arc<AtomicUsize>| {
+        let read = read.clone();
+        ByteStream::new(Box::pin(
+            futures::stream::iter(["a", "bb", "ccc", "dddd"])
+                .map(|chunk| {
+                    Ok::<_, reqwest::Error>(Bytes::from_static(
+                        chunk.as_bytes(),
+                    ))
+                })
+                .inspect(move |_| {
+                    read.fetch_add(1, Ordering::SeqCst);
+                }),
+        ))
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        let read = Arc::new(AtomicUsize::new(0));
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let pipe =
+            tokio::spawn(counted(&read).pipe_to(tx, |chunk| chunk.len()));
+
+        // With the channel full, no more is read than is waiting to be sent.
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(read.load(Ordering::SeqCst), 2);
+
+        let mut lens = Vec::new();
+        while let Some(len) = rx.recv().await {
+            lens.push(len);
+        }
+        assert_eq!(lens, [1, 2, 3, 4]);
+        pipe.await.unwrap().unwrap();
+    });
+
+    // Dropping the receiver stops the pipe early without an error.
+    runtime.block_on(async {
+        let read = Arc::new(AtomicUsize::new(0));
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        drop(rx);
+        counted(&read).pipe_to(tx, |chunk| chunk).await.unwrap();
+        assert_eq!(read.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+fn test_collect_with_limit() {
+    use progenitor_client::CollectError;
+
+    let body = futures::executor::block_on(
+        byte_stream(&["abc", "defg"]).collect_with_limit(7),
+    )
+    .unwrap();
+    assert_eq!(body, "abcdefg");
+
+    let result = futures::executor::block_on(
+        byte_stream(&["abc", "defg"]).collect_with_limit(6),
+    );
+    assert!(matches!(
+        result,
+        Err(CollectError::LimitExceeded { limit: 6, read: 7 }),
+    ));
+
+    // The Content-Length header doesn't override the limit.
+    let mut headers = HeaderMap::new();
+    headers.insert("content-length", HeaderValue::from_static("2"));
+    let rv = ResponseValue::new(
+        byte_stream(&["abc", "defg"]),
+        reqwest::StatusCode::OK,
+        headers,
+    );
+    let result = futures::executor::block_on(rv.collect_with_limit(4));
+    assert!(matches!(result, Err(CollectError::LimitExceeded { .. })));
+}
+
+#[test]
+fn test_into_bytes() {
+    let mut headers = HeaderMap::new();
+    headers.insert("content-length", HeaderValue::from_static("7"));
+    let rv = ResponseValue::new(
+        byte_stream(&["abc", "defg"]),
+        reqwest::StatusCode::OK,
+        headers,
+    );
+    let body = futures::executor::block_on(rv.into_bytes()).unwrap();
+    assert_eq!(body, "abcdefg");
+
+    // An implausible Content-Length header is only a hint.
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "content-length",
+        HeaderValue::from_static("18446744073709551615"),
+    );
+    let rv = ResponseValue::new(
+        byte_stream(&["abc"]),
+        reqwest::StatusCode::OK,
+        headers,
+    );
+    let body = futures::executor::block_on(rv.into_bytes()).unwrap();
+    assert_eq!(body, "abc");
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_cbor() {
+    use progenitor_client::RequestBuilderExt;
+    type Map = ResponseValue<std::collections::HashMap<String, u32>>;
+
+    let body = std::collections::HashMap::from([("a", 1)]);
+    let request = RequestBuilderExt::<()>::cbor(
+        reqwest::Client::new().post("http://localhost/"),
+        &body,
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+    assert_eq!(request.headers()["content-type"], "application/cbor");
+    // {"a": 1}
+    let encoded = request.body().and_then(reqwest::Body::as_bytes).unwrap();
+    assert_eq!(encoded, &[0xa1, 0x61, b'a', 0x01]);
+
+    let decode = |body: Vec<u8>| {
+        let response: reqwest::Response = http::Response::builder()
+            .header("content-type", "application/cbor")
+            .body(body)
+            .unwrap()
+            .into();
+        futures::executor::block_on(Map::from_response_cbor::<()>(response))
+    };
+
+    let rv = decode(encoded.to_vec()).unwrap();
+    assert_eq!(rv["a"], 1);
+
+    match decode(vec![0xff]) {
+        Err(Error::InvalidResponseEncoding(body, _)) => {
+            assert_eq!(body, Bytes::from_static(&[0xff]));
+        }
+        other => panic!("unexpected result {:?}", other.map(|rv| rv.len())),
+    }
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn test_from_response_msgpack() {
+    type Map = ResponseValue<std::collections::HashMap<String, u32>>;
+
+    let decode = |body: &'static [u8]| {
+        let response: reqwest::Response = http::Response::builder()
+            .header("content-type", "application/msgpack")
+            .body(body)
+            .unwrap()
+            .into();
+        futures::executor::block_on(Map::from_response_msgpack::<()>(response))
+    };
+
+    // {"a": 1}
+    let rv = decode(&[0x81, 0xa1, b'a', 0x01]).unwrap();
+    assert_eq!(rv.status(), reqwest::StatusCode::OK);
+    assert_eq!(rv.headers()["content-type"], "application/msgpack");
+    assert_eq!(rv["a"], 1);
+
+    match decode(&[0xc1]) {
+        Err(Error::InvalidResponseEncoding(body, _)) => {
+            assert_eq!(body, Bytes::from_static(&[0xc1]));
+        }
+        other => panic!("unexpected result {:?}", other.map(|rv| rv.len())),
+    }
+}
+
+#[test]
+fn test_text() {
+    let text = |content_type: &'static str, body: &'static [u8]| {
+        let response: reqwest::Response = http::Response::builder()
+            .header("content-type", content_type)
+            .body(body)
+            .unwrap()
+            .into();
+        futures::executor::block_on(ResponseValue::<String>::text::<()>(
+            response,
+        ))
+    };
+
+    assert_eq!(*text("text/plain", "héllo".as_bytes()).unwrap(), "héllo");
+    assert_eq!(
+        *text("text/plain; charset=ISO-8859-1", b"h\xe9llo").unwrap(),
+        "héllo",
+    );
+    assert!(matches!(
+        text("text/plain; charset=utf-8", b"h\xe9llo"),
+        Err(Error::InvalidResponsePayload(..)),
+    ));
+    assert!(matches!(
+        text("text/plain; charset=us-ascii", "héllo".as_bytes()),
+        Err(Error::InvalidResponsePayload(..)),
+    ));
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_long_poll() {
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    let mut cursors = Vec::new();
+    let mut outcomes = vec![
+        Ok(Some("1")),
+        Err(503),
+        Ok(None),
+        Ok(Some("2")),
+        Err(400),
+        Ok(Some("3")),
+    ]
+    .into_iter();
+    let events = progenitor_client::long_poll(
+        |cursor: Option<String>| {
+            cursors.push(cursor);
+            let outcome = outcomes.next().unwrap();
+            async move {
+                let status = match outcome {
+                    Ok(_) => 200,
+                    Err(status) => status,
+                };
+                let status = reqwest::StatusCode::from_u16(status).unwrap();
+                let mut headers = HeaderMap::new();
+                match outcome {
+                    Ok(cursor) => {
+                        if let Some(cursor) = cursor {
+                            headers.insert(
+                                "x-cursor",
+                                HeaderValue::from_static(cursor),
+                            );
+                        }
+                        Ok(ResponseValue::new((), status, headers))
+                    }
+                    Err(_) => Err(Error::error_response((), status, headers)),
+                }
+            }
+        },
+        |rv: &ResponseValue<()>| {
+            Some(rv.headers().get("x-cursor")?.to_str().ok()?.to_string())
+        },
+    )
+    .backoff(Duration::from_millis(1), Duration::from_millis(2));
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+    let statuses = runtime.block_on(
+        events
+            .map(|result| match result {
+                Ok(rv) => rv.status().as_u16(),
+                Err(e) => e.status().unwrap().as_u16(),
+            })
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(statuses, [200, 503, 200, 200, 400]);
+    assert_eq!(
+        cursors,
+        [
+            None,
+            Some("1".to_string()),
+            Some("1".to_string()),
+            Some("1".to_string()),
+            Some("2".to_string()),
+        ],
+    );
+}
+
+#[cfg(feature = "opentelemetry")]
+#[test]
+fn test_opentelemetry_transport() {
+    use opentelemetry::propagation::{
+        text_map_propagator::FieldIter, Extractor, Injector, TextMapPropagator,
+    };
+    use opentelemetry::Context;
+    use progenitor_client::{OpenTelemetryTransport, Transport, TransportFn};
+
+    /// Injects a fixed `traceparent`, since spans from the default no-op
+    /// tracer have no valid context to propagate.
+    #[derive(Debug)]
+    struct FixedPropagator;
+
+    impl TextMapPropagator for FixedPropagator {
+        fn inject_context(&self, _cx: &Context, injector: &mut dyn Injector) {
+            injector.set(
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"
+                    .to_string(),
+            );
+        }
+
+        fn extract_with_context(
+            &self,
+            cx: &Context,
+            _extractor: &dyn Extractor,
+        ) -> Context {
+            cx.clone()
+        }
+
+        fn fields(&self) -> FieldIter<'_> {
+            FieldIter::new(&[])
+        }
+    }
+
+    opentelemetry::global::set_text_map_propagator(FixedPropagator);
+    let transport = OpenTelemetryTransport::new(TransportFn::new(|request| {
+        let traceparent = request.headers()["traceparent"].clone();
+        Ok(http::Response::builder()
+            .status(404)
+            .header("traceparent", traceparent)
+            .body("")
+            .unwrap()
+            .into())
+    }));
+    let request = reqwest::Client::new()
+        .get("http://localhost/widgets")
+        .build()
+        .unwrap();
+    let response =
+        futures::executor::block_on(transport.execute(request)).unwrap();
+    assert_eq!(response.status(), 404);
+    assert_eq!(
+        response.headers()["traceparent"],
+        "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+    );
+}
+
+#[test]
+fn test_into_json_lines() {
+    use futures::StreamExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let rv = ResponseValue::new(
+        byte_stream(&["{\"id\": 1}\n\n{\"id\"", ": 2}\n{\"id\": 3}"]),
+        reqwest::StatusCode::OK,
+        HeaderMap::new(),
+    );
+    let values = futures::executor::block_on(
+        rv.into_json_lines::<serde_json::Value>()
+            .map(|value| value.unwrap()["id"].as_u64().unwrap())
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(values, [1, 2, 3]);
+
+    // A body cut short by the server ends with a communication error.
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .unwrap();
+    let values = runtime.block_on(async {
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 1024];
+            let _ = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\r\n1\n2",
+                )
+                .await
+                .unwrap();
+        });
+
+        let response = reqwest::get(format!("http://{}/events", addr))
+            .await
+            .unwrap();
+        ResponseValue::stream(response)
+            .into_json_lines::<u32>()
+            .collect::<Vec<_>>()
+            .await
+    });
+    assert_eq!(values.len(), 2);
+    assert_eq!(*values[0].as_ref().unwrap(), 1);
+    assert!(matches!(values[1], Err(Error::CommunicationError(_))));
+}
+
+#[test]
+fn test_client_builder() {
+    use progenitor_client::ClientBuilder;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    // Counts the connections opened to send two requests with `client`.
+    let connections = |client: reqwest::Client| {
+        runtime.block_on(async {
+            let listener =
+                tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/", listener.local_addr().unwrap());
+            let accepted = Arc::new(AtomicUsize::new(0));
+            tokio::spawn({
+                let accepted = accepted.clone();
+                async move {
+                    loop {
+                        let (mut stream, _) = listener.accept().await.unwrap();
+                        accepted.fetch_add(1, Ordering::SeqCst);
+                        tokio::spawn(async move {
+                            let mut buf = vec![0; 1024];
+                            while stream.read(&mut buf).await.unwrap_or(0) > 0 {
+                                let response = b"HTTP/1.1 200 OK\r\n\
+                                    content-length: 2\r\n\r\nok";
+                                stream.write_all(response).await.unwrap();
+                            }
+                        });
+                    }
+                }
+            });
+
+            for _ in 0..2 {
+                let response = client.get(&url).send().await.unwrap();
+                assert_eq!(response.text().await.unwrap(), "ok");
+            }
+            accepted.load(Ordering::SeqCst)
+        })
+    };
+
+    // Connections are pooled and reused.
+    let client = ClientBuilder::new()
+        .tcp_keepalive(Duration::from_secs(60))
+        .tcp_nodelay(true)
+        .pool_idle_timeout(Duration::from_secs(30))
+        .build()
+        .unwrap();
+    assert_eq!(connections(client), 1);
+
+    // Without idle connections, each request opens one of its own.
+    let client = ClientBuilder::new()
+        .pool_max_idle_per_host(0)
+        .build()
+        .unwrap();
+    assert_eq!(connections(client), 2);
+}
+
+#[test]
+fn test_redirect_policy() {
+    use progenitor_client::{ClientBuilder, RedirectPolicy};
+    use std::io::{Read, Write};
+
+    /// Serves each request with the response `route` gives for its path.
+    fn serve(route: impl Fn(&str) -> String + Send + 'static) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut buf = [0; 1024];
+                let len = stream.read(&mut buf).unwrap();
+                let request = String::from_utf8_lossy(&buf[..len]);
+                let path = request.split(' ').nth(1).unwrap_or("/");
+                stream.write_all(route(path).as_bytes()).unwrap();
+            }
+        });
+        url
+    }
+    fn redirect(location: &str) -> String {
+        format!(
+            "HTTP/1.1 302 Found\r\nlocation: {}\r\ncontent-length: 0\r\n\
+            connection: close\r\n\r\n",
+            location,
+        )
+    }
+    fn ok(body: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\
+            content-length: {}\r\nconnection: close\r\n\r\n{}",
+            body.len(),
+            body,
+        )
+    }
+
+    let elsewhere = serve(|_| ok("\"elsewhere\""));
+    let origin = serve({
+        let elsewhere = elsewhere.clone();
+        move |path| match path {
+            "/moved" => redirect("/widgets"),
+            "/away" => redirect(&format!("{}/widgets", elsewhere)),
+            "/loop" => redirect("/loop"),
+            _ => ok("\"origin\""),
+        }
+    });
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let get = |policy: RedirectPolicy, path: &str| {
+        let client = ClientBuilder::new()
+            .redirect_policy(policy)
+            .build()
+            .unwrap();
+        let url = format!("{}{}", origin, path);
+        runtime.block_on(async {
+            let response = client.get(url).send().await?;
+            Ok::<_, reqwest::Error>(response)
+        })
+    };
+
+    // Redirects to the same origin are followed.
+    let response = get(RedirectPolicy::same_origin(), "/moved").unwrap();
+    let rv = runtime
+        .block_on(ResponseValue::<String>::from_response::<()>(response))
+        .unwrap();
+    assert_eq!(
+        rv.final_url().unwrap().as_str(),
+        format!("{}/widgets", origin),
+    );
+    assert_eq!(rv.into_inner(), "origin");
+
+    // A redirect elsewhere is returned rather than followed.
+    let response = get(RedirectPolicy::same_origin(), "/away").unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FOUND);
+    assert_eq!(
+        response.headers()["location"],
+        format!("{}/widgets", elsewhere).as_str(),
+    );
+    let response = get(RedirectPolicy::limited(1), "/away").unwrap();
+    assert_eq!(response.url().as_str(), format!("{}/widgets", elsewhere));
+
+    // Endless redirects fail.
+    assert!(get(RedirectPolicy::same_origin(), "/loop")
+        .unwrap_err()
+        .is_redirect());
+
+    let response = get(RedirectPolicy::none(), "/moved").unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FOUND);
+}
+
+#[test]
+fn test_into_result_if() {
+    let rv = |body: serde_json::Value| {
+        ResponseValue::new(body, reqwest::StatusCode::OK, HeaderMap::new())
+    };
+    // Reports the `error` member that the API sets in successful responses.
+    let api_error = |rv: &ResponseValue<serde_json::Value>| {
+        assert_eq!(rv.status(), reqwest::StatusCode::OK);
+        rv.get("error")?.as_str().map(str::to_string)
+    };
+
+    let body = serde_json::json!({ "id": 7 });
+    assert_eq!(rv(body.clone()).into_result_if(api_error), Ok(body));
+    assert_eq!(
+        rv(serde_json::json!({ "error": "quota exceeded" }))
+            .into_result_if(api_error),
+        Err("quota exceeded".to_string()),
+    );
+}
+
+#[test]
+fn test_validate() {
+    #[derive(Debug, PartialEq)]
+    struct Percent(u8);
+
+    let rv = |value: u32| {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", HeaderValue::from_static("abc"));
+        ResponseValue::new(value, reqwest::StatusCode::ACCEPTED, headers)
+    };
+    let percent = |value: u32| match u8::try_from(value) {
+        Ok(value) if value <= 100 => Ok(Percent(value)),
+        _ => Err(format!("{} is not a percentage", value)),
+    };
+
+    let validated = rv(42).validate(percent).unwrap();
+    assert_eq!(validated.status(), reqwest::StatusCode::ACCEPTED);
+    assert_eq!(validated.headers()["x-request-id"], "abc");
+    assert_eq!(validated.into_inner(), Percent(42));
+
+    assert_eq!(
+        rv(300).validate(percent).unwrap_err(),
+        "300 is not a percentage",
+    );
+}
+
+#[test]
+fn test_into_parts() {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-request-id", HeaderValue::from_static("abc"));
+    let rv = ResponseValue::new(7u32, reqwest::StatusCode::CREATED, headers);
+    let (inner, status, headers) = rv.into_parts();
+    assert_eq!(inner, 7);
+    assert_eq!(status, reqwest::StatusCode::CREATED);
+    assert_eq!(headers["x-request-id"], "abc");
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_responses() {
+    use std::io::{Read, Write};
+
+    /// Answers each of `responses` on a connection of its own.
+    fn serve(responses: &'static [&'static str]) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let _ = stream.read(&mut [0; 1024]).unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        format!("http://{}/", addr)
+    }
+
+    let url = serve(&[
+        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\
+         content-length: 7\r\nconnection: close\r\n\r\n[1,2,3]",
+        "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\n\
+         content-length: 6\r\nconnection: close\r\n\r\n<html>",
+        "HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\
+         connection: close\r\n\r\nbytes",
+    ]);
+    let get = || reqwest::blocking::get(&url).unwrap();
+
+    let decode = |response: reqwest::blocking::Response| {
+        ResponseValue::<Vec<u32>>::from_response_blocking::<()>(response, false)
+    };
+
+    let rv = decode(get()).unwrap();
+    assert_eq!(rv.into_inner(), [1, 2, 3]);
+
+    match decode(get()) {
+        Err(Error::UnexpectedResponse(response)) => {
+            assert_eq!(response.headers()["content-type"], "text/html");
+            let body = futures::executor::block_on(response.text()).unwrap();
+            assert_eq!(body, "<html>");
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+
+    let mut body = String::new();
+    ResponseValue::stream_blocking(get())
+        .into_inner()
+        .read_to_string(&mut body)
+        .unwrap();
+    assert_eq!(body, "bytes");
+}
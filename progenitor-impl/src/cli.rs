@@ -14,7 +14,7 @@ use crate::{
     },
     to_schema::ToSchema,
     util::{sanitize, Case},
-    validate_openapi, Generator, Result,
+    validate_openapi, Error, Generator, Result,
 };
 
 struct CliOperation {
@@ -65,7 +65,7 @@ impl Generator {
         let methods = raw_methods
             .iter()
             .map(|method| self.cli_method(method))
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>>>()?;
 
         let cli_ops = methods.iter().map(|op| &op.cli_fn);
         let execute_ops = methods.iter().map(|op| &op.execute_fn);
@@ -196,7 +196,7 @@ impl Generator {
     fn cli_method(
         &mut self,
         method: &crate::method::OperationMethod,
-    ) -> CliOperation {
+    ) -> Result<CliOperation> {
         let CliArg {
             parser: parser_args,
             consumer: consumer_args,
@@ -238,6 +238,16 @@ impl Generator {
             OperationResponseStatus::is_error_or_default,
         );
 
+        // The CLI outputs a single success type.
+        if let crate::method::OperationResponseKind::Multiple(_) = success_kind
+        {
+            return Err(Error::UnexpectedFormat(format!(
+                "operation {} has success responses whose bodies differ by \
+                status, which the CLI doesn't support",
+                method.operation_id,
+            )));
+        }
+
         let execute_and_output = match method.dropshot_paginated {
             // Normal, one-shot API calls.
             None => {
@@ -259,13 +269,17 @@ impl Generator {
                         }
                     }
                     crate::method::OperationResponseKind::Raw
-                    | crate::method::OperationResponseKind::Upgrade => {
+                    | crate::method::OperationResponseKind::Upgrade
+                    | crate::method::OperationResponseKind::Problem => {
                         quote! {
                             {
                                 todo!()
                             }
                         }
                     }
+                    crate::method::OperationResponseKind::Multiple(_) => {
+                        unreachable!("rejected above")
+                    }
                 };
 
                 let error_output = match error_kind {
@@ -279,13 +293,18 @@ impl Generator {
                         }
                     }
                     crate::method::OperationResponseKind::Raw
-                    | crate::method::OperationResponseKind::Upgrade => {
+                    | crate::method::OperationResponseKind::Upgrade
+                    | crate::method::OperationResponseKind::Problem => {
                         quote! {
                             {
                                 todo!()
                             }
                         }
                     }
+                    // Error responses are never gathered into an enum.
+                    crate::method::OperationResponseKind::Multiple(_) => {
+                        unreachable!()
+                    }
                 };
 
                 quote! {
@@ -307,6 +326,10 @@ impl Generator {
                     crate::method::OperationResponseKind::None => quote! { () },
                     crate::method::OperationResponseKind::Raw => todo!(),
                     crate::method::OperationResponseKind::Upgrade => todo!(),
+                    crate::method::OperationResponseKind::Problem => todo!(),
+                    crate::method::OperationResponseKind::Multiple(_) => {
+                        unreachable!("rejected above")
+                    }
                 };
                 let error_output = match error_kind {
                     crate::method::OperationResponseKind::Type(_)
@@ -319,13 +342,18 @@ impl Generator {
                         }
                     }
                     crate::method::OperationResponseKind::Raw
-                    | crate::method::OperationResponseKind::Upgrade => {
+                    | crate::method::OperationResponseKind::Upgrade
+                    | crate::method::OperationResponseKind::Problem => {
                         quote! {
                             {
                                 todo!()
                             }
                         }
                     }
+                    // Error responses are never gathered into an enum.
+                    crate::method::OperationResponseKind::Multiple(_) => {
+                        unreachable!()
+                    }
                 };
                 quote! {
                     self.config.list_start::<#success_type>();
@@ -384,11 +412,11 @@ impl Generator {
             }
         };
 
-        CliOperation {
+        Ok(CliOperation {
             cli_fn,
             execute_fn,
             execute_trait,
-        }
+        })
     }
 
     fn cli_method_args(
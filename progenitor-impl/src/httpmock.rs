@@ -325,9 +325,21 @@ impl Generator {
                             .json_body(value)
                         },
                     ),
+                    crate::method::OperationResponseKind::Problem => (
+                        quote! {
+                            value: serde_json::Value,
+                        },
+                        quote! {
+                            .header("content-type", "application/problem+json")
+                            .json_body(value)
+                        },
+                    ),
                     crate::method::OperationResponseKind::Upgrade => {
                         Default::default()
                     }
+                    crate::method::OperationResponseKind::Multiple(_) => {
+                        unreachable!()
+                    }
                 };
 
                 match status_code {
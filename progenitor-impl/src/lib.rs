@@ -65,6 +65,20 @@ pub struct GenerationSettings {
     pre_hook_async: Option<TokenStream>,
     post_hook: Option<TokenStream>,
     extra_derives: Vec<String>,
+    custom_transport: bool,
+    response_schemas: bool,
+    operation_registry: bool,
+    stream_items: bool,
+    capture_unknown_fields: bool,
+    debug_schema: bool,
+    response_fixups: bool,
+    msgpack: bool,
+    cbor: bool,
+    head_variants: bool,
+    strict_decoding: bool,
+    blocking: bool,
+    lenient_content_type: bool,
+    prewarm: bool,
 
     unknown_crates: UnknownPolicy,
     crates: BTreeMap<String, CrateSpec>,
@@ -152,6 +166,154 @@ impl GenerationSettings {
         self
     }
 
+    /// Route requests through a `progenitor_client::Transport` stored in the
+    /// client rather than executing them directly with its `reqwest::Client`;
+    /// this adds a `Client::new_with_transport` constructor.
+    ///
+    /// The methods that wrap the transport for every request, such as
+    /// `Client::with_default_query` and `Client::with_propagated_headers`,
+    /// are generated only for clients with a custom transport.
+    ///
+    /// Note that the result passed to the post hook carries a
+    /// `progenitor_client::TransportError` rather than a `reqwest::Error`.
+    pub fn with_custom_transport(
+        &mut self,
+        custom_transport: bool,
+    ) -> &mut Self {
+        self.custom_transport = custom_transport;
+        self
+    }
+
+    /// Emit a `response_schemas` module holding, for each operation with a
+    /// JSON success response, a `&str` constant containing that response's
+    /// JSON Schema; these can be checked with
+    /// `ResponseValue::json_schema_validate`.
+    pub fn with_response_schemas(
+        &mut self,
+        response_schemas: bool,
+    ) -> &mut Self {
+        self.response_schemas = response_schemas;
+        self
+    }
+
+    /// Add a `Client::operations` method that describes each operation of
+    /// the API with a `progenitor_client::OperationInfo`.
+    pub fn with_operation_registry(
+        &mut self,
+        operation_registry: bool,
+    ) -> &mut Self {
+        self.operation_registry = operation_registry;
+        self
+    }
+
+    /// For operations whose success response is a JSON array, add an
+    /// `<operation>_items` method that produces the elements of the array as
+    /// they arrive (using `progenitor_client::JsonArrayStream`) rather than
+    /// deserializing the whole response. This currently applies only to the
+    /// positional interface.
+    pub fn with_stream_items(&mut self, stream_items: bool) -> &mut Self {
+        self.stream_items = stream_items;
+        self
+    }
+
+    /// Capture fields not described by the API in generated structs: each
+    /// component object schema that doesn't say whether additional properties
+    /// are permitted gains a flattened `extra` map and an `extra_fields`
+    /// accessor, so that fields added by newer servers aren't dropped.
+    pub fn with_capture_unknown_fields(
+        &mut self,
+        capture_unknown_fields: bool,
+    ) -> &mut Self {
+        self.capture_unknown_fields = capture_unknown_fields;
+        self
+    }
+
+    /// Report the JSON request and response bodies of each operation to the
+    /// hook set with `progenitor_client::set_debug_hook`. Bodies are reported
+    /// only if the `debug-schema` feature of `progenitor-client` is enabled.
+    pub fn with_debug_schema(&mut self, debug_schema: bool) -> &mut Self {
+        self.debug_schema = debug_schema;
+        self
+    }
+
+    /// Generate a `Client::with_response_fixup` method that registers, by
+    /// operation ID, functions that adjust JSON response bodies before they
+    /// are deserialized.
+    pub fn with_response_fixups(&mut self, response_fixups: bool) -> &mut Self {
+        self.response_fixups = response_fixups;
+        self
+    }
+
+    /// Decode responses documented only as `application/msgpack` into their
+    /// types, as is done for JSON, rather than producing them as a
+    /// `ByteStream`. Generated clients need the `msgpack` feature of
+    /// `progenitor-client`.
+    pub fn with_msgpack(&mut self, msgpack: bool) -> &mut Self {
+        self.msgpack = msgpack;
+        self
+    }
+
+    /// Encode request bodies documented as `application/cbor`, and decode
+    /// responses documented only as `application/cbor`, as CBOR. Without
+    /// this, such request bodies are an error and such responses are produced
+    /// as a `ByteStream`. Generated clients need the `cbor` feature of
+    /// `progenitor-client`.
+    pub fn with_cbor(&mut self, cbor: bool) -> &mut Self {
+        self.cbor = cbor;
+        self
+    }
+
+    /// Generate, for each `GET` operation without a request body, an
+    /// `<operation>_head` method that sends a `HEAD` request instead and
+    /// produces only the response headers. Operations that upgrade the
+    /// connection to a WebSocket are skipped. This applies only to the
+    /// `Positional` interface; `Builder` clients get no `HEAD` variants.
+    pub fn with_head_variants(&mut self, head_variants: bool) -> &mut Self {
+        self.head_variants = head_variants;
+        self
+    }
+
+    /// Generate a `Client::with_strict_decoding` method that makes the client
+    /// reject JSON response bodies with fields their types don't describe,
+    /// e.g. to catch drift between a server and its API description in tests.
+    /// Response types must implement `Serialize`.
+    pub fn with_strict_decoding(&mut self, strict_decoding: bool) -> &mut Self {
+        self.strict_decoding = strict_decoding;
+        self
+    }
+
+    /// Generate a `blocking::Client` whose methods match those of the
+    /// `Positional` interface but block the calling thread, using
+    /// `reqwest::blocking`. The generated code requires the `blocking`
+    /// feature of `progenitor-client`, which enables that of `reqwest`.
+    /// Operations that use WebSockets, MessagePack, or CBOR are omitted, and
+    /// hooks, custom transports, response fixups, and strict decoding don't
+    /// apply to the blocking client.
+    pub fn with_blocking(&mut self, blocking: bool) -> &mut Self {
+        self.blocking = blocking;
+        self
+    }
+
+    /// Decode JSON response bodies whatever their `Content-Type`, for APIs
+    /// that omit the header or label JSON bodies with some other media type.
+    /// By default, a response whose `Content-Type` isn't a JSON media type is
+    /// returned as `Error::UnexpectedResponse`.
+    pub fn with_lenient_content_type(
+        &mut self,
+        lenient_content_type: bool,
+    ) -> &mut Self {
+        self.lenient_content_type = lenient_content_type;
+        self
+    }
+
+    /// Generate a `Client::prewarm` method that opens a connection to the
+    /// host of the base URL ahead of the first operation. An operation named
+    /// `prewarm` would collide with it, so that is rejected.
+    pub fn with_prewarm(&mut self, prewarm: bool) -> &mut Self {
+        self.prewarm = prewarm;
+        self
+    }
+
     /// Additional derive macros applied to generated types.
     pub fn with_derive(&mut self, derive: impl ToString) -> &mut Self {
         self.extra_derives.push(derive.to_string());
@@ -304,14 +466,30 @@ impl Generator {
         validate_openapi(spec)?;
 
         // Convert our components dictionary to schemars
-        let schemas = spec.components.iter().flat_map(|components| {
-            components.schemas.iter().map(|(name, ref_or_schema)| {
-                (name.clone(), ref_or_schema.to_schema())
+        let mut open_schemas = Vec::new();
+        let schemas = spec
+            .components
+            .iter()
+            .flat_map(|components| {
+                components.schemas.iter().map(|(name, ref_or_schema)| {
+                    let mut schema = ref_or_schema.to_schema();
+                    if self.settings.capture_unknown_fields
+                        && allow_unknown_fields(&mut schema)
+                    {
+                        open_schemas.push(name.clone());
+                    }
+                    (name.clone(), schema)
+                })
             })
-        });
+            .collect::<Vec<_>>();
 
         self.type_space.add_ref_types(schemas)?;
 
+        let extra_fields = open_schemas
+            .iter()
+            .map(|name| self.extra_fields_accessor(name))
+            .collect::<Result<Vec<_>>>()?;
+
         let raw_methods = spec
             .paths
             .iter()
@@ -333,6 +511,16 @@ impl Generator {
             })
             .collect::<Result<Vec<_>>>()?;
 
+        if self.settings.prewarm
+            && raw_methods
+                .iter()
+                .any(|method| method.operation_id == "prewarm")
+        {
+            return Err(Error::UnexpectedFormat(
+                "operation prewarm collides with Client::prewarm".to_string(),
+            ));
+        }
+
         let operation_code = match (
             &self.settings.interface,
             &self.settings.tag,
@@ -358,6 +546,25 @@ impl Generator {
 
         let types = self.type_space.to_stream();
 
+        // Enums for operations with several kinds of success response are
+        // defined alongside the other types, in a module of their own so that
+        // they can name those types as the methods do.
+        let success_enums = raw_methods
+            .iter()
+            .filter_map(|method| self.success_response_enum(method))
+            .collect::<Vec<_>>();
+        let success_enums = (!success_enums.is_empty()).then(|| {
+            quote! {
+                pub use self::responses::*;
+
+                mod responses {
+                    use super::super::types;
+
+                    #(#success_enums)*
+                }
+            }
+        });
+
         // Generate an implementation of a `Self::as_inner` method, if an inner
         // type is defined.
         let maybe_inner = self.settings.inner_type.as_ref().map(|inner| {
@@ -385,6 +592,332 @@ impl Generator {
             }
         });
 
+        let (fixups_property, fixups_value, maybe_fixups) = if self
+            .settings
+            .response_fixups
+        {
+            (
+                quote! {
+                    pub(crate) response_fixups:
+                        progenitor_client::ResponseFixups,
+                },
+                quote! {
+                    response_fixups: Default::default(),
+                },
+                quote! {
+                    /// Adjust the JSON body of each response to the operation
+                    /// `operation_id` with `fixup` before it's deserialized,
+                    /// e.g. to work around a server that doesn't quite follow
+                    /// its API description; see
+                    /// `progenitor_client::ResponseFixups`.
+                    pub fn with_response_fixup<F>(
+                        mut self,
+                        operation_id: &str,
+                        fixup: F,
+                    ) -> Self
+                    where
+                        F: Fn(&mut serde_json::Value) + Send + Sync + 'static,
+                    {
+                        self.response_fixups.add(operation_id, fixup);
+                        self
+                    }
+                },
+            )
+        } else {
+            (quote! {}, quote! {}, quote! {})
+        };
+
+        let (strict_property, strict_value, maybe_strict) =
+            if self.settings.strict_decoding {
+                (
+                    quote! {
+                        pub(crate) strict_decoding: bool,
+                    },
+                    quote! {
+                        strict_decoding: false,
+                    },
+                    quote! {
+                        /// Reject JSON response bodies with fields that their
+                        /// types don't describe, reporting them as
+                        /// `Error::InvalidResponsePayload`, if `strict` is set.
+                        pub fn with_strict_decoding(
+                            mut self,
+                            strict: bool,
+                        ) -> Self {
+                            self.strict_decoding = strict;
+                            self
+                        }
+                    },
+                )
+            } else {
+                (quote! {}, quote! {}, quote! {})
+            };
+
+        let (transport_property, transport_value, transport_new) =
+            if self.settings.custom_transport {
+                (
+                    quote! {
+                        pub(crate) transport:
+                            std::sync::Arc<dyn progenitor_client::Transport>,
+                        pub(crate) request_log: Option<
+                            std::sync::Arc<progenitor_client::RequestLog>,
+                        >,
+                        #[cfg(not(target_arch = "wasm32"))]
+                        pub(crate) retry_budget: Option<
+                            std::sync::Arc<progenitor_client::RetryBudget>,
+                        >,
+                    },
+                    quote! {
+                        transport: std::sync::Arc::new(client.clone()),
+                        request_log: None,
+                        #[cfg(not(target_arch = "wasm32"))]
+                        retry_budget: None,
+                    },
+                    quote! {
+                        /// Construct a new client that executes requests using the
+                        /// given transport rather than an internal
+                        /// `reqwest::Client`.
+                        ///
+                        /// `baseurl` is the base URL used to form requests, and
+                        /// should include a scheme and hostname, as well as port
+                        /// and a path stem if applicable.
+                        pub fn new_with_transport<T>(
+                            baseurl: &str,
+                            transport: T,
+                            #inner_parameter
+                        ) -> Self
+                        where
+                            T: progenitor_client::Transport + 'static,
+                        {
+                            Self {
+                                baseurl: baseurl.to_string(),
+                                client: reqwest::Client::new(),
+                                transport: std::sync::Arc::new(transport),
+                                request_log: None,
+                                #[cfg(not(target_arch = "wasm32"))]
+                                retry_budget: None,
+                                #fixups_value
+                                #strict_value
+                                #inner_value
+                            }
+                        }
+
+                        /// Copy the headers in `names` from the map produced by
+                        /// `source` onto each request that doesn't already set
+                        /// them; see `progenitor_client::PropagateHeaders`.
+                        pub fn with_propagated_headers<F>(
+                            mut self,
+                            names: &[reqwest::header::HeaderName],
+                            source: F,
+                        ) -> Self
+                        where
+                            F: Fn() -> Option<reqwest::header::HeaderMap>
+                                + Send
+                                + Sync
+                                + 'static,
+                        {
+                            self.transport = std::sync::Arc::new(
+                                progenitor_client::PropagateHeaders::new(
+                                    self.transport,
+                                    names,
+                                    source,
+                                ),
+                            );
+                            self
+                        }
+
+                        /// Append the `params` pairs of names and values to the
+                        /// query of each request that doesn't already have
+                        /// them; see `progenitor_client::DefaultQuery`.
+                        pub fn with_default_query(
+                            mut self,
+                            params: &[(&str, &str)],
+                        ) -> Self {
+                            self.transport = std::sync::Arc::new(
+                                progenitor_client::DefaultQuery::new(
+                                    self.transport,
+                                    params,
+                                ),
+                            );
+                            self
+                        }
+
+                        /// Allow at most `limit` requests in flight to each host,
+                        /// queueing the excess; see
+                        /// `progenitor_client::ConcurrencyLimit`.
+                        pub fn with_max_concurrent_requests(
+                            mut self,
+                            limit: usize,
+                        ) -> Self {
+                            self.transport = std::sync::Arc::new(
+                                progenitor_client::ConcurrencyLimit::new(
+                                    self.transport,
+                                    limit,
+                                ),
+                            );
+                            self
+                        }
+
+                        /// Allow the retries of `Self::body_factory` factories
+                        /// to make up at most `ratio` of requests, plus
+                        /// `min_per_sec` retries each second, so that retrying
+                        /// backs off while the API is failing; see
+                        /// `progenitor_client::RetryBudget`. By default there's
+                        /// no budget and retries are limited only by the
+                        /// attempts allowed for each operation;
+                        /// `RetryBudget::default()` uses a `ratio` of 0.2 and a
+                        /// `min_per_sec` of 10.
+                        #[cfg(not(target_arch = "wasm32"))]
+                        pub fn with_retry_budget(
+                            mut self,
+                            ratio: f64,
+                            min_per_sec: u32,
+                        ) -> Self {
+                            self.retry_budget = Some(std::sync::Arc::new(
+                                progenitor_client::RetryBudget::new(
+                                    ratio,
+                                    min_per_sec,
+                                ),
+                            ));
+                            self
+                        }
+
+                        /// Create a `progenitor_client::BodyFactory` for
+                        /// retrying streamed uploads, whose retries draw on the
+                        /// budget set with `Self::with_retry_budget`, if any.
+                        pub fn body_factory<F>(
+                            &self,
+                            make: F,
+                        ) -> progenitor_client::BodyFactory
+                        where
+                            F: Fn() -> reqwest::Body + Send + Sync + 'static,
+                        {
+                            let factory =
+                                progenitor_client::BodyFactory::new(make);
+                            #[cfg(not(target_arch = "wasm32"))]
+                            if let Some(budget) = &self.retry_budget {
+                                return factory.retry_budget(budget.clone());
+                            }
+                            factory
+                        }
+
+                        /// Close the connection of an operation when it's
+                        /// cancelled, so that the server can stop its work; see
+                        /// `progenitor_client::CloseOnCancel`.
+                        pub fn with_close_on_cancel(mut self) -> Self {
+                            self.transport = std::sync::Arc::new(
+                                progenitor_client::CloseOnCancel::new(
+                                    self.transport,
+                                ),
+                            );
+                            self
+                        }
+
+                        /// Retain the body of each decoded response, for
+                        /// `ResponseValue::raw_body`; see
+                        /// `progenitor_client::RetainBodies`.
+                        pub fn with_retained_bodies(mut self) -> Self {
+                            self.transport = std::sync::Arc::new(
+                                progenitor_client::RetainBodies::new(
+                                    self.transport,
+                                ),
+                            );
+                            self
+                        }
+
+                        /// Retain summaries of the last `capacity` requests,
+                        /// with bodies truncated to `body_limit` bytes, for
+                        /// `Self::recent_requests`; see
+                        /// `progenitor_client::RequestLog`.
+                        pub fn with_recent_requests(
+                            mut self,
+                            capacity: usize,
+                            body_limit: usize,
+                        ) -> Self {
+                            let log = std::sync::Arc::new(
+                                progenitor_client::RequestLog::new(
+                                    capacity,
+                                    body_limit,
+                                ),
+                            );
+                            self.transport = std::sync::Arc::new(
+                                progenitor_client::RecordRequests::new(
+                                    self.transport,
+                                    log.clone(),
+                                ),
+                            );
+                            self.request_log = Some(log);
+                            self
+                        }
+
+                        /// Get summaries of the most recent requests, oldest
+                        /// first, if enabled with `Self::with_recent_requests`.
+                        pub fn recent_requests(
+                            &self,
+                        ) -> Vec<progenitor_client::RequestSummary> {
+                            self.request_log
+                                .as_ref()
+                                .map(|log| log.recent())
+                                .unwrap_or_default()
+                        }
+                    },
+                )
+            } else {
+                (quote! {}, quote! {}, quote! {})
+            };
+
+        let maybe_prewarm = self.settings.prewarm.then(|| {
+            let (transport, map_err) = if self.settings.custom_transport {
+                (
+                    quote! { transport },
+                    quote! { .map_err(Error::from_transport) },
+                )
+            } else {
+                (quote! { client }, quote! {})
+            };
+            quote! {
+                /// Open a connection to the host of the base URL ahead of the
+                /// first operation, so that it doesn't pay for connection
+                /// setup.
+                ///
+                /// This sends a `HEAD` request for the base URL, subject to the
+                /// client's TLS and proxy configuration, and discards the
+                /// response whatever its status; it fails only if the request
+                /// couldn't be sent.
+                pub async fn prewarm(&self) -> Result<(), Error> {
+                    let request = self.client.head(&self.baseurl).build()?;
+                    let response = self.#transport
+                        .execute(request)
+                        .await #map_err?;
+                    let _ = response.bytes().await;
+                    Ok(())
+                }
+            }
+        });
+
+        let maybe_operations = self.settings.operation_registry.then(|| {
+            let operations = raw_methods.iter().map(|method| {
+                let operation_id = &method.operation_id;
+                let http_method = method.method.as_str().to_uppercase();
+                let path = method.path.to_string();
+                quote! {
+                    progenitor_client::OperationInfo {
+                        operation_id: #operation_id,
+                        method: #http_method,
+                        path: #path,
+                    }
+                }
+            });
+            quote! {
+                /// Get descriptions of the operations of this API.
+                pub fn operations(
+                    &self,
+                ) -> &'static [progenitor_client::OperationInfo] {
+                    &[ #(#operations),* ]
+                }
+            }
+        });
+
         let client_docstring = {
             let mut s = format!("Client for {}", spec.info.title);
 
@@ -404,6 +937,18 @@ impl Generator {
 
         let version_str = &spec.info.version;
 
+        let response_schemas = if self.settings.response_schemas {
+            self.generate_response_schemas(spec)?
+        } else {
+            quote! {}
+        };
+
+        let blocking_client = if self.settings.blocking {
+            self.generate_blocking_client(&raw_methods, version_str)?
+        } else {
+            quote! {}
+        };
+
         // The allow(unused_imports) on the `pub use` is necessary with Rust 1.76+, in case the
         // generated file is not at the top level of the crate.
 
@@ -421,6 +966,7 @@ impl Generator {
             #[allow(clippy::all)]
             pub mod types {
                 #types
+                #success_enums
             }
 
             #[derive(Clone, Debug)]
@@ -428,6 +974,9 @@ impl Generator {
             pub struct Client {
                 pub(crate) baseurl: String,
                 pub(crate) client: reqwest::Client,
+                #transport_property
+                #fixups_property
+                #strict_property
                 #inner_property
             }
 
@@ -468,11 +1017,16 @@ impl Generator {
                 ) -> Self {
                     Self {
                         baseurl: baseurl.to_string(),
+                        #transport_value
+                        #fixups_value
+                        #strict_value
                         client,
                         #inner_value
                     }
                 }
 
+                #transport_new
+
                 /// Get the base URL to which requests are made.
                 pub fn baseurl(&self) -> &String {
                     &self.baseurl
@@ -491,15 +1045,116 @@ impl Generator {
                     #version_str
                 }
 
+                #maybe_prewarm
+
                 #maybe_inner
+
+                #maybe_operations
+
+                #maybe_fixups
+
+                #maybe_strict
             }
 
+            #(#extra_fields)*
+
             #operation_code
+
+            #blocking_client
+
+            #response_schemas
         };
 
         Ok(file)
     }
 
+    /// Generate an accessor for the map that captures unknown fields of the
+    /// struct generated for the named component schema, if typify emitted
+    /// one.
+    fn extra_fields_accessor(&mut self, name: &str) -> Result<TokenStream> {
+        let reference = schemars::schema::Schema::Object(
+            schemars::schema::SchemaObject::new_ref(format!(
+                "#/components/schemas/{}",
+                name
+            )),
+        );
+        let type_id = self.type_space.add_type(&reference)?;
+        let ty = self.type_space.get_type(&type_id)?;
+        let typify::TypeDetails::Struct(details) = ty.details() else {
+            return Ok(quote! {});
+        };
+        let Some((_, extra_id)) = details
+            .properties()
+            .find(|(property, _)| *property == "extra")
+        else {
+            return Ok(quote! {});
+        };
+        let type_ident = ty.ident();
+        let extra_ident = self.type_space.get_type(extra_id)?.ident();
+
+        Ok(quote! {
+            impl #type_ident {
+                /// Get the fields received for this value that the API
+                /// description doesn't include.
+                pub fn extra_fields(&self) -> &#extra_ident {
+                    &self.extra
+                }
+            }
+        })
+    }
+
+    fn generate_response_schemas(&self, spec: &OpenAPI) -> Result<TokenStream> {
+        // Component schemas are carried along with each operation's schema so
+        // that references of the form `#/components/schemas/...` resolve
+        // against the document root.
+        let component_schemas = spec
+            .components
+            .as_ref()
+            .map(|components| &components.schemas);
+
+        let consts = spec
+            .paths
+            .iter()
+            .filter_map(|(_, ref_or_item)| ref_or_item.as_item())
+            .flat_map(|item| item.iter().map(|(_, operation)| operation))
+            .map(|operation| {
+                let Some(schema) =
+                    success_response_schema(operation, &spec.components)?
+                else {
+                    return Ok(None);
+                };
+                let operation_id = operation.operation_id.as_ref().unwrap();
+
+                let document = serde_json::json!({
+                    "allOf": [schema],
+                    "components": { "schemas": component_schemas },
+                })
+                .to_string();
+                let name = quote::format_ident!(
+                    "{}",
+                    util::sanitize(operation_id, util::Case::Snake)
+                        .to_ascii_uppercase()
+                );
+                let doc = format!(
+                    "JSON Schema of the successful response of `{}`.",
+                    operation_id,
+                );
+
+                Ok(Some(quote! {
+                    #[doc = #doc]
+                    pub const #name: &str = #document;
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(quote! {
+            /// JSON Schemas of operation responses.
+            pub mod response_schemas {
+                #(#consts)*
+            }
+        })
+    }
+
     fn generate_tokens_positional_merged(
         &mut self,
         input_methods: &[method::OperationMethod],
@@ -527,6 +1182,86 @@ impl Generator {
         Ok(out)
     }
 
+    fn generate_blocking_client(
+        &mut self,
+        input_methods: &[method::OperationMethod],
+        version_str: &str,
+    ) -> Result<TokenStream> {
+        let methods = input_methods
+            .iter()
+            .map(|method| self.blocking_method(method))
+            .collect::<Result<Vec<_>>>()?;
+
+        let out = quote! {
+            /// Blocking client, for callers without an async runtime.
+            #[cfg(not(target_arch = "wasm32"))]
+            pub mod blocking {
+                #[allow(unused_imports)]
+                use super::*;
+
+                /// Client whose operations block the calling thread.
+                #[derive(Clone, Debug)]
+                pub struct Client {
+                    pub(crate) baseurl: String,
+                    pub(crate) client: reqwest::blocking::Client,
+                }
+
+                impl Client {
+                    /// Create a new client.
+                    ///
+                    /// `baseurl` is the base URL provided to the internal
+                    /// `reqwest::blocking::Client`, and should include a
+                    /// scheme and hostname, as well as port and a path stem if
+                    /// applicable.
+                    pub fn new(baseurl: &str) -> Self {
+                        let dur = std::time::Duration::from_secs(15);
+                        let client = reqwest::blocking::ClientBuilder::new()
+                            .connect_timeout(dur)
+                            .timeout(dur)
+                            .build()
+                            .unwrap();
+                        Self::new_with_client(baseurl, client)
+                    }
+
+                    /// Construct a new client with an existing
+                    /// `reqwest::blocking::Client`, allowing more control over
+                    /// its configuration.
+                    pub fn new_with_client(
+                        baseurl: &str,
+                        client: reqwest::blocking::Client,
+                    ) -> Self {
+                        Self {
+                            baseurl: baseurl.to_string(),
+                            client,
+                        }
+                    }
+
+                    /// Get the base URL to which requests are made.
+                    pub fn baseurl(&self) -> &String {
+                        &self.baseurl
+                    }
+
+                    /// Get the internal `reqwest::blocking::Client` used to
+                    /// make requests.
+                    pub fn client(&self) -> &reqwest::blocking::Client {
+                        &self.client
+                    }
+
+                    /// Get the version of this API.
+                    pub fn api_version(&self) -> &'static str {
+                        #version_str
+                    }
+                }
+
+                #[allow(clippy::all)]
+                impl Client {
+                    #(#methods)*
+                }
+            }
+        };
+        Ok(out)
+    }
+
     fn generate_tokens_builder_merged(
         &mut self,
         input_methods: &[method::OperationMethod],
@@ -696,6 +1431,70 @@ pub fn validate_openapi(spec: &OpenAPI) -> Result<()> {
     Ok(())
 }
 
+/// Permit unknown fields in a component object schema that doesn't say
+/// whether they're allowed, so that typify captures them in a flattened
+/// `extra` map. Returns whether the schema was changed.
+fn allow_unknown_fields(schema: &mut schemars::schema::Schema) -> bool {
+    let schemars::schema::Schema::Object(object) = schema else {
+        return false;
+    };
+    let Some(validation) = object.object.as_mut() else {
+        return false;
+    };
+    // A property named `extra` would collide with the captured fields.
+    if validation.properties.is_empty()
+        || validation.additional_properties.is_some()
+        || validation.properties.contains_key("extra")
+    {
+        return false;
+    }
+    validation.additional_properties = Some(Box::new(
+        schemars::schema::Schema::Object(Default::default()),
+    ));
+    true
+}
+
+/// Find the schema of the JSON body of an operation's success response,
+/// preferring explicit 2xx codes over a 2XX range over the default response.
+fn success_response_schema<'a>(
+    operation: &'a openapiv3::Operation,
+    components: &'a Option<openapiv3::Components>,
+) -> Result<Option<&'a openapiv3::ReferenceOr<openapiv3::Schema>>> {
+    use openapiv3::StatusCode;
+    use util::ReferenceOrExt;
+
+    let responses = &operation.responses;
+    let response = responses
+        .responses
+        .iter()
+        .find(|(code, _)| {
+            matches!(code, StatusCode::Code(code) if (200..300).contains(code))
+        })
+        .or_else(|| {
+            responses
+                .responses
+                .iter()
+                .find(|(code, _)| matches!(code, StatusCode::Range(2)))
+        })
+        .map(|(_, response)| response)
+        .or(responses.default.as_ref());
+
+    let Some(response) = response else {
+        return Ok(None);
+    };
+
+    Ok(response
+        .item(components)?
+        .content
+        .iter()
+        .find_map(|(content_type, media)| {
+            (content_type == "application/json"
+                || content_type.starts_with("application/json;"))
+            .then_some(media.schema.as_ref())
+        })
+        .flatten())
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
@@ -30,6 +30,48 @@ pub(crate) struct OperationMethod {
     pub responses: Vec<OperationResponse>,
     pub dropshot_paginated: Option<DropshotPagination>,
     dropshot_websocket: bool,
+    /// The largest request body the server accepts, in bytes.
+    max_request_size: Option<u64>,
+    /// Path parameters, by API name, that are arrays or aren't of the simple
+    /// style.
+    path_encodings: BTreeMap<String, PathEncoding>,
+    /// Whether each array query parameter, by API name, is exploded.
+    query_arrays: BTreeMap<String, bool>,
+}
+
+/// How to encode a path parameter that isn't a simple scalar.
+struct PathEncoding {
+    style: openapiv3::PathStyle,
+    explode: bool,
+    array: bool,
+}
+
+impl PathEncoding {
+    /// Generates an expression that encodes the parameter `name`.
+    fn encode(&self, api_name: &str, name: &syn::Ident) -> TokenStream {
+        let style = match self.style {
+            openapiv3::PathStyle::Simple => {
+                quote! { progenitor_client::PathStyle::Simple }
+            }
+            openapiv3::PathStyle::Label => {
+                quote! { progenitor_client::PathStyle::Label }
+            }
+            openapiv3::PathStyle::Matrix => {
+                quote! { progenitor_client::PathStyle::Matrix(#api_name) }
+            }
+        };
+        let values = if self.array {
+            quote! {
+                &#name.iter().map(ToString::to_string).collect::<Vec<_>>()
+            }
+        } else {
+            quote! { &[#name.to_string()] }
+        };
+        let explode = self.explode;
+        quote! {
+            progenitor_client::encode_path_array(#values, #style, #explode)
+        }
+    }
 }
 
 pub enum HttpMethod {
@@ -61,7 +103,7 @@ impl std::str::FromStr for HttpMethod {
     }
 }
 impl HttpMethod {
-    fn as_str(&self) -> &'static str {
+    pub(crate) fn as_str(&self) -> &'static str {
         match self {
             HttpMethod::Get => "get",
             HttpMethod::Put => "put",
@@ -81,6 +123,20 @@ struct MethodSigBody {
     body: TokenStream,
 }
 
+/// Variations on the method generated for an operation.
+#[derive(Clone, Copy)]
+enum MethodVariant<'a> {
+    /// The operation as described.
+    Plain,
+    /// Streams the elements of the operation's JSON array response.
+    Items(&'a TypeId),
+    /// Sends a `HEAD` request in place of a `GET`, producing only the
+    /// response headers.
+    Head,
+    /// The operation as described, sent with a `reqwest::blocking::Client`.
+    Blocking,
+}
+
 struct BuilderImpl {
     doc: String,
     sig: TokenStream,
@@ -137,6 +193,7 @@ pub enum BodyContentType {
     OctetStream,
     Json,
     FormUrlencoded,
+    Cbor,
     Text(String),
 }
 
@@ -149,6 +206,7 @@ impl FromStr for BodyContentType {
             "application/octet-stream" => Ok(Self::OctetStream),
             "application/json" => Ok(Self::Json),
             "application/x-www-form-urlencoded" => Ok(Self::FormUrlencoded),
+            "application/cbor" => Ok(Self::Cbor),
             "text/plain" | "text/x-markdown" => {
                 Ok(Self::Text(String::from(&s[..offset])))
             }
@@ -166,6 +224,7 @@ impl std::fmt::Display for BodyContentType {
             Self::OctetStream => "application/octet-stream",
             Self::Json => "application/json",
             Self::FormUrlencoded => "application/x-www-form-urlencoded",
+            Self::Cbor => "application/cbor",
             Self::Text(typ) => typ,
         })
     }
@@ -175,6 +234,8 @@ impl std::fmt::Display for BodyContentType {
 pub(crate) struct OperationResponse {
     pub status_code: OperationResponseStatus,
     pub typ: OperationResponseKind,
+    /// How the typed body is encoded.
+    pub encoding: ResponseEncoding,
     // TODO this isn't currently used because dropshot doesn't give us a
     // particularly useful message here.
     #[allow(dead_code)]
@@ -198,6 +259,14 @@ impl PartialOrd for OperationResponse {
     }
 }
 
+/// The encoding of a typed response body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ResponseEncoding {
+    Json,
+    MessagePack,
+    Cbor,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub(crate) enum OperationResponseStatus {
     Code(u16),
@@ -262,6 +331,12 @@ pub(crate) enum OperationResponseKind {
     None,
     Raw,
     Upgrade,
+    /// Success responses whose bodies differ by status, gathered into the
+    /// named enum; see [`Generator::success_response_enum`].
+    Multiple(String),
+    /// `application/problem+json` bodies without a schema, decoded as the
+    /// client's `ProblemDetails`.
+    Problem,
 }
 
 impl OperationResponseKind {
@@ -280,10 +355,38 @@ impl OperationResponseKind {
             OperationResponseKind::Upgrade => {
                 quote! { reqwest::Upgraded }
             }
+            OperationResponseKind::Multiple(ref name) => {
+                let type_name = format_ident!("{}", name);
+                quote! { types::#type_name }
+            }
+            OperationResponseKind::Problem => {
+                quote! { progenitor_client::ProblemDetails }
+            }
         }
     }
 }
 
+/// The name of the variant of an operation's success enum for a response.
+fn success_variant(
+    status_code: &OperationResponseStatus,
+) -> proc_macro2::Ident {
+    match status_code {
+        OperationResponseStatus::Code(code) => {
+            match http::StatusCode::from_u16(*code)
+                .ok()
+                .and_then(|status| status.canonical_reason())
+            {
+                Some(reason) => {
+                    format_ident!("{}", sanitize(reason, Case::Pascal))
+                }
+                None => format_ident!("Status{}", code),
+            }
+        }
+        OperationResponseStatus::Range(_) => format_ident!("Success"),
+        OperationResponseStatus::Default => format_ident!("Default"),
+    }
+}
+
 impl Generator {
     pub(crate) fn process_operation(
         &mut self,
@@ -303,6 +406,9 @@ impl Generator {
                 .insert(&parameter.parameter_data_ref().name, parameter);
         }
 
+        let mut path_encodings = BTreeMap::new();
+        let mut query_arrays = BTreeMap::new();
+
         // Filter out any path parameters that have been overridden by an
         // operation parameter
         let mut params = combined_path_parameters
@@ -311,7 +417,7 @@ impl Generator {
                 match parameter {
                     openapiv3::Parameter::Path {
                         parameter_data,
-                        style: openapiv3::PathStyle::Simple,
+                        style,
                     } => {
                         // Path parameters MUST be required.
                         assert!(parameter_data.required);
@@ -329,6 +435,27 @@ impl Generator {
                             .type_space
                             .add_type_with_name(&schema, Some(name))?;
 
+                        // Arrays and the label and matrix styles are encoded
+                        // by `encode_path_array`.
+                        let array = matches!(
+                            self.type_space.get_type(&typ)?.details(),
+                            typify::TypeDetails::Vec(_)
+                        );
+                        if array
+                            || !matches!(style, openapiv3::PathStyle::Simple)
+                        {
+                            path_encodings.insert(
+                                parameter_data.name.clone(),
+                                PathEncoding {
+                                    style: style.clone(),
+                                    explode: parameter_data
+                                        .explode
+                                        .unwrap_or(false),
+                                    array,
+                                },
+                            );
+                        }
+
                         Ok(OperationParameter {
                             name: sanitize(&parameter_data.name, Case::Snake),
                             api_name: parameter_data.name.clone(),
@@ -372,6 +499,17 @@ impl Generator {
                                 (type_id, parameter_data.required)
                             };
 
+                        // Arrays are encoded by `encode_query`; the form
+                        // style is exploded by default.
+                        if let typify::TypeDetails::Vec(_) =
+                            self.type_space.get_type(&type_id)?.details()
+                        {
+                            query_arrays.insert(
+                                parameter_data.name.clone(),
+                                parameter_data.explode.unwrap_or(true),
+                            );
+                        }
+
                         Ok(OperationParameter {
                             name: sanitize(&parameter_data.name, Case::Snake),
                             api_name: parameter_data.name.clone(),
@@ -408,12 +546,6 @@ impl Generator {
                             ),
                         })
                     }
-                    openapiv3::Parameter::Path { style, .. } => {
-                        Err(Error::UnexpectedFormat(format!(
-                            "unsupported style of path parameter {:#?}",
-                            style,
-                        )))
-                    }
                     openapiv3::Parameter::Query { style, .. } => {
                         Err(Error::UnexpectedFormat(format!(
                             "unsupported style of query parameter {:#?}",
@@ -439,6 +571,7 @@ impl Generator {
         if let Some(body_param) = self.get_body_param(operation, components)? {
             params.push(body_param);
         }
+        let max_request_size = Self::max_request_size(operation, components)?;
 
         let tmp = crate::template::parse(path)?;
         let names = tmp.names();
@@ -485,15 +618,37 @@ impl Generator {
                 // enum; the generated client method would check for the
                 // content type of the response just as it currently examines
                 // the status code.
-                let typ = if let Some(mt) =
+                // With the msgpack and cbor settings, MessagePack and CBOR
+                // bodies are typed too, though JSON is preferred if several
+                // are offered. RFC 7807 "application/problem+json" bodies
+                // are JSON as well.
+                let json = response.content.iter().find_map(|(x, v)| {
+                    (x == "application/json"
+                        || x.starts_with("application/json;"))
+                    .then_some((v, ResponseEncoding::Json))
+                });
+                let problem = || {
                     response.content.iter().find_map(|(x, v)| {
-                        (x == "application/json"
-                            || x.starts_with("application/json;"))
-                        .then_some(v)
-                    }) {
+                        is_problem_json(x)
+                            .then_some((v, ResponseEncoding::Json))
+                    })
+                };
+                let binary = || {
+                    response.content.iter().find_map(|(x, v)| {
+                        if self.settings.msgpack && is_msgpack(x) {
+                            Some((v, ResponseEncoding::MessagePack))
+                        } else if self.settings.cbor && is_cbor(x) {
+                            Some((v, ResponseEncoding::Cbor))
+                        } else {
+                            None
+                        }
+                    })
+                };
+                let typed = json.or_else(problem).or_else(binary);
+                let typ = if let Some((mt, _)) = typed {
                     assert!(mt.encoding.is_empty());
 
-                    let typ = if let Some(schema) = &mt.schema {
+                    if let Some(schema) = &mt.schema {
                         let schema = schema.to_schema();
                         let name = sanitize(
                             &format!(
@@ -502,13 +657,15 @@ impl Generator {
                             ),
                             Case::Pascal,
                         );
-                        self.type_space
-                            .add_type_with_name(&schema, Some(name))?
+                        OperationResponseKind::Type(
+                            self.type_space
+                                .add_type_with_name(&schema, Some(name))?,
+                        )
+                    } else if json.is_none() && problem().is_some() {
+                        OperationResponseKind::Problem
                     } else {
                         todo!("media type encoding, no schema: {:#?}", mt);
-                    };
-
-                    OperationResponseKind::Type(typ)
+                    }
                 } else if dropshot_websocket {
                     OperationResponseKind::Upgrade
                 } else if response.content.first().is_some() {
@@ -536,6 +693,10 @@ impl Generator {
                 Ok(OperationResponse {
                     status_code,
                     typ,
+                    encoding: typed
+                        .map_or(ResponseEncoding::Json, |(_, encoding)| {
+                            encoding
+                        }),
                     description,
                 })
             })
@@ -549,6 +710,7 @@ impl Generator {
             responses.push(OperationResponse {
                 status_code: OperationResponseStatus::Range(2),
                 typ: OperationResponseKind::Raw,
+                encoding: ResponseEncoding::Json,
                 description: None,
             });
         }
@@ -558,6 +720,7 @@ impl Generator {
             responses.push(OperationResponse {
                 status_code: OperationResponseStatus::Code(101),
                 typ: OperationResponseKind::Upgrade,
+                encoding: ResponseEncoding::Json,
                 description: None,
             })
         }
@@ -586,27 +749,41 @@ impl Generator {
             responses,
             dropshot_paginated,
             dropshot_websocket,
+            max_request_size,
+            path_encodings,
+            query_arrays,
         })
     }
 
-    pub(crate) fn positional_method(
-        &mut self,
+    /// Renders the parameters of the positional method for an operation as
+    /// they appear in its signature, along with its generic parameters; a raw
+    /// body may be anything that converts into `body_type`.
+    fn positional_params(
+        &self,
         method: &OperationMethod,
-    ) -> Result<TokenStream> {
-        let operation_id = format_ident!("{}", method.operation_id);
-
-        // Render each parameter as it will appear in the method signature.
+        body_type: TokenStream,
+    ) -> (Vec<TokenStream>, TokenStream) {
         let params = method
             .params
             .iter()
             .map(|param| {
                 let name = format_ident!("{}", param.name);
                 let typ = match (&param.typ, param.kind.is_optional()) {
-                    (OperationParameterType::Type(type_id), false) => self
-                        .type_space
-                        .get_type(type_id)
-                        .unwrap()
-                        .parameter_ident_with_lifetime("a"),
+                    (OperationParameterType::Type(type_id), false) => {
+                        match (&param.kind, self.array_body_item(type_id)) {
+                            // Array bodies are borrowed as slices, which
+                            // serialize just as their vectors do, so that
+                            // callers needn't own or clone a `Vec`.
+                            (OperationParameterKind::Body(_), Some(item)) => {
+                                quote! { &'a [#item] }
+                            }
+                            _ => self
+                                .type_space
+                                .get_type(type_id)
+                                .unwrap()
+                                .parameter_ident_with_lifetime("a"),
+                        }
+                    }
                     (OperationParameterType::Type(type_id), true) => {
                         let t = self
                             .type_space
@@ -647,18 +824,98 @@ impl Generator {
         });
 
         let bounds = if raw_body_param {
-            quote! { <'a, B: Into<reqwest::Body> > }
+            quote! { <'a, B: Into<#body_type> > }
         } else {
             quote! { <'a> }
         };
 
+        (params, bounds)
+    }
+
+    /// Generates the method of the blocking client for an operation, or
+    /// `None` if the operation needs something the blocking client doesn't
+    /// support: a WebSocket upgrade, a MessagePack or CBOR body, or a success
+    /// enum holding byte streams.
+    pub(crate) fn blocking_method(
+        &mut self,
+        method: &OperationMethod,
+    ) -> Result<Option<TokenStream>> {
+        let unsupported_body = method.params.iter().any(|param| {
+            param.kind == OperationParameterKind::Body(BodyContentType::Cbor)
+        });
+        let unsupported_response = method.responses.iter().any(|response| {
+            matches!(response.typ, OperationResponseKind::Upgrade)
+                || (matches!(response.typ, OperationResponseKind::Type(_))
+                    && response.encoding != ResponseEncoding::Json)
+        });
+        // The success enum of an operation holds its byte streams as
+        // `ByteStream`s, which a blocking client can't produce.
+        let (_, response_type) = self.extract_responses(
+            method,
+            OperationResponseStatus::is_success_or_default,
+        );
+        let multiple_raw =
+            matches!(response_type, OperationResponseKind::Multiple(_))
+                && method.responses.iter().any(|response| {
+                    response.status_code.is_success_or_default()
+                        && matches!(response.typ, OperationResponseKind::Raw)
+                });
+        if method.dropshot_websocket
+            || unsupported_body
+            || unsupported_response
+            || multiple_raw
+        {
+            return Ok(None);
+        }
+
+        let operation_id = format_ident!("{}", method.operation_id);
+        let (params, bounds) =
+            self.positional_params(method, quote! { reqwest::blocking::Body });
+        let doc_comment = make_doc_comment(method);
+
+        let MethodSigBody {
+            success: success_type,
+            error: error_type,
+            body,
+        } = self.method_sig_body(
+            method,
+            quote! { self },
+            MethodVariant::Blocking,
+        )?;
+
+        Ok(Some(quote! {
+            #[doc = #doc_comment]
+            pub fn #operation_id #bounds (
+                &'a self,
+                #(#params),*
+            ) -> Result<
+                ResponseValue<#success_type>,
+                Error<#error_type>,
+            > {
+                #body
+            }
+        }))
+    }
+
+    pub(crate) fn positional_method(
+        &mut self,
+        method: &OperationMethod,
+    ) -> Result<TokenStream> {
+        let operation_id = format_ident!("{}", method.operation_id);
+        let (params, bounds) =
+            self.positional_params(method, quote! { reqwest::Body });
+
         let doc_comment = make_doc_comment(method);
 
         let MethodSigBody {
             success: success_type,
             error: error_type,
             body,
-        } = self.method_sig_body(method, quote! { self })?;
+        } = self.method_sig_body(
+            method,
+            quote! { self },
+            MethodVariant::Plain,
+        )?;
 
         let method_impl = quote! {
             #[doc = #doc_comment]
@@ -673,6 +930,77 @@ impl Generator {
             }
         };
 
+        let items_impl = match self.array_item_type(method) {
+            Some(item) if self.settings.stream_items => {
+                let items_id = format_ident!("{}_items", method.operation_id);
+                let MethodSigBody {
+                    success: items_type,
+                    error: _,
+                    body: items_body,
+                } = self.method_sig_body(
+                    method,
+                    quote! { self },
+                    MethodVariant::Items(&item),
+                )?;
+                let doc_comment = format!(
+                    "Sends the same request as [`Client::{}`], producing the \
+                    elements of the response array as they arrive rather \
+                    than buffering the whole response.",
+                    method.operation_id,
+                );
+
+                Some(quote! {
+                    #[doc = #doc_comment]
+                    pub async fn #items_id #bounds (
+                        &'a self,
+                        #(#params),*
+                    ) -> Result<
+                        ResponseValue<#items_type>,
+                        Error<#error_type>,
+                    > {
+                        #items_body
+                    }
+                })
+            }
+            _ => None,
+        };
+
+        let has_body = method
+            .params
+            .iter()
+            .any(|param| matches!(param.kind, OperationParameterKind::Body(_)));
+        // A `HEAD` request can't upgrade the connection, so WebSocket
+        // operations have no `HEAD` variant.
+        let head_impl = (self.settings.head_variants
+            && matches!(method.method, HttpMethod::Get)
+            && !has_body
+            && !method.dropshot_websocket)
+            .then(|| {
+                let head_id = format_ident!("{}_head", method.operation_id);
+                let MethodSigBody { body, .. } = self.method_sig_body(
+                    method,
+                    quote! { self },
+                    MethodVariant::Head,
+                )?;
+                let doc_comment = format!(
+                    "Sends a `HEAD` request in place of the `GET` request of \
+                    [`Client::{}`], producing the response headers (e.g. the \
+                    content length or ETag) without the body.",
+                    method.operation_id,
+                );
+
+                Ok::<_, Error>(quote! {
+                    #[doc = #doc_comment]
+                    pub async fn #head_id #bounds (
+                        &'a self,
+                        #(#params),*
+                    ) -> Result<ResponseValue<()>, Error<()>> {
+                        #body
+                    }
+                })
+            })
+            .transpose()?;
+
         let stream_impl = method.dropshot_paginated.as_ref().map(|page_data| {
             // We're now using futures.
             self.uses_futures = true;
@@ -801,6 +1129,8 @@ impl Generator {
 
         let all = quote! {
             #method_impl
+            #items_impl
+            #head_impl
             #stream_impl
         };
 
@@ -814,7 +1144,15 @@ impl Generator {
         &self,
         method: &OperationMethod,
         client: TokenStream,
+        variant: MethodVariant<'_>,
     ) -> Result<MethodSigBody> {
+        let array_item = match variant {
+            MethodVariant::Items(item) => Some(item),
+            _ => None,
+        };
+        let head = matches!(variant, MethodVariant::Head);
+        let blocking = matches!(variant, MethodVariant::Blocking);
+
         let param_names = method
             .params
             .iter()
@@ -833,6 +1171,11 @@ impl Generator {
             .params
             .iter()
             .filter_map(|param| match &param.kind {
+                OperationParameterKind::Query(_)
+                    if method.query_arrays.contains_key(&param.api_name) =>
+                {
+                    None
+                }
                 OperationParameterKind::Query(required) => {
                     let qn = &param.api_name;
                     let qn_ident = format_ident!("{}", &param.name);
@@ -854,6 +1197,57 @@ impl Generator {
             })
             .collect::<Vec<_>>();
 
+        // Array query parameters are encoded once the request is built, as
+        // their values are encoded up front.
+        let query_arrays = method
+            .params
+            .iter()
+            .filter_map(|param| {
+                let explode = method.query_arrays.get(&param.api_name)?;
+                let OperationParameterKind::Query(required) = &param.kind
+                else {
+                    return None;
+                };
+                let qn = &param.api_name;
+                let qn_ident = format_ident!("{}", &param.name);
+                // A blocking request isn't a `reqwest::Request`, so its URL
+                // is modified directly.
+                let (append_fn, target) = if blocking {
+                    (
+                        quote! { append_query_to_url },
+                        quote! { #request_ident.url_mut() },
+                    )
+                } else {
+                    (quote! { append_query }, quote! { &mut #request_ident })
+                };
+                let append = |values: TokenStream| {
+                    quote! {
+                        progenitor_client::#append_fn(
+                            #target,
+                            progenitor_client::encode_query(
+                                #qn,
+                                &#values
+                                    .iter()
+                                    .map(ToString::to_string)
+                                    .collect::<Vec<_>>(),
+                                #explode,
+                            ),
+                        );
+                    }
+                };
+                Some(if *required {
+                    append(quote! { #qn_ident })
+                } else {
+                    let append = append(quote! { v });
+                    quote! {
+                        if let Some(v) = & #qn_ident {
+                            #append
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
         let (query_build, query_use) = if query_items.is_empty() {
             (quote! {}, quote! {})
         } else {
@@ -938,7 +1332,21 @@ impl Generator {
             })
             .collect();
 
-        let url_path = method.path.compile(url_renames, client.clone());
+        let path_encoders = method
+            .params
+            .iter()
+            .filter_map(|param| {
+                let encoding = method.path_encodings.get(&param.api_name)?;
+                let name = format_ident!("{}", param.name);
+                Some((&param.api_name, encoding.encode(&param.api_name, &name)))
+            })
+            .collect();
+
+        let url_path = method.path.compile_with(
+            url_renames,
+            &path_encoders,
+            client.clone(),
+        );
         let url_path = quote! {
             let #url_ident = #url_path;
         };
@@ -970,6 +1378,19 @@ impl Generator {
                     )
                     .body(body)
                 }),
+                (
+                    OperationParameterKind::Body(BodyContentType::Json),
+                    OperationParameterType::Type(_),
+                ) if self.settings.debug_schema => {
+                    let operation_id = &method.operation_id;
+                    Some(quote! {
+                        // Serialization errors are deferred.
+                        .json(progenitor_client::debug_request_body(
+                            #operation_id,
+                            &body,
+                        ))
+                    })
+                }
                 (
                     OperationParameterKind::Body(BodyContentType::Json),
                     OperationParameterType::Type(_),
@@ -977,6 +1398,15 @@ impl Generator {
                     // Serialization errors are deferred.
                     .json(&body)
                 }),
+                (
+                    OperationParameterKind::Body(
+                        BodyContentType::FormUrlencoded
+                    ),
+                    OperationParameterType::Type(_),
+                ) if blocking => Some(quote! {
+                    // Serialization errors are deferred.
+                    .form(&body)
+                }),
                 (
                     OperationParameterKind::Body(
                         BodyContentType::FormUrlencoded
@@ -987,6 +1417,13 @@ impl Generator {
                     // returns an error in the case of a serialization failure.
                     .form_urlencoded(&body)?
                 }),
+                (
+                    OperationParameterKind::Body(BodyContentType::Cbor),
+                    OperationParameterType::Type(_),
+                ) => Some(quote! {
+                    // As above, serialization failures are errors.
+                    .cbor(&body)?
+                }),
                 (OperationParameterKind::Body(_), _) => {
                     unreachable!("invalid body kind/type combination")
                 }
@@ -1001,6 +1438,12 @@ impl Generator {
             OperationResponseStatus::is_success_or_default,
         );
 
+        let (empty, stream) = if blocking {
+            (quote! { empty_blocking }, quote! { stream_blocking })
+        } else {
+            (quote! { empty }, quote! { stream })
+        };
+
         let success_response_matches =
             success_response_items.iter().map(|response| {
                 let pat = match &response.status_code {
@@ -1012,19 +1455,59 @@ impl Generator {
                 };
 
                 let decode = match &response.typ {
-                    OperationResponseKind::Type(_) => {
+                    OperationResponseKind::Type(_)
+                        if response.encoding
+                            == ResponseEncoding::MessagePack =>
+                    {
                         quote! {
-                            ResponseValue::from_response(#response_ident).await
+                            ResponseValue::from_response_msgpack(
+                                #response_ident,
+                            ).await
                         }
                     }
+                    OperationResponseKind::Type(_)
+                        if response.encoding == ResponseEncoding::Cbor =>
+                    {
+                        quote! {
+                            ResponseValue::from_response_cbor(
+                                #response_ident,
+                            ).await
+                        }
+                    }
+                    OperationResponseKind::Type(_) if array_item.is_some() => {
+                        quote! {
+                            Ok(ResponseValue::json_array_stream(#response_ident))
+                        }
+                    }
+                    OperationResponseKind::Type(_)
+                    | OperationResponseKind::Problem
+                        if blocking =>
+                    {
+                        let lenient = self.settings.lenient_content_type;
+                        quote! {
+                            ResponseValue::from_response_blocking(
+                                #response_ident,
+                                #lenient,
+                            )
+                        }
+                    }
+                    OperationResponseKind::Type(_)
+                    | OperationResponseKind::Problem => {
+                        let decode = self.decode_response(
+                            method,
+                            &client,
+                            &response_ident,
+                        );
+                        quote! { #decode.await }
+                    }
                     OperationResponseKind::None => {
                         quote! {
-                            Ok(ResponseValue::empty(#response_ident))
+                            Ok(ResponseValue::#empty(#response_ident))
                         }
                     }
                     OperationResponseKind::Raw => {
                         quote! {
-                            Ok(ResponseValue::stream(#response_ident))
+                            Ok(ResponseValue::#stream(#response_ident))
                         }
                     }
                     OperationResponseKind::Upgrade => {
@@ -1032,6 +1515,29 @@ impl Generator {
                             ResponseValue::upgrade(#response_ident).await
                         }
                     }
+                    OperationResponseKind::Multiple(_) => unreachable!(),
+                };
+
+                // Wrap each body in the variant of the success enum for its
+                // status.
+                let decode = match (&response_type, &response.typ) {
+                    (
+                        OperationResponseKind::Multiple(name),
+                        OperationResponseKind::None,
+                    ) => {
+                        let name = format_ident!("{}", name);
+                        let variant = success_variant(&response.status_code);
+                        quote! {
+                            ResponseValue::#empty(#response_ident)
+                                .map(|()| types::#name::#variant)
+                        }
+                    }
+                    (OperationResponseKind::Multiple(name), _) => {
+                        let name = format_ident!("{}", name);
+                        let variant = success_variant(&response.status_code);
+                        quote! { #decode?.map(types::#name::#variant) }
+                    }
+                    _ => decode,
                 };
 
                 quote! { #pat => { #decode } }
@@ -1061,28 +1567,69 @@ impl Generator {
                 };
 
                 let decode = match &response.typ {
-                    OperationResponseKind::Type(_) => {
+                    OperationResponseKind::Type(_)
+                        if response.encoding
+                            == ResponseEncoding::MessagePack =>
+                    {
+                        quote! {
+                            Err(Error::ErrorResponse(
+                                ResponseValue::from_response_msgpack(
+                                    #response_ident,
+                                ).await?
+                            ))
+                        }
+                    }
+                    OperationResponseKind::Type(_)
+                        if response.encoding == ResponseEncoding::Cbor =>
+                    {
                         quote! {
                             Err(Error::ErrorResponse(
-                                ResponseValue::from_response(#response_ident)
-                                    .await?
+                                ResponseValue::from_response_cbor(
+                                    #response_ident,
+                                ).await?
                             ))
                         }
                     }
+                    OperationResponseKind::Type(_)
+                    | OperationResponseKind::Problem
+                        if blocking =>
+                    {
+                        let lenient = self.settings.lenient_content_type;
+                        quote! {
+                            Err(Error::ErrorResponse(
+                                ResponseValue::from_response_blocking(
+                                    #response_ident,
+                                    #lenient,
+                                )?
+                            ))
+                        }
+                    }
+                    OperationResponseKind::Type(_)
+                    | OperationResponseKind::Problem => {
+                        let decode = self.decode_response(
+                            method,
+                            &client,
+                            &response_ident,
+                        );
+                        quote! {
+                            Err(Error::ErrorResponse(#decode.await?))
+                        }
+                    }
                     OperationResponseKind::None => {
                         quote! {
                             Err(Error::ErrorResponse(
-                                ResponseValue::empty(#response_ident)
+                                ResponseValue::#empty(#response_ident)
                             ))
                         }
                     }
                     OperationResponseKind::Raw => {
                         quote! {
                             Err(Error::ErrorResponse(
-                                ResponseValue::stream(#response_ident)
+                                ResponseValue::#stream(#response_ident)
                             ))
                         }
                     }
+                    OperationResponseKind::Multiple(_) => unreachable!(),
                     OperationResponseKind::Upgrade => {
                         if response.status_code
                             == OperationResponseStatus::Default
@@ -1100,17 +1647,52 @@ impl Generator {
                 quote! { #pat => { #decode } }
             });
 
-        let accept_header = matches!(
-            (&response_type, &error_type),
-            (OperationResponseKind::Type(_), _)
-                | (OperationResponseKind::None, OperationResponseKind::Type(_))
-        )
+        // Accept the media types of the typed responses.
+        let typed = success_response_items
+            .iter()
+            .chain(&error_response_items)
+            .filter(|response| {
+                matches!(response.typ, OperationResponseKind::Type(_))
+            })
+            .collect::<Vec<_>>();
+        let accepts = |encoding| {
+            typed.iter().any(|response| response.encoding == encoding)
+        };
+        let problem = success_response_items
+            .iter()
+            .chain(&error_response_items)
+            .any(|response| response.typ == OperationResponseKind::Problem);
+        let accept = [
+            (accepts(ResponseEncoding::Json) || typed.is_empty())
+                .then_some("application/json"),
+            accepts(ResponseEncoding::MessagePack)
+                .then_some("application/msgpack"),
+            accepts(ResponseEncoding::Cbor).then_some("application/cbor"),
+            problem.then_some("application/problem+json"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(", ");
+        let accept_header = (!head
+            && matches!(
+                (&response_type, &error_type),
+                (
+                    OperationResponseKind::Type(_)
+                        | OperationResponseKind::Multiple(_),
+                    _
+                ) | (
+                    OperationResponseKind::None,
+                    OperationResponseKind::Type(_)
+                        | OperationResponseKind::Problem
+                )
+            ))
         .then(|| {
             quote! {
                     .header(
                         reqwest::header::ACCEPT,
                         reqwest::header::HeaderValue::from_static(
-                            "application/json",
+                            #accept,
                         ),
                     )
             }
@@ -1124,11 +1706,46 @@ impl Generator {
         // API description.
         let default_response = match method.responses.iter().last() {
             Some(response) if response.status_code.is_default() => quote! {},
+            // The response of a blocking request is read into a
+            // `reqwest::Response` to be reported.
+            _ if blocking => quote! {
+                _ => Err(Error::unexpected_blocking(#response_ident)),
+            },
             _ => {
                 quote! { _ => Err(Error::UnexpectedResponse(#response_ident)), }
             }
         };
 
+        // Reject oversized bodies before sending them.
+        let size_check = method
+            .max_request_size
+            .filter(|_| {
+                method.params.iter().any(|param| {
+                    matches!(param.kind, OperationParameterKind::Body(_))
+                })
+            })
+            .map(|max| {
+                if blocking {
+                    quote! {
+                        progenitor_client::check_body_size(
+                            #request_ident
+                                .body()
+                                .and_then(|body| body.as_bytes()),
+                            #max,
+                        )
+                        .map_err(Error::InvalidRequest)?;
+                    }
+                } else {
+                    quote! {
+                        progenitor_client::check_request_size(
+                            &#request_ident,
+                            #max,
+                        )
+                        .map_err(Error::InvalidRequest)?;
+                    }
+                }
+            });
+
         let pre_hook = self.settings.pre_hook.as_ref().map(|hook| {
             quote! {
                 (#hook)(&#client.inner, &#request_ident);
@@ -1147,35 +1764,48 @@ impl Generator {
                 (#hook)(&#client.inner, &#result_ident);
             }
         });
+        // Hooks take the client's inner value and `reqwest` requests, and so
+        // don't apply to the blocking client.
+        let (pre_hook, pre_hook_async, post_hook) = if blocking {
+            (None, None, None)
+        } else {
+            (pre_hook, pre_hook_async, post_hook)
+        };
 
-        let method_func = format_ident!("{}", method.method.as_str());
-
-        let body_impl = quote! {
-            #url_path
-            #query_build
-
-            #headers_build
-
-            #[allow(unused_mut)]
-            let mut #request_ident = #client.client
-                . #method_func (#url_ident)
-                #accept_header
-                #(#body_func)*
-                #query_use
-                #headers_use
-                #websock_hdrs
-                .build()?;
-
-            #pre_hook
-            #pre_hook_async
-            let #result_ident = #client.client
-                .execute(#request_ident)
-                .await;
-            #post_hook
+        let (transport, transport_error) = if self.settings.custom_transport {
+            (
+                quote! { transport },
+                quote! { .map_err(Error::from_transport) },
+            )
+        } else {
+            (quote! { client }, quote! {})
+        };
+        let (execute, transport_error) = if blocking {
+            (quote! { #client.client.execute(#request_ident) }, quote! {})
+        } else {
+            let execute = quote! {
+                #client.#transport
+                    .execute(#request_ident)
+                    .await
+            };
+            (execute, transport_error)
+        };
 
-            let #response_ident = #result_ident?;
+        let method_func = if head {
+            format_ident!("head")
+        } else {
+            format_ident!("{}", method.method.as_str())
+        };
 
-            match #response_ident.status().as_u16() {
+        // A `HEAD` response has no body to decode, so any success produces
+        // just its headers.
+        let response_matches = if head {
+            quote! {
+                200u16..=299u16 => Ok(ResponseValue::empty(#response_ident)),
+                _ => Err(Error::UnexpectedResponse(#response_ident)),
+            }
+        } else {
+            quote! {
                 // These will be of the form...
                 // 201 => ResponseValue::from_response(response).await,
                 // 200..299 => ResponseValue::empty(response),
@@ -1211,13 +1841,156 @@ impl Generator {
             }
         };
 
+        let body_impl = quote! {
+            #url_path
+            #query_build
+
+            #headers_build
+
+            #[allow(unused_mut)]
+            let mut #request_ident = #client.client
+                . #method_func (#url_ident)
+                #accept_header
+                #(#body_func)*
+                #query_use
+                #headers_use
+                #websock_hdrs
+                .build()?;
+            #(#query_arrays)*
+            #size_check
+
+            #pre_hook
+            #pre_hook_async
+            let #result_ident = #execute;
+            #post_hook
+
+            let #response_ident = #result_ident #transport_error?;
+
+            match #response_ident.status().as_u16() {
+                #response_matches
+            }
+        };
+
+        let error = if head {
+            quote! { () }
+        } else if blocking && error_type == OperationResponseKind::Raw {
+            quote! { progenitor_client::BlockingBody }
+        } else {
+            error_type.into_tokens(&self.type_space)
+        };
+        let success = match variant {
+            MethodVariant::Items(item) => {
+                let item = self.type_space.get_type(item)?.ident();
+                quote! { progenitor_client::JsonArrayStream<#item, #error> }
+            }
+            MethodVariant::Head => quote! { () },
+            MethodVariant::Blocking
+                if response_type == OperationResponseKind::Raw =>
+            {
+                quote! { progenitor_client::BlockingBody }
+            }
+            MethodVariant::Plain | MethodVariant::Blocking => {
+                response_type.into_tokens(&self.type_space)
+            }
+        };
+
         Ok(MethodSigBody {
-            success: response_type.into_tokens(&self.type_space),
-            error: error_type.into_tokens(&self.type_space),
+            success,
+            error,
             body: body_impl,
         })
     }
 
+    /// Generates the call that deserializes a JSON response body, applying
+    /// the client's fixups, reporting the body to the debug hook, and
+    /// ignoring its `Content-Type` if enabled.
+    fn decode_response(
+        &self,
+        method: &OperationMethod,
+        client: &TokenStream,
+        response_ident: &proc_macro2::Ident,
+    ) -> TokenStream {
+        let lenient = self.settings.lenient_content_type;
+        if self.settings.strict_decoding {
+            let operation_id = &method.operation_id;
+            let fixups = if self.settings.response_fixups {
+                quote! { Some(&#client.response_fixups) }
+            } else {
+                quote! { None }
+            };
+            let debug = self.settings.debug_schema;
+            quote! {
+                ResponseValue::from_response_strict(
+                    #response_ident,
+                    #operation_id,
+                    #fixups,
+                    #debug,
+                    #client.strict_decoding,
+                    #lenient,
+                )
+            }
+        } else if self.settings.response_fixups {
+            let operation_id = &method.operation_id;
+            let debug = self.settings.debug_schema;
+            quote! {
+                ResponseValue::from_response_fixup(
+                    #response_ident,
+                    #operation_id,
+                    &#client.response_fixups,
+                    #debug,
+                    #lenient,
+                )
+            }
+        } else if self.settings.debug_schema {
+            let operation_id = &method.operation_id;
+            quote! {
+                ResponseValue::from_response_debug(
+                    #response_ident,
+                    #operation_id,
+                    #lenient,
+                )
+            }
+        } else if lenient {
+            quote! { ResponseValue::from_response_lenient(#response_ident) }
+        } else {
+            quote! { ResponseValue::from_response(#response_ident) }
+        }
+    }
+
+    /// If `type_id` is an array, returns the type of its elements for use in a
+    /// borrowed body slice.
+    fn array_body_item(&self, type_id: &TypeId) -> Option<TokenStream> {
+        match self.type_space.get_type(type_id).ok()?.details() {
+            typify::TypeDetails::Vec(item) => {
+                Some(self.type_space.get_type(&item).ok()?.ident())
+            }
+            _ => None,
+        }
+    }
+
+    /// If the operation's success response is a JSON array, returns the type
+    /// of its elements.
+    fn array_item_type(&self, method: &OperationMethod) -> Option<TypeId> {
+        let (success, response_type) = self.extract_responses(
+            method,
+            OperationResponseStatus::is_success_or_default,
+        );
+        let OperationResponseKind::Type(type_id) = response_type else {
+            return None;
+        };
+        // Array streaming parses JSON.
+        if success
+            .iter()
+            .any(|response| response.encoding != ResponseEncoding::Json)
+        {
+            return None;
+        }
+        match self.type_space.get_type(&type_id).ok()?.details() {
+            typify::TypeDetails::Vec(item) => Some(item),
+            _ => None,
+        }
+    }
+
     /// Extract responses that match criteria specified by the `filter`. The
     /// result is a `Vec<OperationResponse>` that enumerates the cases matching
     /// the filter, and a `TokenStream` that represents the generated type for
@@ -1259,8 +2032,27 @@ impl Generator {
             .map(|response| response.typ.clone())
             .collect::<BTreeSet<_>>();
 
-        // TODO to deal with multiple response types, we'll need to create an
-        // enum type with variants for each of the response types.
+        // Success responses with bodies that differ by status are gathered
+        // into an enum with a variant for each status.
+        if response_types.len() > 1
+            && response_items.iter().all(|response| {
+                response.status_code.is_success_or_default()
+                    && matches!(
+                        response.typ,
+                        OperationResponseKind::Type(_)
+                            | OperationResponseKind::None
+                    )
+            })
+        {
+            let name = sanitize(
+                &format!("{}_success", method.operation_id),
+                Case::Pascal,
+            );
+            return (response_items, OperationResponseKind::Multiple(name));
+        }
+
+        // TODO to deal with multiple error response types, we'll need to
+        // create an enum type with variants for each of the response types.
         assert!(response_types.len() <= 1);
         let response_type = response_types
             .into_iter()
@@ -1270,6 +2062,63 @@ impl Generator {
         (response_items, response_type)
     }
 
+    /// Generates the enum for an operation whose success responses have
+    /// bodies that differ by status, if it has any.
+    pub(crate) fn success_response_enum(
+        &self,
+        method: &OperationMethod,
+    ) -> Option<TokenStream> {
+        let (response_items, response_type) = self.extract_responses(
+            method,
+            OperationResponseStatus::is_success_or_default,
+        );
+        let OperationResponseKind::Multiple(name) = response_type else {
+            return None;
+        };
+        let name = format_ident!("{}", name);
+
+        let variants = response_items.iter().map(|response| {
+            let variant = success_variant(&response.status_code);
+            let doc = match &response.status_code {
+                OperationResponseStatus::Code(code) => {
+                    format!("The body of a `{}` response.", code)
+                }
+                OperationResponseStatus::Range(_) => {
+                    "The body of any other `2xx` response.".to_string()
+                }
+                OperationResponseStatus::Default => {
+                    "The body of any other successful response.".to_string()
+                }
+            };
+            match &response.typ {
+                OperationResponseKind::Type(type_id) => {
+                    let typ =
+                        self.type_space.get_type(type_id).unwrap().ident();
+                    quote! {
+                        #[doc = #doc]
+                        #variant(#typ)
+                    }
+                }
+                _ => quote! {
+                    #[doc = #doc]
+                    #variant
+                },
+            }
+        });
+
+        let doc = format!(
+            "Successful responses of `{}`, distinguished by status.",
+            method.operation_id,
+        );
+        Some(quote! {
+            #[doc = #doc]
+            #[derive(Clone, Debug)]
+            pub enum #name {
+                #(#variants,)*
+            }
+        })
+    }
+
     // Validates all the necessary conditions for Dropshot pagination. Returns
     // the paginated item type data if all conditions are met.
     fn dropshot_pagination_data(
@@ -1505,6 +2354,16 @@ impl Generator {
                     ) = (&param.kind, ty.builder())
                     {
                         Ok(quote! { Result<#builder_name, String> })
+                    } else if let (
+                        OperationParameterKind::Body(_),
+                        Some(item),
+                    ) = (&param.kind, self.array_body_item(type_id))
+                    {
+                        // Array bodies may be borrowed, as they are by
+                        // positional methods, or owned.
+                        Ok(quote! {
+                            Result<std::borrow::Cow<'a, [#item]>, String>
+                        })
                     } else if param.kind.is_required() {
                         let t = ty.ident();
                         Ok(quote! { Result<#t, String> })
@@ -1586,13 +2445,20 @@ impl Generator {
                 match &param.typ {
                     OperationParameterType::Type(type_id) => {
                         let ty = self.type_space.get_type(type_id)?;
-                        match (ty.builder(), param.kind.is_optional()) {
+                        let array_item = match &param.kind {
+                            OperationParameterKind::Body(_) => {
+                                self.array_body_item(type_id)
+                            }
+                            _ => None,
+                        };
+                        let optional = param.kind.is_optional();
+                        match (ty.builder(), optional, array_item) {
                             // TODO right now optional body parameters are not
                             // addressed
-                            (Some(_), true) => {
+                            (Some(_), true, _) => {
                                 unreachable!()
                             }
-                            (None, true) => {
+                            (None, true, _) => {
                                 let typ = ty.ident();
                                 let err_msg = format!(
                                     "conversion to `{}` for {} failed",
@@ -1613,7 +2479,23 @@ impl Generator {
                                     }
                                 })
                             }
-                            (None, false) => {
+                            // Array bodies accept slices as well as vectors.
+                            (None, false, Some(item)) => {
+                                Ok(quote! {
+                                    pub fn #param_name<V>(
+                                        mut self,
+                                        value: V,
+                                    ) -> Self
+                                        where V: Into<
+                                            std::borrow::Cow<'a, [#item]>,
+                                        >,
+                                    {
+                                        self.#param_name = Ok(value.into());
+                                        self
+                                    }
+                                })
+                            }
+                            (None, false, None) => {
                                 let typ = ty.ident();
                                 let err_msg = format!(
                                     "conversion to `{}` for {} failed",
@@ -1639,7 +2521,7 @@ impl Generator {
                             // a builder **from** the body type). We also offer
                             // a `body_map()` method that operates on the
                             // builder itself.
-                            (Some(builder_name), false) => {
+                            (Some(builder_name), false, _) => {
                                 assert_eq!(param.name, "body");
                                 let typ = ty.ident();
                                 let err_msg = format!(
@@ -1718,7 +2600,11 @@ impl Generator {
             success,
             error,
             body,
-        } = self.method_sig_body(method, quote! { #client_ident })?;
+        } = self.method_sig_body(
+            method,
+            quote! { #client_ident },
+            MethodVariant::Plain,
+        )?;
 
         let send_doc = format!(
             "Sends a `{}` request to `{}`",
@@ -2102,6 +2988,33 @@ impl Generator {
         impl_body
     }
 
+    /// The `x-max-request-size` extension declares the largest request body
+    /// the server accepts. It may be placed on the request body or on the
+    /// operation itself.
+    fn max_request_size(
+        operation: &openapiv3::Operation,
+        components: &Option<Components>,
+    ) -> Result<Option<u64>> {
+        let body_extensions = match &operation.request_body {
+            Some(body) => Some(&body.item(components)?.extensions),
+            None => None,
+        };
+        body_extensions
+            .into_iter()
+            .chain([&operation.extensions])
+            .find_map(|extensions| extensions.get("x-max-request-size"))
+            .map(|value| {
+                value.as_u64().ok_or_else(|| {
+                    Error::InvalidExtension(format!(
+                        "x-max-request-size must be a non-negative integer \
+                         in {:?}",
+                        operation.operation_id,
+                    ))
+                })
+            })
+            .transpose()
+    }
+
     fn get_body_param(
         &mut self,
         operation: &openapiv3::Operation,
@@ -2130,6 +3043,12 @@ impl Generator {
         })?;
 
         let content_type = BodyContentType::from_str(content_str)?;
+        if content_type == BodyContentType::Cbor && !self.settings.cbor {
+            return Err(Error::UnexpectedFormat(format!(
+                "unexpected content type: {} (see the cbor setting)",
+                content_str,
+            )));
+        }
 
         let typ = match content_type {
             BodyContentType::OctetStream => {
@@ -2205,7 +3124,9 @@ impl Generator {
                 }?;
                 OperationParameterType::RawBody
             }
-            BodyContentType::Json | BodyContentType::FormUrlencoded => {
+            BodyContentType::Json
+            | BodyContentType::FormUrlencoded
+            | BodyContentType::Cbor => {
                 // TODO it would be legal to have the encoding field set for
                 // application/x-www-form-urlencoded content, but I'm not sure
                 // how to interpret the values.
@@ -2236,6 +3157,30 @@ impl Generator {
     }
 }
 
+/// Recognizes the RFC 7807 media type for problem details bodies.
+fn is_problem_json(media_type: &str) -> bool {
+    let essence = media_type.split(';').next().unwrap_or_default().trim();
+    essence.eq_ignore_ascii_case("application/problem+json")
+}
+
+/// Recognizes the media types used for MessagePack bodies.
+fn is_msgpack(media_type: &str) -> bool {
+    let essence = media_type.split(';').next().unwrap_or_default().trim();
+    [
+        "application/msgpack",
+        "application/x-msgpack",
+        "application/vnd.msgpack",
+    ]
+    .iter()
+    .any(|msgpack| essence.eq_ignore_ascii_case(msgpack))
+}
+
+/// Recognizes the media type used for CBOR bodies.
+fn is_cbor(media_type: &str) -> bool {
+    let essence = media_type.split(';').next().unwrap_or_default().trim();
+    essence.eq_ignore_ascii_case("application/cbor")
+}
+
 fn make_doc_comment(method: &OperationMethod) -> String {
     let mut buf = String::new();
 
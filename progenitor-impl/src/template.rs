@@ -23,6 +23,17 @@ impl PathTemplate {
         &self,
         rename: HashMap<&String, &String>,
         client: TokenStream,
+    ) -> TokenStream {
+        self.compile_with(rename, &HashMap::new(), client)
+    }
+
+    /// Like `compile`, but with the parameters in `encoders` encoded by the
+    /// given expressions rather than as simple scalars.
+    pub fn compile_with(
+        &self,
+        rename: HashMap<&String, &String>,
+        encoders: &HashMap<&String, TokenStream>,
+        client: TokenStream,
     ) -> TokenStream {
         let mut fmt = String::new();
         fmt.push_str("{}");
@@ -35,6 +46,9 @@ impl PathTemplate {
 
         let components = self.components.iter().filter_map(|component| {
             if let Component::Parameter(n) = &component {
+                if let Some(encoder) = encoders.get(n) {
+                    return Some(encoder.clone());
+                }
                 let param = format_ident!(
                     "{}",
                     rename
@@ -0,0 +1,227 @@
+#[allow(unused_imports)]
+use progenitor_client::{encode_path, RequestBuilderExt};
+#[allow(unused_imports)]
+pub use progenitor_client::{ByteStream, Error, ResponseValue};
+#[allow(unused_imports)]
+use reqwest::header::{HeaderMap, HeaderValue};
+/// Types used as operation parameters and responses.
+#[allow(clippy::all)]
+pub mod types {
+    /// Error types.
+    pub mod error {
+        /// Error from a TryFrom or FromStr implementation.
+        pub struct ConversionError(std::borrow::Cow<'static, str>);
+        impl std::error::Error for ConversionError {}
+        impl std::fmt::Display for ConversionError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+                std::fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        impl std::fmt::Debug for ConversionError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+                std::fmt::Debug::fmt(&self.0, f)
+            }
+        }
+
+        impl From<&'static str> for ConversionError {
+            fn from(value: &'static str) -> Self {
+                Self(value.into())
+            }
+        }
+
+        impl From<String> for ConversionError {
+            fn from(value: String) -> Self {
+                Self(value.into())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+///Client for Blocking client test
+///
+///Minimal API for testing the blocking client
+///
+///Version: v1
+pub struct Client {
+    pub(crate) baseurl: String,
+    pub(crate) client: reqwest::Client,
+}
+
+impl Client {
+    /// Create a new client.
+    ///
+    /// `baseurl` is the base URL provided to the internal
+    /// `reqwest::Client`, and should include a scheme and hostname,
+    /// as well as port and a path stem if applicable.
+    pub fn new(baseurl: &str) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        let client = {
+            let dur = std::time::Duration::from_secs(15);
+            reqwest::ClientBuilder::new()
+                .connect_timeout(dur)
+                .timeout(dur)
+        };
+        #[cfg(target_arch = "wasm32")]
+        let client = reqwest::ClientBuilder::new();
+        Self::new_with_client(baseurl, client.build().unwrap())
+    }
+
+    /// Construct a new client with an existing `reqwest::Client`,
+    /// allowing more control over its configuration.
+    ///
+    /// `baseurl` is the base URL provided to the internal
+    /// `reqwest::Client`, and should include a scheme and hostname,
+    /// as well as port and a path stem if applicable.
+    pub fn new_with_client(baseurl: &str, client: reqwest::Client) -> Self {
+        Self {
+            baseurl: baseurl.to_string(),
+            client,
+        }
+    }
+
+    /// Get the base URL to which requests are made.
+    pub fn baseurl(&self) -> &String {
+        &self.baseurl
+    }
+
+    /// Get the internal `reqwest::Client` used to make requests.
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Get the version of this API.
+    ///
+    /// This string is pulled directly from the source OpenAPI
+    /// document and may be in any format the API selects.
+    pub fn api_version(&self) -> &'static str {
+        "v1"
+    }
+}
+
+#[allow(clippy::all)]
+impl Client {
+    ///Sends a `GET` request to `/widgets/{id}`
+    pub async fn get_widget<'a>(&'a self, id: &'a str) -> Result<ResponseValue<String>, Error<()>> {
+        let url = format!("{}/widgets/{}", self.baseurl, encode_path(&id.to_string()),);
+        #[allow(unused_mut)]
+        let mut request = self
+            .client
+            .get(url)
+            .header(
+                reqwest::header::ACCEPT,
+                reqwest::header::HeaderValue::from_static("application/json"),
+            )
+            .build()?;
+        let result = self.client.execute(request).await;
+        let response = result?;
+        match response.status().as_u16() {
+            200u16 => ResponseValue::from_response(response).await,
+            _ => Err(Error::UnexpectedResponse(response)),
+        }
+    }
+
+    ///Sends a `PUT` request to `/widgets/{id}`
+    pub async fn put_widget<'a>(
+        &'a self,
+        id: &'a str,
+        body: &'a str,
+    ) -> Result<ResponseValue<()>, Error<()>> {
+        let url = format!("{}/widgets/{}", self.baseurl, encode_path(&id.to_string()),);
+        #[allow(unused_mut)]
+        let mut request = self.client.put(url).cbor(&body)?.build()?;
+        let result = self.client.execute(request).await;
+        let response = result?;
+        match response.status().as_u16() {
+            204u16 => Ok(ResponseValue::empty(response)),
+            _ => Err(Error::UnexpectedResponse(response)),
+        }
+    }
+}
+
+/// Items consumers will typically use such as the Client.
+pub mod prelude {
+    #[allow(unused_imports)]
+    pub use super::Client;
+}
+
+/// Blocking client, for callers without an async runtime.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod blocking {
+    #[allow(unused_imports)]
+    use super::*;
+    /// Client whose operations block the calling thread.
+    #[derive(Clone, Debug)]
+    pub struct Client {
+        pub(crate) baseurl: String,
+        pub(crate) client: reqwest::blocking::Client,
+    }
+
+    impl Client {
+        /// Create a new client.
+        ///
+        /// `baseurl` is the base URL provided to the internal
+        /// `reqwest::blocking::Client`, and should include a
+        /// scheme and hostname, as well as port and a path stem if
+        /// applicable.
+        pub fn new(baseurl: &str) -> Self {
+            let dur = std::time::Duration::from_secs(15);
+            let client = reqwest::blocking::ClientBuilder::new()
+                .connect_timeout(dur)
+                .timeout(dur)
+                .build()
+                .unwrap();
+            Self::new_with_client(baseurl, client)
+        }
+
+        /// Construct a new client with an existing
+        /// `reqwest::blocking::Client`, allowing more control over
+        /// its configuration.
+        pub fn new_with_client(baseurl: &str, client: reqwest::blocking::Client) -> Self {
+            Self {
+                baseurl: baseurl.to_string(),
+                client,
+            }
+        }
+
+        /// Get the base URL to which requests are made.
+        pub fn baseurl(&self) -> &String {
+            &self.baseurl
+        }
+
+        /// Get the internal `reqwest::blocking::Client` used to
+        /// make requests.
+        pub fn client(&self) -> &reqwest::blocking::Client {
+            &self.client
+        }
+
+        /// Get the version of this API.
+        pub fn api_version(&self) -> &'static str {
+            "v1"
+        }
+    }
+
+    #[allow(clippy::all)]
+    impl Client {
+        ///Sends a `GET` request to `/widgets/{id}`
+        pub fn get_widget<'a>(&'a self, id: &'a str) -> Result<ResponseValue<String>, Error<()>> {
+            let url = format!("{}/widgets/{}", self.baseurl, encode_path(&id.to_string()),);
+            #[allow(unused_mut)]
+            let mut request = self
+                .client
+                .get(url)
+                .header(
+                    reqwest::header::ACCEPT,
+                    reqwest::header::HeaderValue::from_static("application/json"),
+                )
+                .build()?;
+            let result = self.client.execute(request);
+            let response = result?;
+            match response.status().as_u16() {
+                200u16 => ResponseValue::from_response_blocking(response, false),
+                _ => Err(Error::unexpected_blocking(response)),
+            }
+        }
+    }
+}
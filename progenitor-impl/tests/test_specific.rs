@@ -16,7 +16,9 @@ use progenitor_impl::{
 use schemars::JsonSchema;
 use serde::Deserialize;
 use std::{
+    fs::File,
     net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
     str::from_utf8,
     sync::{Arc, Mutex},
 };
@@ -34,6 +36,12 @@ fn generate_formatted(generator: &mut Generator, spec: &OpenAPI) -> String {
     .unwrap()
 }
 
+fn load_api(openapi_file: &str) -> OpenAPI {
+    let mut in_path = PathBuf::from("../sample_openapi");
+    in_path.push(openapi_file);
+    serde_json::from_reader(File::open(in_path).unwrap()).unwrap()
+}
+
 #[allow(dead_code)]
 #[derive(Deserialize, JsonSchema)]
 struct CursedPath {
@@ -376,3 +384,189 @@ async fn test_stream_pagination() {
 
     server.close().await.expect("failed to close server");
 }
+
+/// Test an operation whose success responses have bodies that differ by
+/// status.
+#[test]
+fn test_multiple_success_responses() {
+    let spec = load_api("multiple-success.json");
+
+    let mut generator = Generator::default();
+    let output = generate_formatted(&mut generator, &spec);
+    expectorate::assert_contents(
+        format!("tests/output/src/{}.rs", "test_multiple_success_responses"),
+        &output,
+    );
+
+    // The CLI can't output a success enum, which is reported rather than
+    // generating a CLI that panics.
+    let mut generator = Generator::default();
+    assert!(generator.cli(&spec, "batch").is_err());
+}
+
+/// Test that the cbor setting types CBOR request and response bodies.
+#[test]
+fn test_cbor() {
+    let spec = load_api("cbor.json");
+
+    let mut generator = Generator::default();
+    assert!(generator.generate_tokens(&spec).is_err());
+
+    let mut generator =
+        Generator::new(GenerationSettings::default().with_cbor(true));
+    let output = generate_formatted(&mut generator, &spec);
+    expectorate::assert_contents(
+        format!("tests/output/src/{}.rs", "test_cbor"),
+        &output,
+    );
+}
+
+/// Test that array request bodies are borrowed as slices.
+#[test]
+fn test_array_body_slice() {
+    let spec = load_api("array-body.json");
+
+    let mut generator = Generator::default();
+    let output = generate_formatted(&mut generator, &spec);
+    expectorate::assert_contents(
+        format!("tests/output/src/{}.rs", "test_array_body_slice_positional"),
+        &output,
+    );
+
+    let mut generator = Generator::new(
+        GenerationSettings::default().with_interface(InterfaceStyle::Builder),
+    );
+    let output = generate_formatted(&mut generator, &spec);
+    expectorate::assert_contents(
+        format!("tests/output/src/{}.rs", "test_array_body_slice_builder"),
+        &output,
+    );
+}
+
+/// Test that array and label-style path parameters are encoded by
+/// `encode_path_array`.
+#[test]
+fn test_path_array_params() {
+    let spec = load_api("path-array-params.json");
+
+    let mut generator = Generator::default();
+    let output = generate_formatted(&mut generator, &spec);
+    expectorate::assert_contents(
+        format!("tests/output/src/{}.rs", "test_path_array_params"),
+        &output,
+    );
+}
+
+/// Test that array query parameters are encoded by `encode_query`.
+#[test]
+fn test_query_array_params() {
+    let spec = load_api("query-array-params.json");
+
+    let mut generator = Generator::default();
+    let output = generate_formatted(&mut generator, &spec);
+    expectorate::assert_contents(
+        format!("tests/output/src/{}.rs", "test_query_array_params"),
+        &output,
+    );
+}
+
+/// Test that the blocking client mirrors the operations it supports.
+#[test]
+fn test_blocking_client() {
+    let spec = load_api("blocking.json");
+
+    let mut generator = Generator::new(
+        GenerationSettings::default()
+            .with_cbor(true)
+            .with_blocking(true),
+    );
+    let output = generate_formatted(&mut generator, &spec);
+    expectorate::assert_contents(
+        format!("tests/output/src/{}.rs", "test_blocking_client"),
+        &output,
+    );
+}
+
+/// Test that `lenient_content_type` selects the decoders that ignore the
+/// `Content-Type` of responses.
+#[test]
+fn test_lenient_content_type() {
+    let spec = load_api("lenient-content-type.json");
+
+    let mut generator = Generator::new(
+        GenerationSettings::default().with_lenient_content_type(true),
+    );
+    let output = generate_formatted(&mut generator, &spec);
+    expectorate::assert_contents(
+        format!("tests/output/src/{}.rs", "test_lenient_content_type"),
+        &output,
+    );
+
+    let mut generator = Generator::new(
+        GenerationSettings::default()
+            .with_lenient_content_type(true)
+            .with_debug_schema(true)
+            .with_blocking(true),
+    );
+    let output = generate_formatted(&mut generator, &spec);
+    expectorate::assert_contents(
+        format!("tests/output/src/{}.rs", "test_lenient_content_type_debug"),
+        &output,
+    );
+}
+
+/// Test that `Client::prewarm` is generated only on request, and that an
+/// operation of the same name is rejected then.
+#[test]
+fn test_prewarm() {
+    let mut spec = load_api("prewarm.json");
+
+    let mut generator =
+        Generator::new(GenerationSettings::default().with_prewarm(true));
+    let output = generate_formatted(&mut generator, &spec);
+    expectorate::assert_contents(
+        format!("tests/output/src/{}.rs", "test_prewarm"),
+        &output,
+    );
+
+    let openapiv3::ReferenceOr::Item(item) = &mut spec.paths.paths["/widgets"]
+    else {
+        unreachable!();
+    };
+    item.post.as_mut().unwrap().operation_id = Some("prewarm".to_string());
+
+    let mut generator =
+        Generator::new(GenerationSettings::default().with_prewarm(true));
+    assert!(generator.generate_tokens(&spec).is_err());
+    let mut generator = Generator::default();
+    assert!(generator.generate_tokens(&spec).is_ok());
+}
+
+/// Test that `GET` operations other than WebSocket upgrades get `HEAD`
+/// variants.
+#[test]
+fn test_head_variants() {
+    let spec = load_api("head-variants.json");
+
+    let mut generator =
+        Generator::new(GenerationSettings::default().with_head_variants(true));
+    let output = generate_formatted(&mut generator, &spec);
+    expectorate::assert_contents(
+        format!("tests/output/src/{}.rs", "test_head_variants"),
+        &output,
+    );
+}
+
+/// Test that `application/problem+json` error responses without a schema are
+/// decoded as `ProblemDetails`.
+#[test]
+fn test_problem_details() {
+    let spec = load_api("problem-details.json");
+
+    let mut generator = Generator::default();
+    let output = generate_formatted(&mut generator, &spec);
+    expectorate::assert_contents(
+        format!("tests/output/src/{}.rs", "test_problem_details"),
+        &output,
+    );
+}
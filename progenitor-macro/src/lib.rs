@@ -42,6 +42,20 @@ mod token_utils;
 ///     [ tags = ( Merged | Separate ), ]
 ///     [ pre_hook = closure::or::path::to::function, ]
 ///     [ post_hook = closure::or::path::to::function, ]
+///     [ custom_transport = ( true | false ), ]
+///     [ response_schemas = ( true | false ), ]
+///     [ operation_registry = ( true | false ), ]
+///     [ stream_items = ( true | false ), ]
+///     [ capture_unknown_fields = ( true | false ), ]
+///     [ debug_schema = ( true | false ), ]
+///     [ response_fixups = ( true | false ), ]
+///     [ msgpack = ( true | false ), ]
+///     [ cbor = ( true | false ), ]
+///     [ head_variants = ( true | false ), ]
+///     [ strict_decoding = ( true | false ), ]
+///     [ blocking = ( true | false ), ]
+///     [ lenient_content_type = ( true | false ), ]
+///     [ prewarm = ( true | false ), ]
 ///
 ///     [ derives = [ path::to::DeriveMacro ], ]
 ///
@@ -81,6 +95,75 @@ mod token_utils;
 /// `&Result<reqwest::Response, reqwest::Error>`. This allows clients to
 /// examine responses, for example to log them.
 ///
+/// The optional `custom_transport` (if true) routes requests through a
+/// `progenitor_client::Transport` held by the client, and adds a
+/// `Client::new_with_transport` constructor. This may be used, for example,
+/// to supply canned responses in tests. Note that with a custom transport the
+/// `post_hook` receives a `&Result<reqwest::Response,
+/// progenitor_client::TransportError>`. The methods that wrap the transport
+/// for every request, such as `Client::with_default_query`, are generated only
+/// with `custom_transport`.
+///
+/// The optional `response_schemas` (if true) generates a `response_schemas`
+/// module with the JSON Schema of each operation's success response; see
+/// `ResponseValue::json_schema_validate`.
+///
+/// The optional `operation_registry` (if true) adds a `Client::operations`
+/// method describing the ID, HTTP method, and path template of each
+/// operation.
+///
+/// The optional `stream_items` (if true) adds, for each operation that returns
+/// a JSON array, an `<operation>_items` method that streams the elements of
+/// the array as they arrive. This applies only to the `Positional` interface.
+///
+/// The optional `capture_unknown_fields` (if true) adds a flattened `extra`
+/// map, along with an `extra_fields` accessor, to structs generated from
+/// component schemas that don't say whether unknown fields are permitted, so
+/// that fields added by newer servers are retained.
+///
+/// The optional `debug_schema` (if true) reports the JSON request and
+/// response bodies of each operation to the hook set with
+/// `progenitor_client::set_debug_hook` when the `debug-schema` feature of
+/// `progenitor-client` is enabled.
+///
+/// The optional `response_fixups` (if true) adds a
+/// `Client::with_response_fixup` method that registers a function to adjust
+/// the JSON response bodies of an operation, identified by its operation ID,
+/// before they are deserialized.
+///
+/// The optional `msgpack` (if true) decodes responses documented only as
+/// `application/msgpack` into their types rather than producing them as a
+/// `ByteStream`; this requires the `msgpack` feature of `progenitor-client`.
+///
+/// The optional `cbor` (if true) likewise encodes `application/cbor` request
+/// bodies and decodes `application/cbor` responses as CBOR; this requires the
+/// `cbor` feature of `progenitor-client`.
+///
+/// The optional `head_variants` (if true) adds, for each `GET` operation
+/// without a request body, an `<operation>_head` method that sends a `HEAD`
+/// request instead, producing only the response headers (e.g. to check the
+/// size or ETag of a resource). WebSocket operations are skipped. This
+/// applies only to the `Positional` interface; `Builder` clients get no
+/// `HEAD` variants.
+///
+/// The optional `strict_decoding` (if true) adds a
+/// `Client::with_strict_decoding` method that makes the client reject JSON
+/// response bodies with fields that their types don't describe.
+///
+/// The optional `blocking` (if true) adds a `blocking::Client` whose methods
+/// match those of the `Positional` interface but block the calling thread.
+/// It requires the `blocking` feature of `progenitor` (or of
+/// `progenitor-client`), which enables that of `reqwest`. Operations that use
+/// WebSockets, MessagePack, or CBOR are omitted.
+///
+/// The optional `lenient_content_type` (if true) makes the client decode JSON
+/// response bodies whatever their `Content-Type`, rather than returning
+/// responses without a JSON media type as `Error::UnexpectedResponse`.
+///
+/// The optional `prewarm` (if true) adds a `Client::prewarm` method that
+/// opens a connection to the host of the base URL ahead of the first
+/// operation.
+///
 /// Additional options control type generation:
 /// - `derives`: optional array of derive macro paths; the derive macros to be
 ///   applied to all generated types
@@ -134,6 +217,34 @@ struct MacroSettings {
     pre_hook: Option<ParseWrapper<ClosureOrPath>>,
     pre_hook_async: Option<ParseWrapper<ClosureOrPath>>,
     post_hook: Option<ParseWrapper<ClosureOrPath>>,
+    #[serde(default)]
+    custom_transport: bool,
+    #[serde(default)]
+    response_schemas: bool,
+    #[serde(default)]
+    operation_registry: bool,
+    #[serde(default)]
+    stream_items: bool,
+    #[serde(default)]
+    capture_unknown_fields: bool,
+    #[serde(default)]
+    debug_schema: bool,
+    #[serde(default)]
+    response_fixups: bool,
+    #[serde(default)]
+    msgpack: bool,
+    #[serde(default)]
+    cbor: bool,
+    #[serde(default)]
+    head_variants: bool,
+    #[serde(default)]
+    strict_decoding: bool,
+    #[serde(default)]
+    blocking: bool,
+    #[serde(default)]
+    lenient_content_type: bool,
+    #[serde(default)]
+    prewarm: bool,
 
     #[serde(default)]
     derives: Vec<ParseWrapper<syn::Path>>,
@@ -304,6 +415,20 @@ fn do_generate_api(item: TokenStream) -> Result<TokenStream, syn::Error> {
             pre_hook,
             pre_hook_async,
             post_hook,
+            custom_transport,
+            response_schemas,
+            operation_registry,
+            stream_items,
+            capture_unknown_fields,
+            debug_schema,
+            response_fixups,
+            msgpack,
+            cbor,
+            head_variants,
+            strict_decoding,
+            blocking,
+            lenient_content_type,
+            prewarm,
             unknown_crates,
             crates,
             derives,
@@ -324,6 +449,20 @@ fn do_generate_api(item: TokenStream) -> Result<TokenStream, syn::Error> {
         });
         post_hook
             .map(|post_hook| settings.with_post_hook(post_hook.into_inner().0));
+        settings.with_custom_transport(custom_transport);
+        settings.with_response_schemas(response_schemas);
+        settings.with_operation_registry(operation_registry);
+        settings.with_stream_items(stream_items);
+        settings.with_capture_unknown_fields(capture_unknown_fields);
+        settings.with_debug_schema(debug_schema);
+        settings.with_response_fixups(response_fixups);
+        settings.with_msgpack(msgpack);
+        settings.with_cbor(cbor);
+        settings.with_head_variants(head_variants);
+        settings.with_strict_decoding(strict_decoding);
+        settings.with_blocking(blocking);
+        settings.with_lenient_content_type(lenient_content_type);
+        settings.with_prewarm(prewarm);
 
         settings.with_unknown_crates(unknown_crates);
         crates.into_iter().for_each(
@@ -51,3 +51,287 @@ mod builder_tagged {
             .send();
     }
 }
+
+mod custom_transport {
+    progenitor::generate_api!(
+        spec = "../sample_openapi/keeper.json",
+        custom_transport = true,
+    );
+
+    fn _ignore() {
+        let transport = progenitor_client::TransportFn::new(|_request| {
+            Ok(http::Response::new("{}").into())
+        });
+        let client = Client::new_with_transport("", transport)
+            .with_propagated_headers(
+                &[reqwest::header::HeaderName::from_static("x-tenant-id")],
+                || None,
+            )
+            .with_default_query(&[("api-version", "2024-01-01")])
+            .with_max_concurrent_requests(8)
+            .with_retry_budget(0.2, 10)
+            .with_close_on_cancel()
+            .with_retained_bodies()
+            .with_recent_requests(16, 1024);
+        let _ = client.recent_requests();
+        let _ = client.enrol(
+            "auth token",
+            &types::EnrolBody {
+                host: "".to_string(),
+                key: "".to_string(),
+            },
+        );
+    }
+
+    #[test]
+    fn test_retry_budget() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Counts the attempts of a `ping` that always fails with a 503.
+        let attempts = |budget: Option<(f64, u32)>| {
+            let sent = std::sync::Arc::new(AtomicUsize::new(0));
+            let transport = progenitor_client::TransportFn::new({
+                let sent = sent.clone();
+                move |_request: reqwest::Request| {
+                    sent.fetch_add(1, Ordering::SeqCst);
+                    Ok(http::Response::builder()
+                        .status(503)
+                        .body("unavailable")
+                        .unwrap()
+                        .into())
+                }
+            });
+            let mut client =
+                Client::new_with_transport("http://keeper.example", transport);
+            if let Some((ratio, min_per_sec)) = budget {
+                client = client.with_retry_budget(ratio, min_per_sec);
+            }
+            let factory = client.body_factory(|| reqwest::Body::from(""));
+            let result = futures::executor::block_on(
+                factory.retry(5, |_body| client.ping("auth token")),
+            );
+            assert!(result.unwrap_err().is_retryable());
+            sent.load(Ordering::SeqCst)
+        };
+
+        // Without a budget, only the attempts allowed limit retries.
+        assert_eq!(attempts(None), 5);
+        // With no credit, the first failure is final.
+        assert_eq!(attempts(Some((0.0, 0))), 1);
+        assert_eq!(attempts(Some((0.0, 2))), 3);
+    }
+}
+
+mod prewarm {
+    progenitor::generate_api!(
+        spec = "../sample_openapi/keeper.json",
+        custom_transport = true,
+        prewarm = true,
+    );
+
+    #[test]
+    fn test_prewarm() {
+        let sent = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let transport = progenitor_client::TransportFn::new({
+            let sent = sent.clone();
+            move |request: reqwest::Request| {
+                sent.lock().unwrap().push((
+                    request.method().clone(),
+                    request.url().to_string(),
+                ));
+                Ok(http::Response::builder()
+                    .status(404)
+                    .body("not here")
+                    .unwrap()
+                    .into())
+            }
+        });
+        let client =
+            Client::new_with_transport("http://keeper.example/", transport);
+
+        // The status of the response doesn't matter.
+        futures::executor::block_on(client.prewarm()).unwrap();
+        assert_eq!(
+            *sent.lock().unwrap(),
+            [(reqwest::Method::HEAD, "http://keeper.example/".to_string())],
+        );
+    }
+}
+
+mod capture_unknown_fields {
+    progenitor::generate_api!(
+        spec = "../sample_openapi/keeper.json",
+        capture_unknown_fields = true,
+    );
+
+    #[test]
+    fn test_extra_fields() {
+        let json = serde_json::json!({
+            "msg": "started",
+            "stream": "stdout",
+            "time": "2024-01-01T00:00:00Z",
+            "level": "info",
+        });
+        let record =
+            serde_json::from_value::<types::OutputRecord>(json.clone())
+                .unwrap();
+        assert_eq!(record.msg, "started");
+        assert_eq!(
+            record.extra_fields().get("level"),
+            Some(&serde_json::json!("info")),
+        );
+        assert_eq!(record.extra_fields().len(), 1);
+
+        // The unknown fields are kept when the value is sent back.
+        assert_eq!(serde_json::to_value(&record).unwrap(), json);
+    }
+}
+
+mod debug_schema {
+    progenitor::generate_api!(
+        spec = "../sample_openapi/keeper.json",
+        debug_schema = true,
+    );
+
+    fn _ignore() {
+        let _ = Client::new("").enrol(
+            "auth token",
+            &types::EnrolBody {
+                host: "".to_string(),
+                key: "".to_string(),
+            },
+        );
+    }
+}
+
+mod response_fixups {
+    progenitor::generate_api!(
+        spec = "../sample_openapi/keeper.json",
+        debug_schema = true,
+        response_fixups = true,
+    );
+
+    fn _ignore() {
+        let client = Client::new("").with_response_fixup(
+            "global_jobs",
+            |value: &mut serde_json::Value| {
+                value.as_object_mut().map(|o| o.remove("summary"));
+            },
+        );
+        let _ = client.global_jobs("auth token");
+    }
+}
+
+mod strict_decoding {
+    progenitor::generate_api!(
+        spec = "../sample_openapi/keeper.json",
+        response_fixups = true,
+        strict_decoding = true,
+    );
+
+    fn _ignore() {
+        let client = Client::new("").with_strict_decoding(true);
+        let _ = client.global_jobs("auth token");
+    }
+}
+
+#[cfg(feature = "blocking")]
+mod blocking {
+    progenitor::generate_api!(
+        spec = "../sample_openapi/keeper.json",
+        blocking = true,
+    );
+
+    fn _ignore() -> Option<usize> {
+        let response =
+            blocking::Client::new("").global_jobs("auth token").ok()?;
+        Some(response.into_inner().summary.len())
+    }
+}
+
+mod lenient_content_type {
+    progenitor::generate_api!(
+        spec = "../sample_openapi/keeper.json",
+        debug_schema = true,
+        lenient_content_type = true,
+        blocking = true,
+    );
+
+    async fn _ignore() {
+        let _ = Client::new("").global_jobs("auth token").await;
+        let _ = blocking::Client::new("").global_jobs("auth token");
+    }
+}
+
+mod head_variants {
+    progenitor::generate_api!(
+        spec = "../sample_openapi/keeper.json",
+        custom_transport = true,
+        head_variants = true,
+    );
+
+    #[test]
+    fn test_head_variants() {
+        let sent = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let transport = progenitor_client::TransportFn::new({
+            let sent = sent.clone();
+            move |request: reqwest::Request| {
+                sent.lock().unwrap().push((
+                    request.method().clone(),
+                    request.url().to_string(),
+                    request.headers()["authorization"].clone(),
+                ));
+                Ok(http::Response::builder()
+                    .status(200)
+                    .header("etag", "\"v1\"")
+                    .body("")
+                    .unwrap()
+                    .into())
+            }
+        });
+        let client =
+            Client::new_with_transport("http://keeper.example", transport);
+
+        let response =
+            futures::executor::block_on(client.global_jobs_head("auth token"))
+                .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["etag"], "\"v1\"");
+        assert_eq!(
+            *sent.lock().unwrap(),
+            [(
+                reqwest::Method::HEAD,
+                "http://keeper.example/global/jobs".to_string(),
+                http::HeaderValue::from_static("auth token"),
+            )],
+        );
+    }
+}
+
+mod operation_registry {
+    progenitor::generate_api!(
+        spec = "../sample_openapi/keeper.json",
+        operation_registry = true,
+    );
+
+    #[test]
+    fn test_operations() {
+        let operation =
+            |operation_id, method, path| progenitor_client::OperationInfo {
+                operation_id,
+                method,
+                path,
+            };
+        assert_eq!(
+            Client::new("http://keeper.example").operations(),
+            [
+                operation("enrol", "POST", "/enrol"),
+                operation("global_jobs", "GET", "/global/jobs"),
+                operation("ping", "GET", "/ping"),
+                operation("report_finish", "POST", "/report/finish"),
+                operation("report_output", "POST", "/report/output"),
+                operation("report_start", "POST", "/report/start"),
+            ],
+        );
+    }
+}
@@ -0,0 +1,38 @@
+mod positional {
+    progenitor::generate_api!("../sample_openapi/multiple-success.json");
+
+    async fn _ignore() -> Option<usize> {
+        let response = Client::new("").update_items().await.ok()?;
+        match response.into_inner() {
+            types::UpdateItemsSuccess::Ok(s) => Some(s.len()),
+            types::UpdateItemsSuccess::NoContent => None,
+            types::UpdateItemsSuccess::MultiStatus(ids) => Some(ids.len()),
+        }
+    }
+}
+
+mod builder_untagged {
+    progenitor::generate_api!(
+        spec = "../sample_openapi/multiple-success.json",
+        interface = Builder,
+        tags = Merged,
+    );
+
+    async fn _ignore() -> Option<types::UpdateItemsSuccess> {
+        let response = Client::new("").update_items().send().await.ok()?;
+        Some(response.into_inner())
+    }
+}
+
+#[cfg(feature = "blocking")]
+mod blocking {
+    progenitor::generate_api!(
+        spec = "../sample_openapi/multiple-success.json",
+        blocking = true,
+    );
+
+    fn _ignore() -> Option<types::UpdateItemsSuccess> {
+        let response = blocking::Client::new("").update_items().ok()?;
+        Some(response.into_inner())
+    }
+}